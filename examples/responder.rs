@@ -1,10 +1,16 @@
 use futures::{SinkExt, StreamExt};
+use showdown::bot::{Command, CommandRouter};
 use showdown::message::{Kind, UpdateUser};
 use showdown::{Result, SendMessage, Stream};
 use std::env;
 
+async fn yay(command: Command) -> Option<String> {
+    Some(format!("YAY {}!", command.user().name().to_uppercase()))
+}
+
 async fn start(login: String, password: String) -> Result<()> {
     let mut stream = Stream::connect("showdown").await?;
+    let mut router = CommandRouter::new(".").command("yay", yay);
     while let Some(message) = stream.next().await {
         let message = message?;
         match message.kind() {
@@ -17,16 +23,11 @@ async fn start(login: String, password: String) -> Result<()> {
                     .send(SendMessage::global_command("join bot dev"))
                     .await?
             }
-            Kind::Chat(text) if text.message() == ".yay" => {
-                stream
-                    .send(SendMessage::chat_message(
-                        message.room(),
-                        format_args!("YAY {}!", text.user().to_uppercase()),
-                    ))
-                    .await?;
-            }
             _ => {}
         }
+        if let Some(reply) = router.handle(&message).await {
+            stream.send(reply).await?;
+        }
     }
     Ok(())
 }
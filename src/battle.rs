@@ -0,0 +1,7 @@
+//! Battle helpers.
+
+mod choice;
+mod state;
+
+pub use self::choice::{BattleChoice, ChoiceError, MoveChoice};
+pub use self::state::{BattleState, Boosts, PokemonState, SideState};
@@ -0,0 +1,465 @@
+use crate::message::BattleRequest;
+use std::fmt::{self, Display, Formatter};
+use thiserror::Error;
+
+/// Battle decision, sent with
+/// [`SendMessage::battle_choice`](crate::SendMessage::battle_choice).
+///
+/// Actions are given in order of active slots, so in doubles the first
+/// action is for the left Pokémon.
+///
+/// # Examples
+///
+/// ```
+/// use showdown::battle::{BattleChoice, MoveChoice};
+///
+/// let choice = BattleChoice::new()
+///     .choose_move(MoveChoice::new(2).target(1).mega())
+///     .switch(3);
+/// assert_eq!(choice.to_string(), "move 2 1 mega, switch 3");
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct BattleChoice(ChoiceKind);
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum ChoiceKind {
+    Actions(Vec<Action>),
+    TeamPreview(Vec<u8>),
+    Default,
+    Undo,
+}
+
+impl Default for ChoiceKind {
+    fn default() -> Self {
+        ChoiceKind::Actions(Vec::new())
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Action {
+    Move(MoveChoice),
+    Switch(u8),
+    Pass,
+    Shift,
+}
+
+impl BattleChoice {
+    /// Creates an empty choice, actions for active slots can be added to it.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Chooses a team order during team preview.
+    ///
+    /// Positions are 1-based indexes in the team.
+    pub fn team_preview(order: impl IntoIterator<Item = u8>) -> Self {
+        Self(ChoiceKind::TeamPreview(order.into_iter().collect()))
+    }
+
+    /// Lets the server pick the decision.
+    pub fn default_choice() -> Self {
+        Self(ChoiceKind::Default)
+    }
+
+    /// Cancels the previous decision.
+    pub fn undo() -> Self {
+        Self(ChoiceKind::Undo)
+    }
+
+    pub fn choose_move(self, choice: MoveChoice) -> Self {
+        self.action(Action::Move(choice))
+    }
+
+    /// Switches to a Pokémon at a given 1-based team position.
+    pub fn switch(self, position: u8) -> Self {
+        self.action(Action::Switch(position))
+    }
+
+    pub fn pass(self) -> Self {
+        self.action(Action::Pass)
+    }
+
+    /// Moves to the center in triples.
+    pub fn shift(self) -> Self {
+        self.action(Action::Shift)
+    }
+
+    fn action(mut self, action: Action) -> Self {
+        match &mut self.0 {
+            ChoiceKind::Actions(actions) => actions.push(action),
+            kind => *kind = ChoiceKind::Actions(vec![action]),
+        }
+        self
+    }
+
+    pub(crate) fn is_undo(&self) -> bool {
+        self.0 == ChoiceKind::Undo
+    }
+
+    /// Checks whether the choice is valid for a request.
+    pub fn validate(&self, request: &BattleRequest<'_>) -> Result<(), ChoiceError> {
+        let actions = match &self.0 {
+            ChoiceKind::Default | ChoiceKind::Undo => return Ok(()),
+            _ if request.wait => return Err(ChoiceError::NotRequested),
+            ChoiceKind::TeamPreview(order) => return validate_team_preview(order, request),
+            _ if request.team_preview => return Err(ChoiceError::TeamPreviewExpected),
+            ChoiceKind::Actions(actions) => actions,
+        };
+        let slots = if request.force_switch.is_empty() {
+            request.active.len()
+        } else {
+            request.force_switch.len()
+        };
+        if actions.len() != slots {
+            return Err(ChoiceError::WrongActionCount {
+                expected: slots,
+                found: actions.len(),
+            });
+        }
+        let mut switched = Vec::new();
+        for (slot, action) in actions.iter().enumerate() {
+            match *action {
+                Action::Move(choice) => {
+                    if !request.force_switch.is_empty() {
+                        return Err(ChoiceError::SwitchExpected(slot));
+                    }
+                    validate_move(choice, slot, request)?;
+                }
+                Action::Switch(position) => {
+                    if request.force_switch.get(slot) == Some(&false) {
+                        return Err(ChoiceError::PassExpected(slot));
+                    }
+                    if request.active.get(slot).is_some_and(|a| a.trapped) {
+                        return Err(ChoiceError::Trapped(slot));
+                    }
+                    let pokemon = usize::from(position)
+                        .checked_sub(1)
+                        .and_then(|i| request.side.pokemon.get(i))
+                        .ok_or(ChoiceError::InvalidPosition(position))?;
+                    let fainted = pokemon.hp_status().is_some_and(|hp| hp.is_fainted());
+                    if pokemon.active || fainted || switched.contains(&position) {
+                        return Err(ChoiceError::CannotSwitch(position));
+                    }
+                    switched.push(position);
+                }
+                Action::Pass | Action::Shift => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+fn validate_team_preview(order: &[u8], request: &BattleRequest<'_>) -> Result<(), ChoiceError> {
+    if !request.team_preview {
+        return Err(ChoiceError::NotTeamPreview);
+    }
+    let team_size = request.side.pokemon.len();
+    let max_team_size = request.max_team_size.map_or(team_size, usize::from);
+    if order.is_empty() || order.len() > max_team_size {
+        return Err(ChoiceError::WrongActionCount {
+            expected: max_team_size,
+            found: order.len(),
+        });
+    }
+    for (i, &position) in order.iter().enumerate() {
+        if position == 0 || usize::from(position) > team_size || order[..i].contains(&position) {
+            return Err(ChoiceError::InvalidPosition(position));
+        }
+    }
+    Ok(())
+}
+
+fn validate_move(
+    choice: MoveChoice,
+    slot: usize,
+    request: &BattleRequest<'_>,
+) -> Result<(), ChoiceError> {
+    let active = &request.active[slot];
+    let index = usize::from(choice.slot).checked_sub(1);
+    let move_slot = index
+        .and_then(|i| active.moves.get(i))
+        .ok_or(ChoiceError::InvalidMove(choice.slot))?;
+    if move_slot.disabled {
+        return Err(ChoiceError::MoveDisabled(choice.slot));
+    }
+    if usize::from(choice.target.unsigned_abs()) > request.active.len().max(2) {
+        return Err(ChoiceError::InvalidTarget(choice.target));
+    }
+    let allowed = match choice.gimmick {
+        None => true,
+        Some(Gimmick::Mega) => active.can_mega_evo,
+        Some(Gimmick::UltraBurst) => active.can_ultra_burst,
+        Some(Gimmick::ZMove) => active
+            .can_z_move
+            .as_ref()
+            .and_then(|z| z.get(index?)?.as_ref())
+            .is_some(),
+        Some(Gimmick::Dynamax) => active.can_dynamax,
+        Some(Gimmick::Terastallize) => active.can_terastallize.is_some(),
+    };
+    if allowed {
+        Ok(())
+    } else {
+        Err(ChoiceError::GimmickUnavailable(slot))
+    }
+}
+
+impl Display for BattleChoice {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.0 {
+            ChoiceKind::Actions(actions) => {
+                for (i, action) in actions.iter().enumerate() {
+                    if i != 0 {
+                        f.write_str(", ")?;
+                    }
+                    match action {
+                        Action::Move(choice) => write!(f, "{}", choice)?,
+                        Action::Switch(position) => write!(f, "switch {}", position)?,
+                        Action::Pass => f.write_str("pass")?,
+                        Action::Shift => f.write_str("shift")?,
+                    }
+                }
+                Ok(())
+            }
+            ChoiceKind::TeamPreview(order) => {
+                f.write_str("team ")?;
+                for (i, position) in order.iter().enumerate() {
+                    if i != 0 {
+                        f.write_str(",")?;
+                    }
+                    write!(f, "{}", position)?;
+                }
+                Ok(())
+            }
+            ChoiceKind::Default => f.write_str("default"),
+            ChoiceKind::Undo => f.write_str("undo"),
+        }
+    }
+}
+
+/// Move used by an active Pokémon.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct MoveChoice {
+    slot: u8,
+    target: i8,
+    gimmick: Option<Gimmick>,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Gimmick {
+    Mega,
+    UltraBurst,
+    ZMove,
+    Dynamax,
+    Terastallize,
+}
+
+impl MoveChoice {
+    /// Uses a move at a given 1-based move slot.
+    pub fn new(slot: u8) -> Self {
+        Self {
+            slot,
+            target: 0,
+            gimmick: None,
+        }
+    }
+
+    /// Sets a move target in doubles and triples.
+    ///
+    /// Foes are positive (`1` is the foe on the left), allies are negative.
+    pub fn target(mut self, target: i8) -> Self {
+        self.target = target;
+        self
+    }
+
+    pub fn mega(self) -> Self {
+        self.gimmick(Gimmick::Mega)
+    }
+
+    pub fn ultra_burst(self) -> Self {
+        self.gimmick(Gimmick::UltraBurst)
+    }
+
+    pub fn zmove(self) -> Self {
+        self.gimmick(Gimmick::ZMove)
+    }
+
+    pub fn dynamax(self) -> Self {
+        self.gimmick(Gimmick::Dynamax)
+    }
+
+    pub fn terastallize(self) -> Self {
+        self.gimmick(Gimmick::Terastallize)
+    }
+
+    fn gimmick(mut self, gimmick: Gimmick) -> Self {
+        self.gimmick = Some(gimmick);
+        self
+    }
+}
+
+impl Display for MoveChoice {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "move {}", self.slot)?;
+        if self.target != 0 {
+            write!(f, " {}", self.target)?;
+        }
+        match self.gimmick {
+            None => Ok(()),
+            Some(Gimmick::Mega) => f.write_str(" mega"),
+            Some(Gimmick::UltraBurst) => f.write_str(" ultra"),
+            Some(Gimmick::ZMove) => f.write_str(" zmove"),
+            Some(Gimmick::Dynamax) => f.write_str(" dynamax"),
+            Some(Gimmick::Terastallize) => f.write_str(" terastallize"),
+        }
+    }
+}
+
+/// Reason why a [`BattleChoice`] doesn't match a request.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Error)]
+#[non_exhaustive]
+pub enum ChoiceError {
+    #[error("No decision was requested")]
+    NotRequested,
+    #[error("Team preview order was expected")]
+    TeamPreviewExpected,
+    #[error("Team preview order was given outside of team preview")]
+    NotTeamPreview,
+    #[error("Expected {expected} actions, found {found}")]
+    WrongActionCount { expected: usize, found: usize },
+    #[error("Slot {0} must switch")]
+    SwitchExpected(usize),
+    #[error("Slot {0} must pass")]
+    PassExpected(usize),
+    #[error("Slot {0} is trapped")]
+    Trapped(usize),
+    #[error("There is no Pokémon at position {0}")]
+    InvalidPosition(u8),
+    #[error("Cannot switch to Pokémon at position {0}")]
+    CannotSwitch(u8),
+    #[error("There is no move at slot {0}")]
+    InvalidMove(u8),
+    #[error("Move at slot {0} is disabled")]
+    MoveDisabled(u8),
+    #[error("Invalid move target {0}")]
+    InvalidTarget(i8),
+    #[error("Slot {0} cannot use the requested gimmick")]
+    GimmickUnavailable(usize),
+}
+
+#[cfg(test)]
+mod test {
+    use super::{BattleChoice, ChoiceError, MoveChoice};
+    use crate::message::BattleRequest;
+    use crate::{RoomId, SendMessage};
+
+    const DOUBLES_REQUEST: &str = r#"{"active":[{"moves":[{"move":"Protect","id":"protect","pp":16,"maxpp":16,"target":"self","disabled":false},{"move":"Flamethrower","id":"flamethrower","pp":24,"maxpp":24,"target":"normal","disabled":true}],"canMegaEvo":true},{"moves":[{"move":"Earthquake","id":"earthquake","pp":16,"maxpp":16,"target":"allAdjacent","disabled":false}],"trapped":true}],"side":{"name":"xfix","id":"p1","pokemon":[{"ident":"p1: Charizard","details":"Charizard, M","condition":"100/100","active":true,"stats":{"atk":1,"def":1,"spa":1,"spd":1,"spe":1},"moves":["protect","flamethrower"],"baseAbility":"blaze","item":"charizarditex","pokeball":"pokeball"},{"ident":"p1: Garchomp","details":"Garchomp, F","condition":"100/100","active":true,"stats":{"atk":1,"def":1,"spa":1,"spd":1,"spe":1},"moves":["earthquake"],"baseAbility":"roughskin","item":"","pokeball":"pokeball"},{"ident":"p1: Pikachu","details":"Pikachu, F","condition":"0 fnt","active":false,"stats":{"atk":1,"def":1,"spa":1,"spd":1,"spe":1},"moves":["thunderbolt"],"baseAbility":"static","item":"","pokeball":"pokeball"},{"ident":"p1: Ditto","details":"Ditto","condition":"100/100","active":false,"stats":{"atk":1,"def":1,"spa":1,"spd":1,"spe":1},"moves":["transform"],"baseAbility":"limber","item":"","pokeball":"pokeball"}]},"rqid":4}"#;
+
+    fn request() -> BattleRequest<'static> {
+        serde_json::from_str(DOUBLES_REQUEST).unwrap()
+    }
+
+    #[test]
+    fn valid_doubles_choice() {
+        let choice = BattleChoice::new()
+            .choose_move(MoveChoice::new(1).mega())
+            .choose_move(MoveChoice::new(1));
+        assert_eq!(choice.validate(&request()), Ok(()));
+        assert_eq!(choice.to_string(), "move 1 mega, move 1");
+    }
+
+    #[test]
+    fn invalid_doubles_choices() {
+        let request = request();
+        let validate = |choice: BattleChoice| choice.validate(&request);
+        assert_eq!(
+            validate(BattleChoice::new().choose_move(MoveChoice::new(1))),
+            Err(ChoiceError::WrongActionCount {
+                expected: 2,
+                found: 1
+            }),
+        );
+        assert_eq!(
+            validate(
+                BattleChoice::new()
+                    .choose_move(MoveChoice::new(2))
+                    .choose_move(MoveChoice::new(1))
+            ),
+            Err(ChoiceError::MoveDisabled(2)),
+        );
+        assert_eq!(
+            validate(BattleChoice::new().switch(4).switch(4)),
+            Err(ChoiceError::Trapped(1)),
+        );
+        assert_eq!(
+            validate(BattleChoice::new().switch(3).pass()),
+            Err(ChoiceError::CannotSwitch(3)),
+        );
+        assert_eq!(
+            validate(
+                BattleChoice::new()
+                    .choose_move(MoveChoice::new(1))
+                    .choose_move(MoveChoice::new(1).dynamax())
+            ),
+            Err(ChoiceError::GimmickUnavailable(1)),
+        );
+    }
+
+    #[test]
+    fn send_message_includes_rqid() {
+        let room = RoomId("battle-gen9doublesou-1");
+        let choice = BattleChoice::new().pass().switch(4);
+        assert!(SendMessage::battle_choice(room, &choice, &request()).is_err());
+        let choice = BattleChoice::new()
+            .choose_move(MoveChoice::new(1))
+            .choose_move(MoveChoice::new(1));
+        assert_eq!(
+            SendMessage::battle_choice(room, &choice, &request()).unwrap(),
+            SendMessage("battle-gen9doublesou-1|/choose move 1, move 1|4".into()),
+        );
+        assert_eq!(
+            SendMessage::battle_choice(room, &BattleChoice::undo(), &request()).unwrap(),
+            SendMessage("battle-gen9doublesou-1|/undo".into()),
+        );
+    }
+
+    #[test]
+    fn team_preview() {
+        let choice = BattleChoice::team_preview([2, 1, 4, 3]);
+        assert_eq!(choice.to_string(), "team 2,1,4,3");
+        assert_eq!(
+            choice.validate(&request()),
+            Err(ChoiceError::NotTeamPreview)
+        );
+    }
+
+    #[test]
+    fn vgc_team_preview() {
+        let pokemon = (1..=6)
+            .map(|i| {
+                format!(
+                    r#"{{"ident":"p1: Pokemon{0}","details":"Pokemon{0}, L50","condition":"100/100","active":false,"stats":{{"atk":1,"def":1,"spa":1,"spd":1,"spe":1}},"moves":["protect"],"baseAbility":"pressure","item":"","pokeball":"pokeball"}}"#,
+                    i,
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+        let json = format!(
+            r#"{{"teamPreview":true,"maxChosenTeamSize":4,"side":{{"name":"xfix","id":"p1","pokemon":[{}]}},"rqid":1}}"#,
+            pokemon,
+        );
+        let request: BattleRequest<'_> = serde_json::from_str(&json).unwrap();
+        let validate =
+            |order: &[u8]| BattleChoice::team_preview(order.iter().copied()).validate(&request);
+        assert_eq!(validate(&[6, 2, 3, 1]), Ok(()));
+        assert_eq!(validate(&[5, 4]), Ok(()));
+        assert_eq!(
+            validate(&[1, 2, 3, 4, 5]),
+            Err(ChoiceError::WrongActionCount {
+                expected: 4,
+                found: 5
+            })
+        );
+        assert_eq!(validate(&[1, 7]), Err(ChoiceError::InvalidPosition(7)));
+    }
+}
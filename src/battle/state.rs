@@ -0,0 +1,818 @@
+use crate::message::{
+    BattleEvent, Boost, Details, GameType, Gender, HpStatus, Kind, Message, Pokemon, Side, Stat,
+    Status, Switch, Tags,
+};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Battle model built from battle room messages.
+///
+/// Messages of a single battle room need to be passed to
+/// [`update`](Self::update) in order they were received.
+///
+/// # Examples
+///
+#[cfg_attr(feature = "__tls", doc = "```no_run")]
+#[cfg_attr(not(feature = "__tls"), doc = "```compile_fail")]
+/// use futures::StreamExt;
+/// use showdown::battle::BattleState;
+/// use showdown::message::Side;
+/// use showdown::{Result, Stream};
+///
+/// #[tokio::main]
+/// async fn main() -> Result<()> {
+///     let mut stream = Stream::connect("showdown").await?;
+///     let mut state = BattleState::new();
+///     while let Some(message) = stream.next().await {
+///         let message = message?;
+///         if message.room().0.starts_with("battle-") {
+///             state.update(&message);
+///             if let Some(pokemon) = state.active(Side::P2, 0) {
+///                 println!("Opponent has {} active", pokemon.species);
+///             }
+///         }
+///     }
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct BattleState {
+    sides: [SideState; 4],
+    turn: u32,
+    gen: Option<u8>,
+    tier: Option<String>,
+    game_type: Option<GameType>,
+    weather: Option<String>,
+    field: BTreeSet<String>,
+    ended: bool,
+    winner: Option<String>,
+}
+
+impl BattleState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Updates the state with every battle line of a message.
+    pub fn update(&mut self, message: &Message) {
+        for kind in message.kinds() {
+            if let Kind::Battle(event) = kind {
+                self.handle(&event);
+            }
+        }
+    }
+
+    /// Updates the state with a single battle event.
+    pub fn handle(&mut self, event: &BattleEvent<'_>) {
+        match *event {
+            BattleEvent::Player {
+                side, ref username, ..
+            } => {
+                self.sides[side as usize].player =
+                    Some(username.to_string()).filter(|u| !u.is_empty());
+            }
+            BattleEvent::TeamSize { side, size } => {
+                self.sides[side as usize].team_size = Some(size)
+            }
+            BattleEvent::GameType(game_type) => self.game_type = Some(game_type),
+            BattleEvent::Gen(gen) => self.gen = Some(gen),
+            BattleEvent::Tier(ref tier) => self.tier = Some(tier.to_string()),
+            BattleEvent::ClearPoke => {
+                for side in &mut self.sides {
+                    side.team.retain(|pokemon| pokemon.name.is_some());
+                }
+            }
+            BattleEvent::Poke {
+                side, ref details, ..
+            } => {
+                let mut pokemon = PokemonState::new(None, details);
+                pokemon.hp = 100;
+                pokemon.max_hp = 100;
+                self.sides[side as usize].team.push(pokemon);
+            }
+            BattleEvent::Turn(turn) => self.turn = turn,
+            BattleEvent::Win(ref winner) => {
+                self.ended = true;
+                self.winner = Some(winner.to_string());
+            }
+            BattleEvent::Tie => self.ended = true,
+            BattleEvent::Move {
+                ref source,
+                ref name,
+                ref tags,
+                ..
+            } if !tags.contains("from") => self.reveal_move(source, name),
+            BattleEvent::Cant {
+                ref pokemon,
+                move_name: Some(ref name),
+                ..
+            } => self.reveal_move(pokemon, name),
+            BattleEvent::Switch(ref switch) | BattleEvent::Drag(ref switch) => {
+                self.switch_in(switch, true);
+            }
+            BattleEvent::Replace(ref switch) => self.switch_in(switch, false),
+            BattleEvent::DetailsChange(Switch {
+                ref pokemon,
+                ref details,
+                hp_status,
+            }) => {
+                if let Some(state) = self.pokemon_mut(pokemon) {
+                    state.set_details(details);
+                    if let Some(hp_status) = hp_status {
+                        state.set_hp_status(hp_status);
+                    }
+                }
+            }
+            BattleEvent::FormeChange {
+                ref pokemon,
+                ref species,
+                hp_status,
+                ..
+            } => {
+                if let Some(state) = self.pokemon_mut(pokemon) {
+                    state.forme = Some(species.to_string());
+                    if let Some(hp_status) = hp_status {
+                        state.set_hp_status(hp_status);
+                    }
+                }
+            }
+            BattleEvent::Swap {
+                ref pokemon,
+                position,
+                ..
+            } => {
+                if let Some(from) = pokemon.slot {
+                    let (from, to) = (usize::from(from), usize::from(position));
+                    let side = &mut self.sides[pokemon.side as usize];
+                    side.ensure_slot(from.max(to));
+                    side.active.swap(from, to);
+                }
+            }
+            BattleEvent::Faint(ref pokemon) => {
+                if let Some(state) = self.pokemon_mut(pokemon) {
+                    state.hp = 0;
+                    state.status = Some(Status::Fainted);
+                }
+            }
+            BattleEvent::Damage {
+                ref pokemon,
+                hp_status,
+                ref tags,
+            }
+            | BattleEvent::Heal {
+                ref pokemon,
+                hp_status,
+                ref tags,
+            }
+            | BattleEvent::SetHp {
+                ref pokemon,
+                hp_status,
+                ref tags,
+            } => {
+                if let Some(state) = self.pokemon_mut(pokemon) {
+                    state.set_hp_status(hp_status);
+                }
+                self.reveal_from(pokemon, tags);
+            }
+            BattleEvent::Status {
+                ref pokemon,
+                status,
+                ref tags,
+            } => {
+                if let Some(state) = self.pokemon_mut(pokemon) {
+                    state.status = Some(status);
+                }
+                self.reveal_from(pokemon, tags);
+            }
+            BattleEvent::CureStatus {
+                ref pokemon,
+                ref tags,
+                ..
+            } => {
+                if let Some(state) = self.pokemon_mut(pokemon) {
+                    state.status = None;
+                }
+                self.reveal_from(pokemon, tags);
+            }
+            BattleEvent::CureTeam { ref pokemon, .. } => {
+                for state in &mut self.sides[pokemon.side as usize].team {
+                    if !state.is_fainted() {
+                        state.status = None;
+                    }
+                }
+            }
+            BattleEvent::Boost(Boost {
+                ref pokemon,
+                stat,
+                amount,
+                ref tags,
+            }) => {
+                self.modify_boosts(pokemon, |boosts| boosts.add(stat, amount));
+                self.reveal_from(pokemon, tags);
+            }
+            BattleEvent::Unboost(Boost {
+                ref pokemon,
+                stat,
+                amount,
+                ref tags,
+            }) => {
+                self.modify_boosts(pokemon, |boosts| boosts.add(stat, amount.saturating_neg()));
+                self.reveal_from(pokemon, tags);
+            }
+            BattleEvent::SetBoost(Boost {
+                ref pokemon,
+                stat,
+                amount,
+                ..
+            }) => self.modify_boosts(pokemon, |boosts| boosts.set(stat, amount)),
+            BattleEvent::SwapBoost {
+                ref source,
+                ref target,
+                ref stats,
+                ..
+            } => {
+                if let (Some(a), Some(b)) = (self.boosts(source), self.boosts(target)) {
+                    let stats: Vec<Stat> = match stats {
+                        Some(stats) => stats
+                            .split(", ")
+                            .filter_map(|stat| stat.parse().ok())
+                            .collect(),
+                        None => STATS.to_vec(),
+                    };
+                    let (mut a_new, mut b_new) = (a, b);
+                    for stat in stats {
+                        a_new.set(stat, b.get(stat));
+                        b_new.set(stat, a.get(stat));
+                    }
+                    self.modify_boosts(source, |boosts| *boosts = a_new);
+                    self.modify_boosts(target, |boosts| *boosts = b_new);
+                }
+            }
+            BattleEvent::InvertBoost { ref pokemon, .. } => self.modify_boosts(pokemon, |boosts| {
+                for stage in &mut boosts.0 {
+                    *stage = -*stage;
+                }
+            }),
+            BattleEvent::ClearBoost { ref pokemon, .. } => {
+                self.modify_boosts(pokemon, |boosts| *boosts = Boosts::default())
+            }
+            BattleEvent::ClearAllBoost { .. } => {
+                for side in &mut self.sides {
+                    for state in &mut side.team {
+                        state.boosts = Boosts::default();
+                    }
+                }
+            }
+            BattleEvent::ClearPositiveBoost { ref target, .. } => {
+                self.modify_boosts(target, |boosts| {
+                    for stage in &mut boosts.0 {
+                        *stage = (*stage).min(0);
+                    }
+                })
+            }
+            BattleEvent::ClearNegativeBoost { ref pokemon, .. } => {
+                self.modify_boosts(pokemon, |boosts| {
+                    for stage in &mut boosts.0 {
+                        *stage = (*stage).max(0);
+                    }
+                })
+            }
+            BattleEvent::CopyBoost {
+                ref source,
+                ref target,
+                ..
+            } => {
+                if let Some(boosts) = self.boosts(target) {
+                    self.modify_boosts(source, |b| *b = boosts);
+                }
+            }
+            BattleEvent::Weather { ref weather, .. } => {
+                self.weather = Some(weather.to_string()).filter(|w| w != "none");
+            }
+            BattleEvent::FieldStart { ref condition, .. } => {
+                self.field.insert(effect_name(condition).into());
+            }
+            BattleEvent::FieldEnd { ref condition, .. } => {
+                self.field.remove(effect_name(condition));
+            }
+            BattleEvent::SideStart {
+                side,
+                ref condition,
+                ..
+            } => {
+                *self.sides[side as usize]
+                    .conditions
+                    .entry(effect_name(condition).into())
+                    .or_insert(0) += 1;
+            }
+            BattleEvent::SideEnd {
+                side,
+                ref condition,
+                ..
+            } => {
+                self.sides[side as usize]
+                    .conditions
+                    .remove(effect_name(condition));
+            }
+            BattleEvent::SwapSideConditions => {
+                let (p1, p2) = self.sides.split_at_mut(1);
+                std::mem::swap(&mut p1[0].conditions, &mut p2[0].conditions);
+            }
+            BattleEvent::StartEffect {
+                ref pokemon,
+                ref effect,
+                ref tags,
+            } => {
+                if let Some(state) = self.pokemon_mut(pokemon) {
+                    state.volatiles.insert(effect_name(effect).into());
+                }
+                self.reveal_from(pokemon, tags);
+            }
+            BattleEvent::EndEffect {
+                ref pokemon,
+                ref effect,
+                ref tags,
+            } => {
+                if let Some(state) = self.pokemon_mut(pokemon) {
+                    state.volatiles.remove(effect_name(effect));
+                }
+                self.reveal_from(pokemon, tags);
+            }
+            BattleEvent::Item {
+                ref pokemon,
+                ref item,
+                ..
+            } => {
+                if let Some(state) = self.pokemon_mut(pokemon) {
+                    state.item = Some(item.to_string());
+                }
+            }
+            BattleEvent::EndItem {
+                ref pokemon,
+                ref item,
+                ..
+            } => {
+                if let Some(state) = self.pokemon_mut(pokemon) {
+                    state.item = None;
+                    state.last_item = Some(item.to_string());
+                }
+            }
+            BattleEvent::Ability {
+                ref pokemon,
+                ref ability,
+                ..
+            } => {
+                if let Some(state) = self.pokemon_mut(pokemon) {
+                    state.ability = Some(ability.to_string());
+                }
+            }
+            BattleEvent::Transform {
+                ref pokemon,
+                ref target,
+                ..
+            } => {
+                let species = self.pokemon(target).map(|t| t.species.clone());
+                if let Some(state) = self.pokemon_mut(pokemon) {
+                    state.volatiles.insert("Transform".into());
+                    state.forme = species;
+                }
+            }
+            BattleEvent::Mega {
+                ref pokemon,
+                mega_stone: Some(ref mega_stone),
+            } => {
+                if let Some(state) = self.pokemon_mut(pokemon) {
+                    state.item = Some(mega_stone.to_string());
+                }
+            }
+            BattleEvent::Terastallize {
+                ref pokemon,
+                ref tera_type,
+            } => {
+                if let Some(state) = self.pokemon_mut(pokemon) {
+                    state.terastallized = Some(tera_type.to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn switch_in(&mut self, switch: &Switch<'_>, reset: bool) {
+        let Switch {
+            pokemon,
+            details,
+            hp_status,
+        } = switch;
+        let slot = match pokemon.slot {
+            Some(slot) => usize::from(slot),
+            None => return,
+        };
+        let side = &mut self.sides[pokemon.side as usize];
+        side.ensure_slot(slot);
+        if let Some(previous) = side.active[slot] {
+            if reset {
+                side.team[previous].clear_volatiles();
+            }
+        }
+        let index = match side.find(&pokemon.name) {
+            Some(index) => index,
+            None => match side.find_unnamed(&details.species) {
+                Some(index) => {
+                    side.team[index].name = Some(pokemon.name.to_string());
+                    index
+                }
+                None => {
+                    side.team
+                        .push(PokemonState::new(Some(&pokemon.name), details));
+                    side.team.len() - 1
+                }
+            },
+        };
+        let state = &mut side.team[index];
+        state.set_details(details);
+        if let Some(hp_status) = hp_status {
+            state.set_hp_status(*hp_status);
+        }
+        side.active[slot] = Some(index);
+    }
+
+    fn reveal_move(&mut self, pokemon: &Pokemon<'_>, name: &str) {
+        if let Some(state) = self.pokemon_mut(pokemon) {
+            if !state.moves.iter().any(|m| m == name) {
+                state.moves.push(name.into());
+            }
+        }
+    }
+
+    /// Reveals items and abilities from `[from]` tags.
+    fn reveal_from(&mut self, pokemon: &Pokemon<'_>, tags: &Tags<'_>) {
+        let from = match tags.get("from") {
+            Some(from) => from,
+            None => return,
+        };
+        let of = tags.get("of").and_then(Pokemon::parse);
+        let owner = of.as_ref().unwrap_or(pokemon);
+        if let Some(item) = from.strip_prefix("item: ") {
+            if let Some(state) = self.pokemon_mut(owner) {
+                state.item = Some(item.into());
+            }
+        } else if let Some(ability) = from.strip_prefix("ability: ") {
+            if let Some(state) = self.pokemon_mut(owner) {
+                state.ability = Some(ability.into());
+            }
+        }
+    }
+
+    fn boosts(&self, pokemon: &Pokemon<'_>) -> Option<Boosts> {
+        self.pokemon(pokemon).map(|state| state.boosts)
+    }
+
+    fn modify_boosts(&mut self, pokemon: &Pokemon<'_>, f: impl FnOnce(&mut Boosts)) {
+        if let Some(state) = self.pokemon_mut(pokemon) {
+            f(&mut state.boosts);
+        }
+    }
+
+    /// Finds a Pokémon by its identifier.
+    pub fn pokemon(&self, pokemon: &Pokemon<'_>) -> Option<&PokemonState> {
+        let side = &self.sides[pokemon.side as usize];
+        side.find(&pokemon.name).map(|index| &side.team[index])
+    }
+
+    fn pokemon_mut(&mut self, pokemon: &Pokemon<'_>) -> Option<&mut PokemonState> {
+        let side = &mut self.sides[pokemon.side as usize];
+        side.find(&pokemon.name)
+            .map(move |index| &mut side.team[index])
+    }
+
+    /// Gets an active Pokémon at a given 0-based slot.
+    pub fn active(&self, side: Side, slot: usize) -> Option<&PokemonState> {
+        let side = &self.sides[side as usize];
+        let index = (*side.active.get(slot)?)?;
+        Some(&side.team[index])
+    }
+
+    /// Gets all Pokémon of a side that were revealed so far, either by
+    /// team preview or by switching in.
+    pub fn revealed_team(&self, side: Side) -> &[PokemonState] {
+        &self.sides[side as usize].team
+    }
+
+    pub fn side(&self, side: Side) -> &SideState {
+        &self.sides[side as usize]
+    }
+
+    pub fn turn(&self) -> u32 {
+        self.turn
+    }
+
+    pub fn gen(&self) -> Option<u8> {
+        self.gen
+    }
+
+    pub fn tier(&self) -> Option<&str> {
+        self.tier.as_deref()
+    }
+
+    pub fn game_type(&self) -> Option<GameType> {
+        self.game_type
+    }
+
+    pub fn weather(&self) -> Option<&str> {
+        self.weather.as_deref()
+    }
+
+    /// Gets active terrain, like `Electric Terrain`.
+    pub fn terrain(&self) -> Option<&str> {
+        self.field_conditions()
+            .find(|condition| condition.ends_with(" Terrain"))
+    }
+
+    /// Gets field conditions, like `Trick Room` or terrains.
+    pub fn field_conditions(&self) -> impl Iterator<Item = &str> {
+        self.field.iter().map(String::as_str)
+    }
+
+    pub fn ended(&self) -> bool {
+        self.ended
+    }
+
+    /// Gets the winner username, `None` when the battle is ongoing or tied.
+    pub fn winner(&self) -> Option<&str> {
+        self.winner.as_deref()
+    }
+}
+
+/// Removes effect type prefix, like `move: ` in `move: Stealth Rock`.
+fn effect_name(effect: &str) -> &str {
+    effect.split_once(": ").map_or(effect, |(_, name)| name)
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct SideState {
+    pub player: Option<String>,
+    pub team_size: Option<u8>,
+    team: Vec<PokemonState>,
+    active: Vec<Option<usize>>,
+    conditions: BTreeMap<String, u8>,
+}
+
+impl SideState {
+    fn find(&self, name: &str) -> Option<usize> {
+        self.team
+            .iter()
+            .position(|pokemon| pokemon.name.as_deref() == Some(name))
+    }
+
+    /// Finds a Pokémon revealed by team preview, where nicknames and
+    /// formes (shown as `Urshifu-*`) are hidden.
+    fn find_unnamed(&self, species: &str) -> Option<usize> {
+        self.team.iter().position(|pokemon| {
+            pokemon.name.is_none()
+                && match pokemon.species.strip_suffix("-*") {
+                    Some(base) => species.starts_with(base),
+                    None => pokemon.species == species,
+                }
+        })
+    }
+
+    fn ensure_slot(&mut self, slot: usize) {
+        if self.active.len() <= slot {
+            self.active.resize(slot + 1, None);
+        }
+    }
+
+    /// Gets side conditions, like `Stealth Rock`, with their layer count.
+    pub fn conditions(&self) -> impl Iterator<Item = (&str, u8)> {
+        self.conditions
+            .iter()
+            .map(|(condition, &layers)| (condition.as_str(), layers))
+    }
+}
+
+/// Known information about a Pokémon.
+#[derive(Clone, Debug)]
+pub struct PokemonState {
+    /// Nickname, `None` when only seen in team preview.
+    pub name: Option<String>,
+    pub species: String,
+    /// Temporary forme, like from `|-formechange|` or Transform.
+    pub forme: Option<String>,
+    pub level: u8,
+    pub gender: Option<Gender>,
+    pub shiny: bool,
+    /// Type the Pokémon terastallized into.
+    pub terastallized: Option<String>,
+    /// Current HP, percentage for the opponent's Pokémon.
+    pub hp: u32,
+    pub max_hp: u32,
+    pub status: Option<Status>,
+    pub boosts: Boosts,
+    /// Volatile effects, like `Substitute` or `confusion`.
+    pub volatiles: BTreeSet<String>,
+    /// Moves revealed so far.
+    pub moves: Vec<String>,
+    pub item: Option<String>,
+    /// Item that was consumed or removed.
+    pub last_item: Option<String>,
+    pub ability: Option<String>,
+}
+
+impl PokemonState {
+    fn new(name: Option<&str>, details: &Details<'_>) -> Self {
+        let mut pokemon = Self {
+            name: name.map(String::from),
+            species: String::new(),
+            forme: None,
+            level: 100,
+            gender: None,
+            shiny: false,
+            terastallized: None,
+            hp: 0,
+            max_hp: 0,
+            status: None,
+            boosts: Boosts::default(),
+            volatiles: BTreeSet::new(),
+            moves: Vec::new(),
+            item: None,
+            last_item: None,
+            ability: None,
+        };
+        pokemon.set_details(details);
+        pokemon
+    }
+
+    fn set_details(&mut self, details: &Details<'_>) {
+        self.species = details.species.to_string();
+        self.level = details.level;
+        self.gender = details.gender;
+        self.shiny = details.shiny;
+        if let Some(tera_type) = &details.tera_type {
+            self.terastallized = Some(tera_type.to_string());
+        }
+    }
+
+    fn set_hp_status(&mut self, hp_status: HpStatus) {
+        self.hp = hp_status.hp;
+        if let Some(max_hp) = hp_status.max_hp {
+            self.max_hp = max_hp;
+        }
+        self.status = hp_status.status;
+    }
+
+    fn clear_volatiles(&mut self) {
+        self.boosts = Boosts::default();
+        self.volatiles.clear();
+        self.forme = None;
+    }
+
+    pub fn is_fainted(&self) -> bool {
+        self.status == Some(Status::Fainted)
+    }
+}
+
+/// Stat stages, from -6 to 6.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct Boosts([i8; 7]);
+
+impl Boosts {
+    pub fn get(&self, stat: Stat) -> i8 {
+        self.0[stat as usize]
+    }
+
+    fn set(&mut self, stat: Stat, stage: i8) {
+        self.0[stat as usize] = stage.clamp(-6, 6);
+    }
+
+    fn add(&mut self, stat: Stat, amount: i8) {
+        self.set(stat, self.get(stat).saturating_add(amount));
+    }
+}
+
+const STATS: [Stat; 7] = [
+    Stat::Attack,
+    Stat::Defense,
+    Stat::SpecialAttack,
+    Stat::SpecialDefense,
+    Stat::Speed,
+    Stat::Accuracy,
+    Stat::Evasion,
+];
+
+#[cfg(test)]
+mod test {
+    use super::BattleState;
+    use crate::message::{Message, Side, Stat, Status};
+
+    fn update(state: &mut BattleState, raw: &str) {
+        state.update(&Message {
+            raw: format!(">battle-gen9ou-1\n{}", raw),
+        });
+    }
+
+    #[test]
+    fn tracks_battle() {
+        let mut state = BattleState::new();
+        update(
+            &mut state,
+            "|init|battle
+|title|xfix vs. Someone
+|player|p1|xfix|1|
+|player|p2|Someone|2|
+|teamsize|p1|2
+|teamsize|p2|2
+|gametype|singles
+|gen|9
+|tier|[Gen 9] OU
+|clearpoke
+|poke|p2|Urshifu-*, L50, F|
+|poke|p2|Clefable, F|
+|teampreview
+|
+|start
+|switch|p1a: Sparky|Pikachu, L50, M|100/100
+|switch|p2a: Fist|Urshifu-Rapid-Strike, L50, F|100/100
+|turn|1",
+        );
+        assert_eq!(state.turn(), 1);
+        assert_eq!(state.gen(), Some(9));
+        assert_eq!(state.side(Side::P2).player.as_deref(), Some("Someone"));
+        let opponent = state.active(Side::P2, 0).unwrap();
+        assert_eq!(opponent.name.as_deref(), Some("Fist"));
+        assert_eq!(opponent.species, "Urshifu-Rapid-Strike");
+        assert_eq!(state.revealed_team(Side::P2).len(), 2);
+
+        update(
+            &mut state,
+            "|
+|t:|1634571729
+|move|p2a: Fist|Close Combat|p1a: Sparky
+|-damage|p1a: Sparky|20/100
+|-unboost|p2a: Fist|def|1
+|-unboost|p2a: Fist|spd|1
+|move|p1a: Sparky|Thunderbolt|p2a: Fist
+|-damage|p2a: Fist|45/100 par
+|-damage|p2a: Fist|35/100 par|[from] item: Life Orb
+|-weather|RainDance
+|-fieldstart|move: Electric Terrain
+|-sidestart|p1: xfix|move: Spikes
+|-sidestart|p1: xfix|move: Spikes
+|
+|upkeep
+|turn|2",
+        );
+        let opponent = state.active(Side::P2, 0).unwrap();
+        assert_eq!(opponent.hp, 35);
+        assert_eq!(opponent.status, Some(Status::Paralysis));
+        assert_eq!(opponent.boosts.get(Stat::Defense), -1);
+        assert_eq!(opponent.item.as_deref(), Some("Life Orb"));
+        assert_eq!(opponent.moves, ["Close Combat"]);
+        assert_eq!(state.active(Side::P1, 0).unwrap().hp, 20);
+        assert_eq!(state.weather(), Some("RainDance"));
+        assert_eq!(state.terrain(), Some("Electric Terrain"));
+        assert_eq!(
+            state.side(Side::P1).conditions().collect::<Vec<_>>(),
+            [("Spikes", 2)],
+        );
+
+        update(
+            &mut state,
+            "|
+|switch|p2a: Clefable|Clefable, F|100/100
+|-weather|none
+|faint|p1a: Sparky
+|win|Someone",
+        );
+        assert_eq!(state.active(Side::P2, 0).unwrap().species, "Clefable");
+        let urshifu = &state.revealed_team(Side::P2)[0];
+        assert_eq!(urshifu.boosts.get(Stat::Defense), 0);
+        assert!(state.revealed_team(Side::P1)[0].is_fainted());
+        assert_eq!(state.weather(), None);
+        assert!(state.ended());
+        assert_eq!(state.winner(), Some("Someone"));
+    }
+
+    #[test]
+    fn tracks_terastallization() {
+        let mut state = BattleState::new();
+        update(
+            &mut state,
+            "|switch|p1a: Sparky|Pikachu, L50, M|100/100
+|switch|p2a: Fist|Urshifu-Rapid-Strike, L50, F|100/100
+|turn|1",
+        );
+        assert_eq!(state.active(Side::P1, 0).unwrap().terastallized, None);
+        update(
+            &mut state,
+            "|
+|-terastallize|p1a: Sparky|Electric
+|detailschange|p2a: Fist|Urshifu-Rapid-Strike, L50, F, tera:Water
+|-unboost|p2a: Fist|atk|-128
+|turn|2",
+        );
+        let pikachu = state.active(Side::P1, 0).unwrap();
+        assert_eq!(pikachu.terastallized.as_deref(), Some("Electric"));
+        let urshifu = state.active(Side::P2, 0).unwrap();
+        assert_eq!(urshifu.terastallized.as_deref(), Some("Water"));
+        assert_eq!(urshifu.boosts.get(Stat::Attack), 6);
+    }
+}
@@ -0,0 +1,395 @@
+//! Chat bot command routing.
+
+use crate::cooldown::Cooldowns;
+use crate::message::{Kind, Message, Rank, UpdateUser, User};
+use crate::{to_id, RoomId, SendMessage};
+use futures_util::future::BoxFuture;
+use std::collections::BTreeMap;
+use std::fmt::{self, Formatter};
+use std::future::Future;
+
+type Handler = Box<dyn Fn(Command) -> BoxFuture<'static, Option<String>> + Send + Sync>;
+
+/// Dispatches chat and private messages starting with a prefix to
+/// command handlers.
+///
+/// Handlers are async functions receiving a [`Command`] and returning an
+/// optional reply. Replies to chat commands are sent to the same room,
+/// and replies to private messages are sent as private messages.
+///
+/// Replies are always sent as text, a leading `/` or `!` is doubled so
+/// that the server doesn't run a reply echoing user input as a command.
+///
+/// # Examples
+///
+#[cfg_attr(feature = "__tls", doc = "```no_run")]
+#[cfg_attr(not(feature = "__tls"), doc = "```compile_fail")]
+/// use futures::{SinkExt, StreamExt};
+/// use showdown::bot::{Command, CommandRouter};
+/// use showdown::{Result, Stream};
+///
+/// async fn yay(command: Command) -> Option<String> {
+///     Some(format!("YAY {}!", command.user().name().to_uppercase()))
+/// }
+///
+/// #[tokio::main]
+/// async fn main() -> Result<()> {
+///     let mut stream = Stream::connect("showdown").await?;
+///     let mut router = CommandRouter::new(".").command("yay", yay);
+///     while let Some(message) = stream.next().await {
+///         if let Some(reply) = router.handle(&message?).await {
+///             stream.send(reply).await?;
+///         }
+///     }
+///     Ok(())
+/// }
+/// ```
+pub struct CommandRouter {
+    prefix: String,
+    commands: BTreeMap<String, (Rank, Handler)>,
+    userid: Option<String>,
+    cooldowns: Option<Cooldowns>,
+}
+
+impl CommandRouter {
+    /// Creates a router for commands starting with `prefix`, like `.`.
+    pub fn new(prefix: impl Into<String>) -> Self {
+        Self {
+            prefix: prefix.into(),
+            commands: BTreeMap::new(),
+            userid: None,
+            cooldowns: None,
+        }
+    }
+
+    /// Limits how often commands can be used.
+    ///
+    /// Commands used on cooldown aren't run, and get a cooldown reply
+    /// instead if it was configured.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use showdown::bot::CommandRouter;
+    /// use showdown::cooldown::Cooldowns;
+    /// use std::time::Duration;
+    ///
+    /// let router = CommandRouter::new(".")
+    ///     .command("ping", |_| async { Some("pong".to_string()) })
+    ///     .cooldowns(Cooldowns::new(Duration::from_secs(5)).reply("Wait a bit."));
+    /// ```
+    pub fn cooldowns(mut self, cooldowns: Cooldowns) -> Self {
+        self.cooldowns = Some(cooldowns);
+        self
+    }
+
+    /// Registers a command handler.
+    ///
+    /// Command names are compared by their ID, so `.Roll Dice` runs a
+    /// command registered as `rolldice`. A handler registered with the
+    /// same name replaces the previous one.
+    pub fn command<F, Fut>(self, name: &str, handler: F) -> Self
+    where
+        F: Fn(Command) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Option<String>> + Send + 'static,
+    {
+        self.restricted_command(name, Rank::Locked, handler)
+    }
+
+    /// Registers a command handler usable only by users with at least
+    /// `rank`.
+    ///
+    /// The rank is read from the message, so for chat commands it's the
+    /// rank in the room the command was used in, and for private messages
+    /// it's the global rank. Commands used by users without the rank are
+    /// ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use showdown::bot::CommandRouter;
+    /// use showdown::message::Rank;
+    ///
+    /// let router = CommandRouter::new(".").restricted_command(
+    ///     "say",
+    ///     Rank::Driver,
+    ///     |command| async move { Some(command.raw_args().to_string()) },
+    /// );
+    /// ```
+    pub fn restricted_command<F, Fut>(mut self, name: &str, rank: Rank, handler: F) -> Self
+    where
+        F: Fn(Command) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Option<String>> + Send + 'static,
+    {
+        self.commands.insert(
+            to_id(name),
+            (rank, Box::new(move |command| Box::pin(handler(command)))),
+        );
+        self
+    }
+
+    /// Parses a command from a message without running it.
+    ///
+    /// Commands the user doesn't have a rank for are ignored.
+    /// Messages sent by the bot itself are ignored too, the bot's name is
+    /// learned from `updateuser` messages passed to
+    /// [`handle`](Self::handle).
+    pub fn parse(&self, message: &Message) -> Option<Command> {
+        let (room, user, text) = match message.kind() {
+            Kind::Chat(chat) => (
+                Some(message.room().0.to_string()),
+                chat.user().into_owned(),
+                chat.message().into_owned(),
+            ),
+            Kind::Private(private) => (None, private.from, private.message.into_owned()),
+            _ => return None,
+        };
+        if self.userid.as_deref() == Some(&user.id()) {
+            return None;
+        }
+        let text = text.strip_prefix(&*self.prefix)?;
+        let (name, args) = text.split_once(' ').unwrap_or((text, ""));
+        let name = to_id(name);
+        let (rank, _) = self.commands.get(&name)?;
+        if !user.rank().has(*rank) {
+            return None;
+        }
+        Some(Command {
+            room,
+            user: user.into_owned(),
+            name,
+            args: args.trim().into(),
+        })
+    }
+
+    /// Runs a command in a message and returns a reply.
+    pub async fn handle(&mut self, message: &Message) -> Option<SendMessage> {
+        if let Kind::UpdateUser(UpdateUser {
+            username,
+            named: true,
+            ..
+        }) = message.kind()
+        {
+            self.userid = Some(to_id(&username));
+            return None;
+        }
+        let command = self.parse(message)?;
+        if let Some(cooldowns) = &mut self.cooldowns {
+            let room = command.room();
+            if let Err(cooldown) = cooldowns.check(room, command.user.name(), &command.name) {
+                return cooldown.into_reply();
+            }
+        }
+        let room = command.room.clone();
+        let user = command.user.id();
+        let reply = escape(&(self.commands[&command.name].1)(command).await?);
+        Some(match room {
+            Some(room) => SendMessage::chat_message(RoomId(&room), reply),
+            None => SendMessage::private_message(&user, reply),
+        })
+    }
+}
+
+/// Doubles a leading command token, making the server show it as text.
+fn escape(reply: &str) -> String {
+    match reply.chars().next() {
+        Some(token @ ('/' | '!')) => format!("{}{}", token, reply),
+        _ => reply.into(),
+    }
+}
+
+impl fmt::Debug for CommandRouter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CommandRouter")
+            .field("prefix", &self.prefix)
+            .field("commands", &self.commands.keys().collect::<Vec<_>>())
+            .field("userid", &self.userid)
+            .field("cooldowns", &self.cooldowns)
+            .finish()
+    }
+}
+
+/// Command invocation passed to a handler.
+#[derive(Clone, Debug)]
+pub struct Command {
+    room: Option<String>,
+    user: User<'static>,
+    name: String,
+    args: String,
+}
+
+impl Command {
+    /// Gets a room the command was used in, `None` for private messages.
+    pub fn room(&self) -> Option<RoomId<'_>> {
+        self.room.as_deref().map(RoomId)
+    }
+
+    pub fn is_private(&self) -> bool {
+        self.room.is_none()
+    }
+
+    pub fn user(&self) -> &User<'static> {
+        &self.user
+    }
+
+    /// Gets a rank of the user, in the room for chat commands.
+    pub fn rank(&self) -> Rank {
+        self.user.rank()
+    }
+
+    /// Gets a command name, normalized to an ID.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Gets the text after a command name.
+    pub fn raw_args(&self) -> &str {
+        &self.args
+    }
+
+    /// Iterates over comma separated arguments, with whitespace trimmed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use showdown::bot::Command;
+    ///
+    /// fn target(command: &Command) -> Option<&str> {
+    ///     command.args().next()
+    /// }
+    /// ```
+    pub fn args(&self) -> impl Iterator<Item = &str> {
+        self.args
+            .split(',')
+            .map(str::trim)
+            .filter(|arg| !arg.is_empty())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Command, CommandRouter};
+    use crate::cooldown::Cooldowns;
+    use crate::message::{Message, Rank};
+    use crate::{RoomId, SendMessage};
+    use std::time::Duration;
+
+    fn message(raw: &str) -> Message {
+        Message { raw: raw.into() }
+    }
+
+    async fn add(command: Command) -> Option<String> {
+        let sum: i64 = command
+            .args()
+            .map(|arg| arg.parse::<i64>().ok())
+            .sum::<Option<_>>()?;
+        Some(format!("{}: {}", command.user().name(), sum))
+    }
+
+    fn router() -> CommandRouter {
+        CommandRouter::new(".")
+            .command("add", add)
+            .command("ping", |_| async { Some("pong".to_string()) })
+            .restricted_command("say", Rank::Driver, |command| async move {
+                Some(command.raw_args().to_string())
+            })
+    }
+
+    #[tokio::test]
+    async fn replies_in_room() {
+        let mut router = router();
+        assert_eq!(
+            router
+                .handle(&message(">botdev\n|c:|1|+xfix|.ADD 1, 2,3"))
+                .await,
+            Some(SendMessage::chat_message(RoomId("botdev"), "xfix: 6")),
+        );
+        assert_eq!(
+            router.handle(&message(">botdev\n|c:|1|+xfix|.add a")).await,
+            None,
+        );
+    }
+
+    #[tokio::test]
+    async fn replies_privately() {
+        let mut router = router();
+        assert_eq!(
+            router.handle(&message("|pm| Some One| Bot|.ping")).await,
+            Some(SendMessage::private_message("someone", "pong")),
+        );
+    }
+
+    #[tokio::test]
+    async fn checks_room_rank() {
+        let mut router = router();
+        assert_eq!(
+            router
+                .handle(&message(">botdev\n|c:|1|+xfix|.say hi"))
+                .await,
+            None,
+        );
+        assert_eq!(
+            router
+                .handle(&message(">botdev\n|c:|1|@xfix|.say hi"))
+                .await,
+            Some(SendMessage::chat_message(RoomId("botdev"), "hi")),
+        );
+        assert_eq!(
+            router.handle(&message("|pm| xfix| Bot|.say hi")).await,
+            None
+        );
+        assert_eq!(
+            router.handle(&message("|pm|&xfix| Bot|.say hi")).await,
+            Some(SendMessage::private_message("xfix", "hi")),
+        );
+    }
+
+    #[tokio::test]
+    async fn escapes_replies() {
+        let mut router = router();
+        assert_eq!(
+            router
+                .handle(&message(">botdev\n|c:|1|@xfix|.say /modchat off"))
+                .await,
+            Some(SendMessage::chat_message(RoomId("botdev"), "//modchat off")),
+        );
+        assert_eq!(
+            router
+                .handle(&message("|pm|&xfix| Bot|.say /modchat off"))
+                .await,
+            Some(SendMessage::private_message("xfix", "//modchat off")),
+        );
+        assert_eq!(
+            router.handle(&message("|pm|&xfix| Bot|.say !rank")).await,
+            Some(SendMessage::private_message("xfix", "!!rank")),
+        );
+    }
+
+    #[tokio::test]
+    async fn applies_cooldowns() {
+        let mut router = router().cooldowns(Cooldowns::new(Duration::from_secs(60)).reply("Wait."));
+        let ping = message(">botdev\n|c:|1|+xfix|.ping");
+        assert_eq!(
+            router.handle(&ping).await,
+            Some(SendMessage::chat_message(RoomId("botdev"), "pong")),
+        );
+        assert_eq!(
+            router.handle(&ping).await,
+            Some(SendMessage::private_message("xfix", "Wait.")),
+        );
+        assert_eq!(
+            router.handle(&message("|pm| xfix| Bot|.ping")).await,
+            Some(SendMessage::private_message("xfix", "pong")),
+        );
+    }
+
+    #[tokio::test]
+    async fn ignores_other_messages() {
+        let mut router = router();
+        for raw in ["|c:|1|+xfix|ping", "|c:|1|+xfix|.unknown", "|J|+xfix"] {
+            assert_eq!(router.handle(&message(raw)).await, None, "{}", raw);
+        }
+        router.handle(&message("|updateuser| Bot|1|1|{}")).await;
+        assert_eq!(router.handle(&message("|pm| Bot| xfix|.ping")).await, None);
+    }
+}
@@ -0,0 +1,560 @@
+#[cfg(feature = "__tls")]
+use crate::server::ServerResolver;
+use crate::{Error, ErrorInner, Result, Stream};
+use std::collections::hash_map::RandomState;
+use std::collections::VecDeque;
+use std::convert::TryFrom;
+use std::fmt::{self, Formatter};
+use std::hash::BuildHasher;
+use std::io;
+#[cfg(feature = "__tls")]
+use std::sync::Arc;
+use std::time::SystemTime;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::error::UrlError;
+use tokio_tungstenite::tungstenite::http::header::{
+    HeaderName, HeaderValue, COOKIE, ORIGIN, USER_AGENT,
+};
+use tokio_tungstenite::tungstenite::http::{self, Request};
+use tokio_tungstenite::tungstenite::protocol::WebSocketConfig;
+use tokio_tungstenite::tungstenite::Error as WsError;
+#[cfg(feature = "__tls")]
+use tokio_tungstenite::Connector;
+#[cfg(not(feature = "__tls"))]
+use tokio_tungstenite::MaybeTlsStream;
+use url::Url;
+
+/// Builder for [`Stream`].
+///
+/// # Examples
+///
+#[cfg_attr(feature = "__tls", doc = "```no_run")]
+#[cfg_attr(not(feature = "__tls"), doc = "```compile_fail")]
+/// use showdown::{Proxy, Result, Stream};
+///
+/// #[tokio::main]
+/// async fn main() -> Result<()> {
+///     let stream = Stream::builder("showdown")
+///         .origin("https://play.pokemonshowdown.com")
+///         .user_agent("my-bot/1.0")
+///         .proxy(Proxy::socks5("127.0.0.1:9050"))
+///         .connect()
+///         .await?;
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone)]
+pub struct StreamBuilder {
+    server: Server,
+    headers: Vec<(String, String)>,
+    cookies: Vec<String>,
+    proxy: Option<Proxy>,
+    transport: Transport,
+    config: WebSocketConfig,
+    #[cfg(feature = "__tls")]
+    connector: Option<Connector>,
+    #[cfg(feature = "__tls")]
+    resolver: Option<Arc<dyn ServerResolver>>,
+}
+
+#[derive(Clone, Debug)]
+enum Server {
+    #[cfg(feature = "__tls")]
+    Named(String),
+    Url(Url),
+}
+
+impl StreamBuilder {
+    #[cfg(feature = "__tls")]
+    pub(crate) fn new(name: &str) -> Self {
+        Self::with_server(Server::Named(name.into()))
+    }
+
+    pub(crate) fn for_url(url: Url) -> Self {
+        Self::with_server(Server::Url(url))
+    }
+
+    fn with_server(server: Server) -> Self {
+        Self {
+            server,
+            headers: Vec::new(),
+            cookies: Vec::new(),
+            proxy: None,
+            transport: Transport::WebSocket,
+            config: WebSocketConfig::default(),
+            #[cfg(feature = "__tls")]
+            connector: None,
+            #[cfg(feature = "__tls")]
+            resolver: None,
+        }
+    }
+
+    /// Adds a header to the websocket handshake request.
+    ///
+    /// Invalid headers are reported when connecting.
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Sets `Origin` header.
+    ///
+    /// Some servers reject connections from unknown origins.
+    pub fn origin(self, origin: &str) -> Self {
+        self.header(ORIGIN.as_str(), origin)
+    }
+
+    /// Sets `User-Agent` header.
+    pub fn user_agent(self, user_agent: &str) -> Self {
+        self.header(USER_AGENT.as_str(), user_agent)
+    }
+
+    /// Adds a cookie to `Cookie` header.
+    pub fn cookie(mut self, name: &str, value: &str) -> Self {
+        self.cookies.push(format!("{}={}", name, value));
+        self
+    }
+
+    /// Connects through a proxy.
+    pub fn proxy(mut self, proxy: Proxy) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    /// Sets a transport, [`Transport::WebSocket`] by default.
+    pub fn transport(mut self, transport: Transport) -> Self {
+        self.transport = transport;
+        self
+    }
+
+    /// Sets the maximum size of a message, `None` means no limit.
+    ///
+    /// Defaults to 64 MiB.
+    pub fn max_message_size(mut self, size: Option<usize>) -> Self {
+        self.config.max_message_size = size;
+        self
+    }
+
+    /// Sets the maximum size of a single frame, `None` means no limit.
+    ///
+    /// Defaults to 16 MiB.
+    pub fn max_frame_size(mut self, size: Option<usize>) -> Self {
+        self.config.max_frame_size = size;
+        self
+    }
+
+    /// Sets a TLS connector used for `wss` URLs.
+    ///
+    /// By default, a connector for the TLS implementation chosen by
+    /// crate features is used.
+    ///
+    /// Requires `native-tls`, `native-tls-vendored` or `rustls-tls` feature.
+    #[cfg(feature = "__tls")]
+    pub fn tls_connector(mut self, connector: Connector) -> Self {
+        self.connector = Some(connector);
+        self
+    }
+
+    /// Sets a resolver used to find a named server.
+    ///
+    /// By default, the official server directory is used.
+    ///
+    /// Requires `native-tls`, `native-tls-vendored` or `rustls-tls` feature.
+    #[cfg(feature = "__tls")]
+    pub fn resolver(mut self, resolver: impl ServerResolver + 'static) -> Self {
+        self.resolver = Some(Arc::new(resolver));
+        self
+    }
+
+    /// Connects to the server.
+    pub async fn connect(self) -> Result<Stream> {
+        let mut url = match self.server {
+            #[cfg(feature = "__tls")]
+            Server::Named(ref name) => match &self.resolver {
+                Some(resolver) => resolver.resolve(name).await?.websocket_url()?,
+                None => crate::fetch_server_url(name).await?,
+            },
+            Server::Url(ref url) => url.clone(),
+        };
+        if self.transport == Transport::SockJs {
+            url = sockjs_url(url);
+        }
+        let request = self.request(&url)?;
+        let host = Error::from_ws(url.host_str().ok_or(WsError::Url(UrlError::NoHostName)))?;
+        let port = Error::from_ws(
+            url.port_or_known_default()
+                .ok_or(WsError::Url(UrlError::UnsupportedUrlScheme)),
+        )?;
+        let socket = match &self.proxy {
+            Some(proxy) => proxy.connect(host, port).await,
+            None => {
+                let host = host.trim_start_matches('[').trim_end_matches(']');
+                TcpStream::connect((host, port)).await
+            }
+        }
+        .map_err(|e| Error(ErrorInner::Io(e)))?;
+        #[cfg(feature = "__tls")]
+        let result = tokio_tungstenite::client_async_tls_with_config(
+            request,
+            socket,
+            Some(self.config),
+            self.connector,
+        )
+        .await;
+        #[cfg(not(feature = "__tls"))]
+        let result = if url.scheme() == "wss" {
+            Err(WsError::Url(UrlError::TlsFeatureNotEnabled))
+        } else {
+            tokio_tungstenite::client_async_with_config(
+                request,
+                MaybeTlsStream::Plain(socket),
+                Some(self.config),
+            )
+            .await
+        };
+        let stream = Error::from_ws(result)?.0;
+        Ok(Stream {
+            stream,
+            transport: self.transport,
+            pending: VecDeque::new(),
+        })
+    }
+
+    fn request(&self, url: &Url) -> Result<Request<()>> {
+        let mut request = Error::from_ws(url.as_str().into_client_request())?;
+        let headers = request.headers_mut();
+        for (name, value) in &self.headers {
+            let name = HeaderName::from_bytes(name.as_bytes()).map_err(http_error)?;
+            let value = HeaderValue::from_str(value).map_err(http_error)?;
+            headers.insert(name, value);
+        }
+        if !self.cookies.is_empty() {
+            let cookies = HeaderValue::from_str(&self.cookies.join("; ")).map_err(http_error)?;
+            headers.insert(COOKIE, cookies);
+        }
+        Ok(request)
+    }
+}
+
+fn http_error(error: impl Into<http::Error>) -> Error {
+    Error(ErrorInner::WebSocket(Box::new(WsError::HttpFormat(
+        error.into(),
+    ))))
+}
+
+impl fmt::Debug for StreamBuilder {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("StreamBuilder");
+        debug
+            .field("server", &self.server)
+            .field("headers", &self.headers)
+            .field("cookies", &self.cookies)
+            .field("proxy", &self.proxy)
+            .field("transport", &self.transport)
+            .field("config", &self.config);
+        #[cfg(feature = "__tls")]
+        debug.field("resolver", &self.resolver);
+        debug.finish()
+    }
+}
+
+/// Protocol used to exchange messages with a server.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Transport {
+    /// Raw websocket, where every websocket message is a Showdown
+    /// message.
+    WebSocket,
+    /// SockJS websocket endpoint.
+    ///
+    /// Server URL should point to SockJS prefix, like
+    /// `wss://sim3.psim.us/showdown`, `/<server>/<session>/websocket` is
+    /// appended to it when connecting. Open and heartbeat frames are
+    /// skipped, and array frames are split into separate messages.
+    SockJs,
+}
+
+/// Appends a random server and session ID to SockJS prefix.
+fn sockjs_url(mut url: Url) -> Url {
+    let random = RandomState::new().hash_one(SystemTime::now());
+    let path = format!(
+        "{}/{:03}/{:08x}/websocket",
+        url.path().trim_end_matches('/'),
+        random % 1000,
+        random >> 32,
+    );
+    url.set_path(&path);
+    url
+}
+
+/// Proxy server used by [`StreamBuilder`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Proxy {
+    kind: ProxyKind,
+    address: String,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum ProxyKind {
+    Http,
+    Socks5,
+}
+
+impl Proxy {
+    /// HTTP proxy supporting `CONNECT` method, like `"127.0.0.1:8080"`.
+    pub fn http(address: impl Into<String>) -> Self {
+        Self {
+            kind: ProxyKind::Http,
+            address: address.into(),
+        }
+    }
+
+    /// SOCKS5 proxy not requiring authentication, like `"127.0.0.1:1080"`.
+    ///
+    /// Host names are resolved by the proxy.
+    pub fn socks5(address: impl Into<String>) -> Self {
+        Self {
+            kind: ProxyKind::Socks5,
+            address: address.into(),
+        }
+    }
+
+    async fn connect(&self, host: &str, port: u16) -> io::Result<TcpStream> {
+        let mut socket = TcpStream::connect(&*self.address).await?;
+        match self.kind {
+            ProxyKind::Http => http_connect(&mut socket, host, port).await?,
+            ProxyKind::Socks5 => socks5_connect(&mut socket, host, port).await?,
+        }
+        Ok(socket)
+    }
+}
+
+async fn http_connect(socket: &mut TcpStream, host: &str, port: u16) -> io::Result<()> {
+    let request = format!(
+        "CONNECT {0}:{1} HTTP/1.1\r\nHost: {0}:{1}\r\n\r\n",
+        host, port,
+    );
+    socket.write_all(request.as_bytes()).await?;
+    // Read byte by byte to avoid consuming data sent after the response.
+    let mut response = Vec::new();
+    while !response.ends_with(b"\r\n\r\n") {
+        if response.len() > 8192 {
+            return Err(proxy_error("HTTP proxy response is too long"));
+        }
+        response.push(socket.read_u8().await?);
+    }
+    let status = response.split(|&b| b == b' ').nth(1);
+    if matches!(status, Some(status) if status.starts_with(b"2")) {
+        Ok(())
+    } else {
+        Err(proxy_error("HTTP proxy refused to connect"))
+    }
+}
+
+async fn socks5_connect(socket: &mut TcpStream, host: &str, port: u16) -> io::Result<()> {
+    // Version 5, one authentication method: no authentication.
+    socket.write_all(&[5, 1, 0]).await?;
+    let mut reply = [0; 2];
+    socket.read_exact(&mut reply).await?;
+    if reply != [5, 0] {
+        return Err(proxy_error("SOCKS5 proxy requires authentication"));
+    }
+    let host_len = u8::try_from(host.len()).map_err(|_| proxy_error("host name is too long"))?;
+    // Version 5, command CONNECT, reserved, address type: domain name.
+    let mut request = vec![5, 1, 0, 3, host_len];
+    request.extend_from_slice(host.as_bytes());
+    request.extend_from_slice(&port.to_be_bytes());
+    socket.write_all(&request).await?;
+    let mut reply = [0; 4];
+    socket.read_exact(&mut reply).await?;
+    if reply[1] != 0 {
+        return Err(proxy_error("SOCKS5 proxy refused to connect"));
+    }
+    let address_len = match reply[3] {
+        1 => 4,
+        3 => usize::from(socket.read_u8().await?),
+        4 => 16,
+        _ => return Err(proxy_error("SOCKS5 proxy sent invalid address")),
+    };
+    let mut address = vec![0; address_len + 2];
+    socket.read_exact(&mut address).await?;
+    Ok(())
+}
+
+fn proxy_error(message: &str) -> io::Error {
+    io::Error::other(message)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Proxy, Transport};
+    use crate::{RoomId, SendMessage, Stream};
+    use futures::{SinkExt, StreamExt};
+    use std::net::Ipv4Addr;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::tungstenite::handshake::server::{Request, Response};
+    use tokio_tungstenite::tungstenite::Message;
+
+    #[tokio::test]
+    async fn sends_headers() {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let url = format!("ws://127.0.0.1:{}/showdown/websocket", port);
+        let server = async {
+            let socket = listener.accept().await.unwrap().0;
+            let mut headers = None;
+            // Callback signature is dictated by tungstenite.
+            #[allow(clippy::result_large_err)]
+            let callback = |request: &Request, response: Response| {
+                headers = Some(request.headers().clone());
+                Ok(response)
+            };
+            tokio_tungstenite::accept_hdr_async(socket, callback)
+                .await
+                .unwrap();
+            headers.unwrap()
+        };
+        let client = Stream::builder_for_url(url.parse().unwrap())
+            .origin("https://play.pokemonshowdown.com")
+            .user_agent("showdown-test")
+            .cookie("sid", "abc")
+            .cookie("showdown_username", "xfix")
+            .connect();
+        let (headers, stream) = tokio::join!(server, client);
+        stream.unwrap();
+        assert_eq!(headers["origin"], "https://play.pokemonshowdown.com");
+        assert_eq!(headers["user-agent"], "showdown-test");
+        assert_eq!(headers["cookie"], "sid=abc; showdown_username=xfix");
+    }
+
+    #[tokio::test]
+    async fn sockjs() {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let url = format!("ws://127.0.0.1:{}/showdown", port);
+        let server = async {
+            let socket = listener.accept().await.unwrap().0;
+            let mut path = None;
+            #[allow(clippy::result_large_err)]
+            let callback = |request: &Request, response: Response| {
+                path = Some(request.uri().path().to_string());
+                Ok(response)
+            };
+            let mut socket = tokio_tungstenite::accept_hdr_async(socket, callback)
+                .await
+                .unwrap();
+            for frame in [
+                "o",
+                "h",
+                r#"a["|J|+xfix","|L|+xfix"]"#,
+                r#"c[3000,"Go away!"]"#,
+            ] {
+                socket.send(Message::Text(frame.into())).await.unwrap();
+            }
+            let received = socket.next().await.unwrap().unwrap();
+            (path.unwrap(), received)
+        };
+        let client = async {
+            let mut stream = Stream::builder_for_url(url.parse().unwrap())
+                .transport(Transport::SockJs)
+                .connect()
+                .await
+                .unwrap();
+            stream
+                .send(SendMessage::chat_message(RoomId::LOBBY, "Hi"))
+                .await
+                .unwrap();
+            let mut messages = Vec::new();
+            while let Some(message) = stream.next().await {
+                messages.push(message.unwrap().raw);
+            }
+            messages
+        };
+        let ((path, received), messages) = tokio::join!(server, client);
+        let segments: Vec<_> = path.split('/').collect();
+        assert_eq!(segments[..2], ["", "showdown"]);
+        assert_eq!(segments[2].len(), 3);
+        assert_eq!(segments[4], "websocket");
+        assert_eq!(received, Message::Text(r#"["lobby| Hi"]"#.into()));
+        assert_eq!(messages, ["|J|+xfix", "|L|+xfix"]);
+    }
+
+    #[tokio::test]
+    async fn invalid_header() {
+        let result = Stream::builder_for_url("ws://127.0.0.1:1/".parse().unwrap())
+            .header("bad header", "value")
+            .connect()
+            .await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn http_proxy() {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let proxy = async {
+            let mut socket = listener.accept().await.unwrap().0;
+            let mut request = Vec::new();
+            while !request.ends_with(b"\r\n\r\n") {
+                request.push(socket.read_u8().await.unwrap());
+            }
+            socket
+                .write_all(b"HTTP/1.1 200 Connection established\r\n\r\n")
+                .await
+                .unwrap();
+            let mut socket = tokio_tungstenite::accept_async(socket).await.unwrap();
+            socket.send(Message::Text("|J|+xfix".into())).await.unwrap();
+            String::from_utf8(request).unwrap()
+        };
+        let client = async {
+            let mut stream = Stream::builder_for_url("ws://sim.example:8000/".parse().unwrap())
+                .proxy(Proxy::http(format!("127.0.0.1:{}", port)))
+                .connect()
+                .await
+                .unwrap();
+            stream.next().await.unwrap().unwrap()
+        };
+        let (request, message) = tokio::join!(proxy, client);
+        assert!(request.starts_with("CONNECT sim.example:8000 HTTP/1.1\r\n"));
+        assert_eq!(message.raw, "|J|+xfix");
+    }
+
+    #[tokio::test]
+    async fn socks5_proxy() {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let proxy = async {
+            let mut socket = listener.accept().await.unwrap().0;
+            let mut greeting = [0; 3];
+            socket.read_exact(&mut greeting).await.unwrap();
+            assert_eq!(greeting, [5, 1, 0]);
+            socket.write_all(&[5, 0]).await.unwrap();
+            let mut request = [0; 5];
+            socket.read_exact(&mut request).await.unwrap();
+            assert_eq!(request[..4], [5, 1, 0, 3]);
+            let mut host = vec![0; usize::from(request[4])];
+            socket.read_exact(&mut host).await.unwrap();
+            let port = socket.read_u16().await.unwrap();
+            socket
+                .write_all(&[5, 0, 0, 1, 127, 0, 0, 1, 0, 80])
+                .await
+                .unwrap();
+            let mut socket = tokio_tungstenite::accept_async(socket).await.unwrap();
+            socket.send(Message::Text("|J|+xfix".into())).await.unwrap();
+            (String::from_utf8(host).unwrap(), port)
+        };
+        let client = async {
+            let mut stream = Stream::builder_for_url("ws://sim.example:8000/".parse().unwrap())
+                .proxy(Proxy::socks5(format!("127.0.0.1:{}", port)))
+                .connect()
+                .await
+                .unwrap();
+            stream.next().await.unwrap().unwrap()
+        };
+        let (target, message) = tokio::join!(proxy, client);
+        assert_eq!(target, ("sim.example".into(), 8000));
+        assert_eq!(message.raw, "|J|+xfix");
+    }
+}
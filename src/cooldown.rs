@@ -0,0 +1,205 @@
+//! Command cooldowns for chat bots.
+
+use crate::message::{Kind, Message};
+use crate::{to_id, RoomId, SendMessage};
+use std::collections::BTreeMap;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Tracks when users last used commands to limit how often they can be
+/// used.
+///
+/// Uses are tracked separately for every user, room and command, so an
+/// user using a command in one room doesn't put it on cooldown in other
+/// rooms or for other users. Private messages are tracked as their own
+/// room.
+///
+/// # Examples
+///
+/// ```
+/// use showdown::cooldown::Cooldowns;
+/// use showdown::RoomId;
+/// use std::time::Duration;
+///
+/// let mut cooldowns = Cooldowns::new(Duration::from_secs(10))
+///     .command("roll", Duration::from_secs(3))
+///     .reply("You're on cooldown.");
+/// let room = Some(RoomId("botdev"));
+/// assert!(cooldowns.check(room, "Xfix", "roll").is_ok());
+/// assert!(cooldowns.check(room, "xfix", "roll").is_err());
+/// assert!(cooldowns.check(room, "Zarel", "roll").is_ok());
+/// assert!(cooldowns.check(None, "xfix", "roll").is_ok());
+/// ```
+#[derive(Clone, Debug)]
+pub struct Cooldowns {
+    window: Duration,
+    commands: BTreeMap<String, Duration>,
+    reply: Option<String>,
+    uses: BTreeMap<(String, Option<String>, String), Instant>,
+}
+
+impl Cooldowns {
+    /// Creates cooldowns with a default window used by every command.
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            commands: BTreeMap::new(),
+            reply: None,
+            uses: BTreeMap::new(),
+        }
+    }
+
+    /// Sets a window for a command, overriding the default one.
+    pub fn command(mut self, name: &str, window: Duration) -> Self {
+        self.commands.insert(to_id(name), window);
+        self
+    }
+
+    /// Sets a reply privately sent to users using a command on cooldown.
+    ///
+    /// By default nothing is sent.
+    pub fn reply(mut self, reply: impl Into<String>) -> Self {
+        self.reply = Some(reply.into());
+        self
+    }
+
+    /// Checks whether an user can use a command, and if so, starts its
+    /// cooldown.
+    ///
+    /// `room` is `None` for commands used in private messages.
+    pub fn check(
+        &mut self,
+        room: Option<RoomId<'_>>,
+        user: &str,
+        command: &str,
+    ) -> Result<(), OnCooldown> {
+        self.check_at(Instant::now(), room, user, command)
+    }
+
+    /// Checks whether a chat or private message can run a command, and if
+    /// so, starts its cooldown.
+    ///
+    /// Other messages aren't limited.
+    pub fn check_message(&mut self, message: &Message, command: &str) -> Result<(), OnCooldown> {
+        match message.kind() {
+            Kind::Chat(chat) => self.check(Some(message.room()), &chat.user().id(), command),
+            Kind::Private(private) => self.check(None, &private.from.id(), command),
+            _ => Ok(()),
+        }
+    }
+
+    /// Gets the time left until an user can use a command again.
+    pub fn remaining(
+        &self,
+        room: Option<RoomId<'_>>,
+        user: &str,
+        command: &str,
+    ) -> Option<Duration> {
+        let until = self.uses.get(&key(room, user, command))?;
+        until
+            .checked_duration_since(Instant::now())
+            .filter(|remaining| !remaining.is_zero())
+    }
+
+    /// Forgets every use of commands.
+    pub fn clear(&mut self) {
+        self.uses.clear();
+    }
+
+    fn check_at(
+        &mut self,
+        now: Instant,
+        room: Option<RoomId<'_>>,
+        user: &str,
+        command: &str,
+    ) -> Result<(), OnCooldown> {
+        let key = key(room, user, command);
+        if let Some(until) = self.uses.get(&key).filter(|until| **until > now) {
+            return Err(OnCooldown {
+                remaining: *until - now,
+                reply: self
+                    .reply
+                    .as_ref()
+                    .map(|reply| SendMessage::private_message(&key.0, reply)),
+            });
+        }
+        let window = self.commands.get(&key.2).copied().unwrap_or(self.window);
+        self.uses.insert(key, now + window);
+        Ok(())
+    }
+}
+
+fn key(room: Option<RoomId<'_>>, user: &str, command: &str) -> (String, Option<String>, String) {
+    (
+        to_id(user),
+        room.map(|room| room.0.to_string()),
+        to_id(command),
+    )
+}
+
+/// A command was used before its cooldown ended.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OnCooldown {
+    remaining: Duration,
+    reply: Option<SendMessage>,
+}
+
+impl OnCooldown {
+    /// Gets the time left until the command can be used again.
+    pub fn remaining(&self) -> Duration {
+        self.remaining
+    }
+
+    /// Gets a configured reply for the user, see
+    /// [`Cooldowns::reply`].
+    pub fn into_reply(self) -> Option<SendMessage> {
+        self.reply
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Cooldowns;
+    use crate::message::Message;
+    use crate::{RoomId, SendMessage};
+    use std::time::Duration;
+    use tokio::time::Instant;
+
+    #[test]
+    fn windows() {
+        let mut cooldowns =
+            Cooldowns::new(Duration::from_secs(10)).command("Roll", Duration::from_secs(2));
+        let room = Some(RoomId("botdev"));
+        let now = Instant::now();
+        let later = now + Duration::from_secs(3);
+        assert!(cooldowns.check_at(now, room, "xfix", "roll").is_ok());
+        assert!(cooldowns.check_at(now, room, "xfix", "help").is_ok());
+        let cooldown = cooldowns.check_at(now + Duration::from_secs(1), room, "Xfix", "ROLL");
+        assert_eq!(cooldown.unwrap_err().remaining(), Duration::from_secs(1));
+        assert!(cooldowns.check_at(later, room, "xfix", "roll").is_ok());
+        assert!(cooldowns.check_at(later, room, "xfix", "help").is_err());
+    }
+
+    #[test]
+    fn messages() {
+        let mut cooldowns = Cooldowns::new(Duration::from_secs(60)).reply("Slow down.");
+        let chat = Message {
+            raw: ">botdev\n|c:|1|+xfix|.roll".into(),
+        };
+        let private = Message {
+            raw: "|pm| xfix| Bot|.roll".into(),
+        };
+        assert!(cooldowns.check_message(&chat, "roll").is_ok());
+        assert_eq!(
+            cooldowns
+                .check_message(&chat, "roll")
+                .unwrap_err()
+                .into_reply(),
+            Some(SendMessage::private_message("xfix", "Slow down.")),
+        );
+        assert!(cooldowns.check_message(&private, "roll").is_ok());
+        assert!(cooldowns.remaining(None, "xfix", "roll").is_some());
+        cooldowns.clear();
+        assert!(cooldowns.remaining(None, "xfix", "roll").is_none());
+    }
+}
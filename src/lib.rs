@@ -9,17 +9,33 @@
 //! removed features. Don't use this crate if you aren't prepared for constant
 //! breakage.
 
+pub mod battle;
+pub mod bot;
+mod builder;
+pub mod cooldown;
+#[cfg(feature = "__tls")]
+pub mod login;
 pub mod message;
+pub mod presence;
+pub mod reconnect;
+pub mod server;
+pub mod team;
+#[cfg(feature = "test-util")]
+pub mod testing;
+pub mod throttle;
+pub mod tournament;
 
-use self::message::Message;
+use self::battle::{BattleChoice, ChoiceError};
+pub use self::builder::{Proxy, StreamBuilder, Transport};
+use self::message::{BattleRequest, Message};
 #[cfg(feature = "__tls")]
-use futures_util::future::TryFutureExt;
+use self::server::{HttpResolver, ServerResolver};
 use futures_util::ready;
 use futures_util::sink::Sink;
 use futures_util::stream::Stream as FuturesStream;
-#[cfg(feature = "__tls")]
-use serde::Deserialize;
+use std::collections::VecDeque;
 use std::fmt::{self, Display, Formatter};
+use std::io;
 use std::pin::Pin;
 use std::result::Result as StdResult;
 use std::task::{Context, Poll};
@@ -30,6 +46,8 @@ use tokio::net::TcpStream;
 use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
 use tokio_tungstenite::tungstenite::protocol::CloseFrame;
 use tokio_tungstenite::tungstenite::{Error as WsError, Message as OwnedMessage};
+#[cfg(feature = "__tls")]
+pub use tokio_tungstenite::Connector;
 use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
 pub use url;
 use url::Url;
@@ -65,6 +83,9 @@ type SocketStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
 /// ```
 pub struct Stream {
     stream: SocketStream,
+    transport: Transport,
+    /// Messages from SockJS array frame which weren't returned yet.
+    pending: VecDeque<String>,
 }
 
 impl Stream {
@@ -92,7 +113,7 @@ impl Stream {
     /// ```
     #[cfg(feature = "__tls")]
     pub async fn connect(name: &str) -> Result<Self> {
-        Self::connect_to_url(&fetch_server_url(name).await?).await
+        Self::builder(name).connect().await
     }
 
     /// Connects to an URL.
@@ -117,8 +138,20 @@ impl Stream {
     /// }
     /// ```
     pub async fn connect_to_url(url: &Url) -> Result<Self> {
-        let stream = Error::from_ws(tokio_tungstenite::connect_async(url).await)?.0;
-        Ok(Self { stream })
+        Self::builder_for_url(url.clone()).connect().await
+    }
+
+    /// Creates a builder for a named Showdown server.
+    ///
+    /// Requires `native-tls`, `native-tls-vendored` or `rustls-tls` feature.
+    #[cfg(feature = "__tls")]
+    pub fn builder(name: &str) -> StreamBuilder {
+        StreamBuilder::new(name)
+    }
+
+    /// Creates a builder for an URL.
+    pub fn builder_for_url(url: Url) -> StreamBuilder {
+        StreamBuilder::for_url(url)
     }
 }
 
@@ -138,7 +171,11 @@ impl Sink<SendMessage> for Stream {
     }
 
     fn start_send(mut self: Pin<&mut Self>, item: SendMessage) -> Result<()> {
-        Error::from_ws(Pin::new(&mut self.stream).start_send(OwnedMessage::Text(item.0)))
+        let text = match self.transport {
+            Transport::WebSocket => item.0,
+            Transport::SockJs => serde_json::to_string(&[item.0]).unwrap(),
+        };
+        Error::from_ws(Pin::new(&mut self.stream).start_send(OwnedMessage::Text(text)))
     }
 
     fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
@@ -159,7 +196,27 @@ impl FuturesStream for Stream {
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
         Poll::Ready(loop {
+            if let Some(raw) = self.pending.pop_front() {
+                break Some(Ok(Message { raw }));
+            }
             match Error::from_ws(ready!(Pin::new(&mut self.stream).poll_next(cx)).transpose())? {
+                Some(OwnedMessage::Text(raw)) if self.transport == Transport::SockJs => {
+                    match raw.as_bytes().first() {
+                        Some(b'o') | Some(b'h') if raw.len() == 1 => {}
+                        Some(b'a') => match serde_json::from_str::<Vec<String>>(&raw[1..]) {
+                            Ok(messages) => self.pending.extend(messages),
+                            Err(_) => {
+                                let message = OwnedMessage::Text(raw);
+                                break Some(Err(Error(ErrorInner::UnrecognizedMessage(message))));
+                            }
+                        },
+                        Some(b'c') => break None,
+                        _ => {
+                            let message = OwnedMessage::Text(raw);
+                            break Some(Err(Error(ErrorInner::UnrecognizedMessage(message))));
+                        }
+                    }
+                }
                 Some(OwnedMessage::Text(raw)) => break Some(Ok(Message { raw })),
                 Some(OwnedMessage::Close(Some(CloseFrame {
                     code: CloseCode::Normal,
@@ -172,7 +229,11 @@ impl FuturesStream for Stream {
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
-        self.stream.size_hint()
+        match self.transport {
+            Transport::WebSocket => self.stream.size_hint(),
+            // Array frames may contain any number of messages.
+            Transport::SockJs => (self.pending.len(), None),
+        }
     }
 }
 
@@ -243,40 +304,201 @@ impl SendMessage {
         Self::prefixed(room_id, '/', command)
     }
 
+    /// Creates a private message to an user.
+    pub fn private_message(user: &str, message: impl Display) -> Self {
+        Self::global_command(format_args!("pm {}, {}", user, message))
+    }
+
+    /// Uploads a team used by following challenges and searches.
+    ///
+    /// The team needs to be in packed format, formats with random teams
+    /// don't need a team.
+    pub fn upload_team(team: impl Display) -> Self {
+        Self::global_command(format_args!("utm {}", team))
+    }
+
+    /// Challenges an user to a battle.
+    ///
+    /// # Examples
+    ///
+    #[cfg_attr(feature = "__tls", doc = "```no_run")]
+    #[cfg_attr(not(feature = "__tls"), doc = "```compile_fail")]
+    /// use futures::{SinkExt, StreamExt};
+    /// use showdown::message::Kind;
+    /// use showdown::{Result, SendMessage, Stream};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let mut stream = Stream::connect("showdown").await?;
+    ///     stream.send(SendMessage::upload_team("null")).await?;
+    ///     stream
+    ///         .send(SendMessage::challenge("xfix", "gen9randombattle"))
+    ///         .await?;
+    ///     while let Some(message) = stream.next().await {
+    ///         if let Kind::UpdateChallenges(challenges) = message?.kind() {
+    ///             println!("Challenging {:?}", challenges.challenge_to);
+    ///         }
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn challenge(user: &str, format: &str) -> Self {
+        Self::global_command(format_args!("challenge {}, {}", user, format))
+    }
+
+    /// Accepts a challenge from an user.
+    pub fn accept_challenge(user: &str) -> Self {
+        Self::global_command(format_args!("accept {}", user))
+    }
+
+    /// Rejects a challenge from an user.
+    pub fn reject_challenge(user: &str) -> Self {
+        Self::global_command(format_args!("reject {}", user))
+    }
+
+    /// Cancels a challenge sent to an user.
+    pub fn cancel_challenge(user: &str) -> Self {
+        Self::global_command(format_args!("cancelchallenge {}", user))
+    }
+
+    /// Searches for a ladder battle in a format.
+    pub fn search(format: &str) -> Self {
+        Self::global_command(format_args!("search {}", format))
+    }
+
+    /// Cancels every ladder search.
+    pub fn cancel_search() -> Self {
+        Self::global_command("cancelsearch")
+    }
+
+    /// Joins a tournament in a room.
+    ///
+    /// # Examples
+    ///
+    #[cfg_attr(feature = "__tls", doc = "```no_run")]
+    #[cfg_attr(not(feature = "__tls"), doc = "```compile_fail")]
+    /// use futures::{SinkExt, StreamExt};
+    /// use showdown::message::{Kind, TournamentEvent};
+    /// use showdown::{Result, RoomId, SendMessage, Stream};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     let mut stream = Stream::connect("showdown").await?;
+    ///     stream.send(SendMessage::global_command("join tours")).await?;
+    ///     while let Some(message) = stream.next().await {
+    ///         let message = message?;
+    ///         if let Kind::Tournament(TournamentEvent::Create { .. }) = message.kind() {
+    ///             stream
+    ///                 .send(SendMessage::join_tournament(message.room()))
+    ///                 .await?;
+    ///         }
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn join_tournament(room_id: RoomId<'_>) -> Self {
+        Self::chat_command(room_id, "tour join")
+    }
+
+    /// Leaves a tournament in a room.
+    pub fn leave_tournament(room_id: RoomId<'_>) -> Self {
+        Self::chat_command(room_id, "tour leave")
+    }
+
+    /// Challenges a tournament opponent to a battle.
+    pub fn tournament_challenge(room_id: RoomId<'_>, user: &str) -> Self {
+        Self::chat_command(room_id, format_args!("tour challenge {}", user))
+    }
+
+    /// Accepts a challenge from a tournament opponent.
+    pub fn accept_tournament_challenge(room_id: RoomId<'_>) -> Self {
+        Self::chat_command(room_id, "tour acceptchallenge")
+    }
+
     pub fn broadcast_command(room_id: RoomId<'_>, command: impl Display) -> Self {
         Self::prefixed(room_id, '!', command)
     }
 
+    /// Creates a battle decision for a request.
+    ///
+    /// The choice is validated against the request, and request ID is
+    /// included so that the server can reject outdated decisions.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use showdown::battle::{BattleChoice, MoveChoice};
+    /// use showdown::message::{Kind, Message};
+    /// use showdown::{Result, SendMessage};
+    ///
+    /// fn respond(message: &Message) -> Result<Option<SendMessage>> {
+    ///     match message.kind() {
+    ///         Kind::Request(request) if !request.wait => {
+    ///             let choice = BattleChoice::new().choose_move(MoveChoice::new(1));
+    ///             SendMessage::battle_choice(message.room(), &choice, &request).map(Some)
+    ///         }
+    ///         _ => Ok(None),
+    ///     }
+    /// }
+    /// ```
+    pub fn battle_choice(
+        room_id: RoomId<'_>,
+        choice: &BattleChoice,
+        request: &BattleRequest<'_>,
+    ) -> Result<Self> {
+        choice
+            .validate(request)
+            .map_err(|e| Error(ErrorInner::InvalidChoice(e)))?;
+        Ok(if choice.is_undo() {
+            Self::chat_command(room_id, "undo")
+        } else if let Some(rqid) = request.rqid {
+            Self::chat_command(room_id, format_args!("choose {}|{}", choice, rqid))
+        } else {
+            Self::chat_command(room_id, format_args!("choose {}", choice))
+        })
+    }
+
     fn prefixed(room_id: RoomId<'_>, prefix: char, message: impl Display) -> Self {
         SendMessage(format!("{}|{}{}", room_id.0, prefix, message))
     }
+
+    /// Gets a room the message is sent to, empty for global commands.
+    pub(crate) fn room(&self) -> &str {
+        self.0.split('|').next().unwrap()
+    }
 }
 
+/// Gets a websocket URL of a named server using the official server
+/// directory.
+///
+/// Use [`ServerResolver`](server::ServerResolver) implementations for
+/// other directories and caching.
+///
 /// Requires `native-tls`, `native-tls-vendored` or `rustls-tls` feature.
 #[cfg(feature = "__tls")]
 pub async fn fetch_server_url(name: &str) -> Result<Url> {
-    let owned_url;
-    let url = if name == "showdown" {
-        "wss://sim3.psim.us/showdown/websocket"
-    } else {
-        let server_info_url = format!("https://pokemonshowdown.com/servers/{}.json", name);
-        let Server { host, port } = reqwest::get(&server_info_url)
-            .and_then(|r| r.json())
-            .await
-            .map_err(|e| Error(ErrorInner::Reqwest(e)))?;
-        let protocol = if port == 443 { "wss" } else { "ws" };
-        // Concatenation is fine, as it's also done by the official Showdown client
-        owned_url = format!("{}://{}:{}/showdown/websocket", protocol, host, port);
-        &owned_url
-    };
-    Url::parse(url).map_err(|e| Error(ErrorInner::Url(e)))
+    HttpResolver::default().resolve(name).await?.websocket_url()
 }
 
-#[cfg(feature = "__tls")]
-#[derive(Deserialize)]
-struct Server {
-    host: String,
-    port: u16,
+/// Converts a name into an ID, the way Showdown does.
+///
+/// Letters are converted to lowercase and characters other than ASCII
+/// letters and digits are removed.
+///
+/// # Examples
+///
+/// ```
+/// use showdown::to_id;
+///
+/// assert_eq!(to_id(" Guest 123"), "guest123");
+/// assert_eq!(to_id("Bot Development"), "botdevelopment");
+/// assert_eq!(to_id("Flabébé"), "flabb");
+/// ```
+pub fn to_id(name: &str) -> String {
+    name.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -295,23 +517,33 @@ pub struct Error(ErrorInner);
 
 impl Error {
     fn from_ws<T>(r: StdResult<T, tokio_tungstenite::tungstenite::Error>) -> Result<T> {
-        r.map_err(|e| Error(ErrorInner::WebSocket(e)))
+        r.map_err(|e| Error(ErrorInner::WebSocket(Box::new(e))))
     }
 }
 
 #[derive(Debug, Error)]
 enum ErrorInner {
     #[error("Websocket error")]
-    WebSocket(#[source] WsError),
+    WebSocket(#[source] Box<WsError>),
+    #[error("IO error")]
+    Io(#[source] io::Error),
     #[cfg(feature = "__tls")]
     #[error("HTTPS request error")]
     Reqwest(#[source] reqwest::Error),
-    #[cfg(feature = "__tls")]
     #[error("Couldn't get a valid server URL")]
     Url(#[source] url::ParseError),
+    #[error("Couldn't parse server information")]
+    ServerInfo(#[source] serde_json::Error),
+    #[error("Unknown server: {0}")]
+    UnknownServer(String),
     #[cfg(feature = "__tls")]
     #[error("Couldn't parse login assertion")]
     Json(#[source] serde_json::Error),
+    #[cfg(feature = "__tls")]
+    #[error("Login server rejected credentials: {0}")]
+    LoginRejected(String),
+    #[error("Invalid battle choice")]
+    InvalidChoice(#[source] ChoiceError),
     #[error("Unrecognized message: {0:?}")]
     UnrecognizedMessage(OwnedMessage),
 }
@@ -0,0 +1,185 @@
+//! Login server client.
+//!
+//! Requires `native-tls`, `native-tls-vendored` or `rustls-tls` feature.
+
+use crate::message::{Challenge, PasswordRequired};
+use crate::{Error, ErrorInner, Result, SendMessage, Stream};
+use futures_util::future::TryFutureExt;
+use futures_util::sink::SinkExt;
+use reqwest::Client;
+use serde::Deserialize;
+use std::borrow::Cow;
+use url::Url;
+
+/// Client for a login server (`action.php`).
+///
+/// Cloning is cheap, as clones share a connection pool.
+///
+/// # Examples
+///
+/// ```no_run
+/// use futures::StreamExt;
+/// use showdown::login::LoginClient;
+/// use showdown::message::Kind;
+/// use showdown::{Result, Stream};
+///
+/// #[tokio::main]
+/// async fn main() -> Result<()> {
+///     let client = LoginClient::new("https://play.example.com/~~example/".parse().unwrap());
+///     let mut stream = Stream::connect("example").await?;
+///     while let Some(message) = stream.next().await {
+///         let message = message?;
+///         if let Kind::Challenge(challenge) = message.kind() {
+///             client
+///                 .login_with_password(challenge, &mut stream, "username", "password")
+///                 .await?;
+///         }
+///     }
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct LoginClient {
+    client: Client,
+    base_url: Url,
+}
+
+impl LoginClient {
+    /// Creates a client for a login server.
+    ///
+    /// Requests are sent to `action.php` relative to `base_url`, so it
+    /// should end with a slash.
+    pub fn new(base_url: Url) -> Self {
+        Self::with_client(Client::new(), base_url)
+    }
+
+    /// Creates a client using an existing HTTP client.
+    pub fn with_client(client: Client, base_url: Url) -> Self {
+        Self { client, base_url }
+    }
+
+    pub fn base_url(&self) -> &Url {
+        &self.base_url
+    }
+
+    /// Gets an assertion for an unregistered user.
+    ///
+    /// Returns `None` when the user is registered and password is
+    /// required.
+    pub async fn assertion(
+        &self,
+        challenge: &Challenge<'_>,
+        login: &str,
+    ) -> Result<Option<String>> {
+        let response = self
+            .post(&[
+                ("act", "getassertion"),
+                ("userid", login),
+                ("challstr", &challenge.0),
+            ])
+            .await?;
+        if response == ";" {
+            Ok(None)
+        } else if let Some(reason) = response.strip_prefix(";;") {
+            Err(Error(ErrorInner::LoginRejected(reason.into())))
+        } else {
+            Ok(Some(response))
+        }
+    }
+
+    /// Gets an assertion for a registered user.
+    pub async fn password_assertion(
+        &self,
+        challenge: &Challenge<'_>,
+        login: &str,
+        password: &str,
+    ) -> Result<String> {
+        let response = self
+            .post(&[
+                ("act", "login"),
+                ("name", login),
+                ("pass", password),
+                ("challstr", &challenge.0),
+            ])
+            .await?;
+        let json = response.strip_prefix(']').unwrap_or(&response);
+        let LoginServerResponse { assertion } =
+            serde_json::from_str(json).map_err(|e| Error(ErrorInner::Json(e)))?;
+        match assertion.strip_prefix(";;") {
+            Some(reason) => Err(Error(ErrorInner::LoginRejected(reason.into()))),
+            None => Ok(assertion.into_owned()),
+        }
+    }
+
+    /// Logs in an user, see [`Challenge::login`].
+    pub async fn login<'a>(
+        &self,
+        challenge: Challenge<'a>,
+        stream: &'a mut Stream,
+        login: &'a str,
+    ) -> Result<Option<PasswordRequired<'a>>> {
+        match self.assertion(&challenge, login).await? {
+            Some(assertion) => {
+                stream.send(trn(login, &assertion)).await?;
+                Ok(None)
+            }
+            None => Ok(Some(PasswordRequired {
+                client: self.clone(),
+                challstr: challenge,
+                login,
+                stream,
+            })),
+        }
+    }
+
+    /// Logs in an user with a password, see
+    /// [`Challenge::login_with_password`].
+    pub async fn login_with_password(
+        &self,
+        challenge: Challenge<'_>,
+        stream: &mut Stream,
+        login: &str,
+        password: &str,
+    ) -> Result<()> {
+        let assertion = if password.is_empty() {
+            match self.assertion(&challenge, login).await? {
+                Some(assertion) => assertion,
+                None => return Ok(()),
+            }
+        } else {
+            self.password_assertion(&challenge, login, password).await?
+        };
+        stream.send(trn(login, &assertion)).await
+    }
+
+    async fn post(&self, form: &[(&str, &str)]) -> Result<String> {
+        let url = self
+            .base_url
+            .join("action.php")
+            .map_err(|e| Error(ErrorInner::Url(e)))?;
+        self.client
+            .post(url)
+            .form(form)
+            .send()
+            .and_then(|r| r.text())
+            .await
+            .map_err(|e| Error(ErrorInner::Reqwest(e)))
+    }
+}
+
+impl Default for LoginClient {
+    /// Client for the official login server.
+    fn default() -> Self {
+        Self::new(Url::parse("https://play.pokemonshowdown.com/").unwrap())
+    }
+}
+
+fn trn(login: &str, assertion: &str) -> SendMessage {
+    SendMessage::global_command(format_args!("trn {},0,{}", login, assertion))
+}
+
+#[derive(Deserialize)]
+struct LoginServerResponse<'a> {
+    #[serde(borrow)]
+    assertion: Cow<'a, str>,
+}
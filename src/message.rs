@@ -1,13 +1,35 @@
+mod battle;
+mod encode;
+mod formats;
+mod matchmaking;
+mod request;
+mod tournament;
+mod user;
+
+pub use self::battle::{
+    BattleEvent, Boost, Details, GameType, Gender, HpStatus, Pokemon, Side, Stat, Status, Switch,
+    Tags,
+};
+pub use self::encode::ServerMessage;
+pub use self::formats::{Format, FormatList, FormatSection};
+pub use self::matchmaking::{ChallengeTo, UpdateChallenges, UpdateSearch};
+pub use self::request::{
+    ActiveRequest, BattleRequest, MaxMove, MaxMoves, MoveSlot, RequestPokemon, RequestSide, Stats,
+    ZMove,
+};
+pub use self::tournament::{
+    BracketData, BracketNode, BracketState, TableBracket, TableCell, TableHeaders, TournamentEnd,
+    TournamentEvent, TournamentResult, TournamentUpdate, TreeBracket,
+};
+pub use self::user::{Rank, User};
 #[cfg(feature = "__tls")]
-use crate::{Error, ErrorInner, Result, SendMessage};
-use crate::{RoomId, Stream};
-#[cfg(feature = "__tls")]
-use futures_util::future::TryFutureExt;
-#[cfg(feature = "__tls")]
-use futures_util::sink::SinkExt;
+use crate::login::LoginClient;
 #[cfg(feature = "__tls")]
-use reqwest::Client;
+use crate::Result;
+use crate::{RoomId, Stream};
 use serde::Deserialize;
+#[cfg(feature = "serialize")]
+use serde::Serialize;
 use std::borrow::Cow;
 use std::fmt::Debug;
 use std::iter::FusedIterator;
@@ -16,7 +38,9 @@ use std::str;
 use time::OffsetDateTime;
 
 /// Owned message type
-#[derive(Debug)]
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serialize", serde(transparent))]
 pub struct Message {
     pub(crate) raw: String,
 }
@@ -37,44 +61,149 @@ impl Message {
     }
 
     pub fn kind(&self) -> Kind<'_> {
-        let message = self.parts().1;
-        if let Some(message) = message.strip_prefix('|') {
-            let (command, arg) = split2(message);
-            Kind::parse(command, arg).unwrap_or(Kind::Unrecognized(UnrecognizedMessage(message)))
+        parse_line(self.parts().1)
+    }
+
+    /// Checks whether the message is sent after leaving a room.
+    pub(crate) fn is_deinit(&self) -> bool {
+        self.parts().1.starts_with("|deinit")
+    }
+
+    /// Parses every line of a message separately.
+    ///
+    /// Showdown may send multiple lines in a single message, for instance
+    /// when joining a room or during a battle turn, while
+    /// [`kind`](Self::kind) only parses the first line. All lines share
+    /// the [`room`](Self::room) of a message.
+    ///
+    /// Room initialization (`|init|`) is provided as [`Kind::RoomInit`],
+    /// followed by the remaining lines, without `|title|` and `|users|`
+    /// lines already included in [`RoomInit`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use showdown::message::{BattleEvent, Kind, Message};
+    ///
+    /// fn count_turns(message: &Message) -> usize {
+    ///     message
+    ///         .kinds()
+    ///         .filter(|kind| matches!(kind, Kind::Battle(BattleEvent::Turn(_))))
+    ///         .count()
+    /// }
+    /// ```
+    pub fn kinds(&self) -> Kinds<'_> {
+        let content = self.parts().1;
+        let mut lines = content.split('\n');
+        let init = if content.starts_with("|init|") {
+            lines.next();
+            Some(self.kind())
         } else {
-            Kind::Unrecognized(UnrecognizedMessage(message))
+            None
+        };
+        let skip_init_lines = matches!(init, Some(Kind::RoomInit(_)));
+        Kinds {
+            init,
+            skip_init_lines,
+            lines,
+        }
+    }
+}
+
+/// Iterator over lines of a message, created by [`Message::kinds`].
+#[derive(Debug)]
+pub struct Kinds<'a> {
+    init: Option<Kind<'a>>,
+    skip_init_lines: bool,
+    lines: str::Split<'a, char>,
+}
+
+impl<'a> Iterator for Kinds<'a> {
+    type Item = Kind<'a>;
+
+    fn next(&mut self) -> Option<Kind<'a>> {
+        if let Some(init) = self.init.take() {
+            return Some(init);
         }
+        let skip_init_lines = self.skip_init_lines;
+        self.lines
+            .find(|line| !line.is_empty() && (!skip_init_lines || !is_init_line(line)))
+            .map(parse_line)
     }
 }
 
+impl FusedIterator for Kinds<'_> {}
+
+/// Checks whether a line is a part of [`RoomInit`].
+fn is_init_line(line: &str) -> bool {
+    matches!(
+        line.strip_prefix('|').map(|line| split2(line).0),
+        Some("title" | "users")
+    )
+}
+
+fn parse_line(message: &str) -> Kind<'_> {
+    message
+        .strip_prefix('|')
+        .and_then(|line| {
+            let (command, arg) = split2(line);
+            Kind::parse(command, arg)
+        })
+        .unwrap_or_else(|| Kind::Unrecognized(UnrecognizedMessage(message.into())))
+}
+
 fn split2(arg: &str) -> (&str, &str) {
     let mut parts = arg.splitn(2, '|');
     (parts.next().unwrap(), parts.next().unwrap_or(""))
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 #[non_exhaustive]
 /// Showdown message kind.
 ///
 /// This structure was designed to be matched on. For performance
 /// reasons, it's borrowing string slices from `Message`, trying
 /// to use this structure when `Message` is not in scope will
-/// cause borrow checker failures.
+/// cause borrow checker failures. Use [`Kind::into_owned`] to get
+/// a `Kind` that doesn't borrow from `Message`, for instance to send
+/// it to another task.
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serialize",
+    serde(tag = "type", content = "data", rename_all = "snake_case")
+)]
 pub enum Kind<'a> {
     Chat(Chat<'a>),
     Private(Private<'a>),
-    Join(&'a str),
-    NicknameChange(&'a str, &'a str),
-    Leave(&'a str),
+    /// User joined a room.
+    ///
+    /// Parsed from both `J` and `j`, which is sent in rooms where join
+    /// messages are hidden.
+    Join(User<'a>),
+    /// User changed their name, the second field is a previous userid.
+    NicknameChange(User<'a>, Cow<'a, str>),
+    Leave(User<'a>),
     /// Login challenge.
     ///
     /// This can be used to authenticate.
     Challenge(Challenge<'a>),
-    Html(&'a str),
+    Html(Cow<'a, str>),
     NoInit(NoInit<'a>),
     RoomInit(RoomInit<'a>),
-    QueryResponse(QueryResponse<'a>),
+    QueryResponse(#[cfg_attr(feature = "serialize", serde(borrow))] QueryResponse<'a>),
     UpdateUser(UpdateUser<'a>),
+    /// Challenges sent and received by the user.
+    UpdateChallenges(#[cfg_attr(feature = "serialize", serde(borrow))] UpdateChallenges<'a>),
+    /// Ladder searches and battles of the user.
+    UpdateSearch(#[cfg_attr(feature = "serialize", serde(borrow))] UpdateSearch<'a>),
+    /// Formats available on the server.
+    Formats(#[cfg_attr(feature = "serialize", serde(borrow))] FormatList<'a>),
+    /// Tournament protocol message.
+    Tournament(TournamentEvent<'a>),
+    /// Battle protocol message.
+    Battle(BattleEvent<'a>),
+    /// Battle decision request.
+    Request(#[cfg_attr(feature = "serialize", serde(borrow))] BattleRequest<'a>),
     Unrecognized(UnrecognizedMessage<'a>),
 }
 
@@ -83,40 +212,94 @@ impl Kind<'_> {
         Some(match command {
             "c:" => Kind::Chat(Chat::parse(arguments)),
             "pm" => Kind::Private(Private::parse(arguments)),
-            "J" => Kind::Join(arguments),
-            "N" => {
+            "J" | "j" => Kind::Join(User::new(arguments)),
+            "N" | "n" => {
                 let (a, b) = split2(arguments);
-                Kind::NicknameChange(a, b)
+                Kind::NicknameChange(User::new(a), b.into())
             }
-            "L" => Kind::Leave(arguments),
-            "challstr" => Kind::Challenge(Challenge(arguments)),
-            "html" => Kind::Html(arguments),
+            "L" | "l" => Kind::Leave(User::new(arguments)),
+            "challstr" => Kind::Challenge(Challenge(arguments.into())),
+            "html" => Kind::Html(arguments.into()),
             "init" => Kind::RoomInit(RoomInit::parse(arguments)?),
             "noinit" => Kind::NoInit(NoInit::parse(arguments)?),
             "queryresponse" => Kind::QueryResponse(QueryResponse::parse(arguments)?),
             "updateuser" => Kind::UpdateUser(UpdateUser::parse(arguments)?),
-            _ => return None,
+            "updatechallenges" => Kind::UpdateChallenges(UpdateChallenges::parse(arguments)?),
+            "updatesearch" => Kind::UpdateSearch(UpdateSearch::parse(arguments)?),
+            "formats" => Kind::Formats(FormatList::parse(arguments)),
+            "tournament" => Kind::Tournament(TournamentEvent::parse(arguments)?),
+            "request" => Kind::Request(BattleRequest::parse(arguments)?),
+            _ => Kind::Battle(BattleEvent::parse(command, arguments)?),
         })
     }
+
+    /// Converts borrowed strings into owned ones.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use showdown::message::{Kind, Message};
+    /// use std::sync::mpsc::Sender;
+    ///
+    /// fn forward(message: &Message, sender: &Sender<Kind<'static>>) {
+    ///     for kind in message.kinds() {
+    ///         sender.send(kind.into_owned()).unwrap();
+    ///     }
+    /// }
+    /// ```
+    pub fn into_owned(self) -> Kind<'static> {
+        match self {
+            Kind::Chat(chat) => Kind::Chat(chat.into_owned()),
+            Kind::Private(private) => Kind::Private(private.into_owned()),
+            Kind::Join(user) => Kind::Join(user.into_owned()),
+            Kind::NicknameChange(new, old) => Kind::NicknameChange(new.into_owned(), owned(old)),
+            Kind::Leave(user) => Kind::Leave(user.into_owned()),
+            Kind::Challenge(challenge) => Kind::Challenge(challenge.into_owned()),
+            Kind::Html(html) => Kind::Html(owned(html)),
+            Kind::NoInit(no_init) => Kind::NoInit(no_init.into_owned()),
+            Kind::RoomInit(room_init) => Kind::RoomInit(room_init.into_owned()),
+            Kind::QueryResponse(response) => Kind::QueryResponse(response.into_owned()),
+            Kind::UpdateUser(update_user) => Kind::UpdateUser(update_user.into_owned()),
+            Kind::UpdateChallenges(challenges) => Kind::UpdateChallenges(challenges.into_owned()),
+            Kind::UpdateSearch(search) => Kind::UpdateSearch(search.into_owned()),
+            Kind::Formats(formats) => Kind::Formats(formats.into_owned()),
+            Kind::Tournament(event) => Kind::Tournament(event.into_owned()),
+            Kind::Battle(event) => Kind::Battle(event.into_owned()),
+            Kind::Request(request) => Kind::Request(request.into_owned()),
+            Kind::Unrecognized(message) => Kind::Unrecognized(message.into_owned()),
+        }
+    }
+}
+
+fn owned(s: Cow<'_, str>) -> Cow<'static, str> {
+    Cow::Owned(s.into_owned())
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub struct Chat<'a> {
-    #[cfg(feature = "time")]
-    timestamp: &'a str,
-    user: &'a str,
-    message: &'a str,
+    #[cfg_attr(not(feature = "time"), allow(dead_code))]
+    timestamp: Cow<'a, str>,
+    user: Cow<'a, str>,
+    message: Cow<'a, str>,
 }
 
 impl<'a> Chat<'a> {
     fn parse(arguments: &'a str) -> Self {
-        let (_timestamp, arguments) = split2(arguments);
+        let (timestamp, arguments) = split2(arguments);
         let (user, message) = split2(arguments);
         Self {
-            #[cfg(feature = "time")]
-            timestamp: _timestamp,
-            user,
-            message,
+            timestamp: timestamp.into(),
+            user: user.into(),
+            message: message.into(),
+        }
+    }
+
+    pub fn into_owned(self) -> Chat<'static> {
+        Chat {
+            timestamp: owned(self.timestamp),
+            user: owned(self.user),
+            message: owned(self.message),
         }
     }
 
@@ -126,36 +309,63 @@ impl<'a> Chat<'a> {
         OffsetDateTime::from_unix_timestamp(self.timestamp.parse().unwrap()).unwrap()
     }
 
-    pub fn user(&self) -> &'a str {
-        self.user
+    pub fn user(&self) -> User<'a> {
+        User::new(self.user.clone())
     }
 
-    pub fn message(&self) -> &'a str {
-        self.message.strip_suffix('\n').unwrap_or(self.message)
+    pub fn message(&self) -> Cow<'a, str> {
+        match &self.message {
+            Cow::Borrowed(message) => message.strip_suffix('\n').unwrap_or(message).into(),
+            Cow::Owned(message) => message
+                .strip_suffix('\n')
+                .unwrap_or(message)
+                .to_owned()
+                .into(),
+        }
     }
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub struct Private<'a> {
-    pub from: &'a str,
-    pub to: &'a str,
-    pub message: &'a str,
+    pub from: User<'a>,
+    pub to: User<'a>,
+    pub message: Cow<'a, str>,
 }
 
 impl<'a> Private<'a> {
     fn parse(arguments: &'a str) -> Self {
         let (from, arguments) = split2(arguments);
         let (to, message) = split2(arguments);
-        Self { from, to, message }
+        Self {
+            from: User::new(from),
+            to: User::new(to),
+            message: message.into(),
+        }
+    }
+
+    pub fn into_owned(self) -> Private<'static> {
+        Private {
+            from: self.from.into_owned(),
+            to: self.to.into_owned(),
+            message: owned(self.message),
+        }
     }
 }
 
 /// Login challenge.
-#[derive(Copy, Clone, Debug)]
-pub struct Challenge<'a>(&'a str);
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct Challenge<'a>(pub(crate) Cow<'a, str>);
 
 impl<'a> Challenge<'a> {
-    /// Logs in an user.
+    pub fn into_owned(self) -> Challenge<'static> {
+        Challenge(owned(self.0))
+    }
+
+    /// Logs in an user using the official login server.
+    ///
+    /// Use [`LoginClient`] to log in to other servers.
     ///
     /// Requires `native-tls`, `native-tls-vendored` or `rustls-tls` feature.
     ///
@@ -213,29 +423,13 @@ impl<'a> Challenge<'a> {
         stream: &'a mut Stream,
         login: &'a str,
     ) -> Result<Option<PasswordRequired<'a>>> {
-        let response = Client::new()
-            .post("https://play.pokemonshowdown.com/action.php")
-            .form(&[
-                ("act", "getassertion"),
-                ("userid", login),
-                ("challstr", self.0),
-            ])
-            .send()
-            .and_then(|r| r.text())
-            .await
-            .map_err(|e| Error(ErrorInner::Reqwest(e)))?;
-        if response == ";" {
-            Ok(Some(PasswordRequired {
-                challstr: self,
-                login,
-                stream,
-            }))
-        } else {
-            let command = SendMessage::global_command(format_args!("trn {},0,{}", login, response));
-            stream.send(command).await.map(|()| None)
-        }
+        LoginClient::default().login(self, stream, login).await
     }
 
+    /// Logs in an user with a password using the official login server.
+    ///
+    /// Use [`LoginClient`] to log in to other servers.
+    ///
     /// Requires `native-tls`, `native-tls-vendored` or `rustls-tls` feature.
     #[cfg(feature = "__tls")]
     pub async fn login_with_password(
@@ -244,57 +438,37 @@ impl<'a> Challenge<'a> {
         login: &str,
         password: &str,
     ) -> Result<()> {
-        if password.is_empty() {
-            return self.login(sender, login).await.map(|_| ());
-        }
-        let response = Client::new()
-            .post("https://play.pokemonshowdown.com/action.php")
-            .form(&[
-                ("act", "login"),
-                ("name", login),
-                ("pass", password),
-                ("challstr", self.0),
-            ])
-            .send()
-            .and_then(|r| r.bytes())
+        LoginClient::default()
+            .login_with_password(self, sender, login, password)
             .await
-            .map_err(|e| Error(ErrorInner::Reqwest(e)))?;
-        let LoginServerResponse { assertion } =
-            serde_json::from_slice(&response[1..]).map_err(|e| Error(ErrorInner::Json(e)))?;
-        let command = SendMessage::global_command(format_args!("trn {},0,{}", login, assertion));
-        sender.send(command).await
     }
 }
 
 #[derive(Debug)]
 pub struct PasswordRequired<'a> {
-    challstr: Challenge<'a>,
-    login: &'a str,
-    stream: &'a mut Stream,
+    #[cfg(feature = "__tls")]
+    pub(crate) client: LoginClient,
+    pub(crate) challstr: Challenge<'a>,
+    pub(crate) login: &'a str,
+    pub(crate) stream: &'a mut Stream,
 }
 
 impl PasswordRequired<'_> {
     /// Requires `native-tls`, `native-tls-vendored` or `rustls-tls` feature.
     #[cfg(feature = "__tls")]
     pub async fn login_with_password(&mut self, password: &str) -> Result<()> {
-        self.challstr
-            .login_with_password(self.stream, self.login, password)
+        self.client
+            .login_with_password(self.challstr.clone(), self.stream, self.login, password)
             .await
     }
 }
 
-#[cfg(feature = "__tls")]
-#[derive(Deserialize)]
-struct LoginServerResponse<'a> {
-    #[serde(borrow)]
-    assertion: Cow<'a, str>,
-}
-
-#[derive(Debug)]
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub struct RoomInit<'a> {
     pub room_type: RoomType,
-    pub title: &'a str,
-    pub users: &'a str,
+    pub title: Cow<'a, str>,
+    pub users: RoomUsers<'a>,
 }
 
 impl RoomInit<'_> {
@@ -323,16 +497,50 @@ impl RoomInit<'_> {
         }
         Some(RoomInit {
             room_type,
-            title: title?,
-            users: users?,
+            title: title?.into(),
+            users: RoomUsers::parse(users?)?,
         })
     }
+
+    pub fn into_owned(self) -> RoomInit<'static> {
+        RoomInit {
+            room_type: self.room_type,
+            title: owned(self.title),
+            users: self.users.into_owned(),
+        }
+    }
+}
+
+/// Users in a room, as sent when joining a room.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct RoomUsers<'a> {
+    /// Number of users, including guests not present in `users`.
+    pub count: u32,
+    pub users: Vec<User<'a>>,
+}
+
+impl<'a> RoomUsers<'a> {
+    fn parse(arguments: &'a str) -> Option<Self> {
+        let mut parts = arguments.split(',');
+        let count = parts.next()?.parse().ok()?;
+        let users = parts.filter(|u| !u.is_empty()).map(User::new).collect();
+        Some(Self { count, users })
+    }
+
+    pub fn into_owned(self) -> RoomUsers<'static> {
+        RoomUsers {
+            count: self.count,
+            users: self.users.into_iter().map(User::into_owned).collect(),
+        }
+    }
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub struct NoInit<'a> {
     pub kind: NoInitKind,
-    pub reason: &'a str,
+    pub reason: Cow<'a, str>,
 }
 
 impl<'a> NoInit<'a> {
@@ -340,12 +548,21 @@ impl<'a> NoInit<'a> {
         let (kind, reason) = split2(arguments);
         Some(Self {
             kind: NoInitKind::parse(kind)?,
-            reason,
+            reason: reason.into(),
         })
     }
+
+    pub fn into_owned(self) -> NoInit<'static> {
+        NoInit {
+            kind: self.kind,
+            reason: owned(self.reason),
+        }
+    }
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serialize", serde(rename_all = "snake_case"))]
 pub enum NoInitKind {
     Nonexistent,
     JoinFailed,
@@ -363,16 +580,23 @@ impl NoInitKind {
     }
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serialize", serde(rename_all = "snake_case"))]
 pub enum RoomType {
     Chat,
     Battle,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 #[non_exhaustive]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serialize",
+    serde(tag = "type", content = "data", rename_all = "snake_case")
+)]
 pub enum QueryResponse<'a> {
-    Rooms(RoomsList<'a>),
+    Rooms(#[cfg_attr(feature = "serialize", serde(borrow))] RoomsList<'a>),
 }
 
 impl QueryResponse<'_> {
@@ -383,9 +607,16 @@ impl QueryResponse<'_> {
             _ => None,
         }
     }
+
+    pub fn into_owned(self) -> QueryResponse<'static> {
+        match self {
+            QueryResponse::Rooms(rooms) => QueryResponse::Rooms(rooms.into_owned()),
+        }
+    }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
 #[serde(rename_all = "camelCase")]
 pub struct RoomsList<'a> {
     #[serde(borrow)]
@@ -408,9 +639,23 @@ impl<'a> RoomsList<'a> {
     ) -> impl Iterator<Item = &Room<'a>> + Clone + Debug + DoubleEndedIterator + FusedIterator {
         self.official.iter().chain(&self.pspl).chain(&self.chat)
     }
+
+    pub fn into_owned(self) -> RoomsList<'static> {
+        fn rooms(rooms: Vec<Room<'_>>) -> Vec<Room<'static>> {
+            rooms.into_iter().map(Room::into_owned).collect()
+        }
+        RoomsList {
+            official: rooms(self.official),
+            pspl: rooms(self.pspl),
+            chat: rooms(self.chat),
+            user_count: self.user_count,
+            battle_count: self.battle_count,
+        }
+    }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
 #[serde(rename_all = "camelCase")]
 pub struct Room<'a> {
     #[serde(borrow)]
@@ -422,11 +667,23 @@ pub struct Room<'a> {
     pub sub_rooms: Vec<Cow<'a, str>>,
 }
 
-#[derive(Copy, Clone, Debug)]
+impl Room<'_> {
+    pub fn into_owned(self) -> Room<'static> {
+        Room {
+            title: owned(self.title),
+            desc: owned(self.desc),
+            user_count: self.user_count,
+            sub_rooms: self.sub_rooms.into_iter().map(owned).collect(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub struct UpdateUser<'a> {
-    pub username: &'a str,
+    pub username: Cow<'a, str>,
     pub named: bool,
-    pub avatar: &'a str,
+    pub avatar: Cow<'a, str>,
 }
 
 impl<'a> UpdateUser<'a> {
@@ -440,19 +697,34 @@ impl<'a> UpdateUser<'a> {
         };
         let avatar = parts.next()?.split('\n').next().unwrap();
         Some(Self {
-            username,
+            username: username.into(),
             named,
-            avatar,
+            avatar: avatar.into(),
         })
     }
+
+    pub fn into_owned(self) -> UpdateUser<'static> {
+        UpdateUser {
+            username: owned(self.username),
+            named: self.named,
+            avatar: owned(self.avatar),
+        }
+    }
 }
 
-#[derive(Debug)]
-pub struct UnrecognizedMessage<'a>(&'a str);
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct UnrecognizedMessage<'a>(Cow<'a, str>);
+
+impl UnrecognizedMessage<'_> {
+    pub fn into_owned(self) -> UnrecognizedMessage<'static> {
+        UnrecognizedMessage(owned(self.0))
+    }
+}
 
 #[cfg(test)]
 mod test {
-    use super::{Kind, Message};
+    use super::{parse_line, BattleEvent, Kind, Message, RoomInit, RoomType, RoomUsers, User};
 
     #[test]
     fn parse_join() {
@@ -461,7 +733,7 @@ mod test {
                 raw: "|J|+xfix".into()
             }
             .kind(),
-            Kind::Join("+xfix")
+            Kind::Join(user) if user.symbol() == '+' && user.name() == "xfix"
         ));
     }
 
@@ -472,7 +744,7 @@ mod test {
                 raw: "|N|+xfix|@xfix".into()
             }
             .kind(),
-            Kind::NicknameChange("+xfix", "@xfix")
+            Kind::NicknameChange(new, old) if new.symbol() == '+' && new.name() == "xfix" && old == "@xfix"
         ));
     }
 
@@ -483,7 +755,100 @@ mod test {
                 raw: "|L|+xfix".into()
             }
             .kind(),
-            Kind::Leave("+xfix")
+            Kind::Leave(user) if user.symbol() == '+' && user.name() == "xfix"
+        ));
+    }
+
+    #[test]
+    fn chat_outlives_kind() {
+        let message = Message {
+            raw: ">lobby\n|c:|1634571729|+xfix|Hello\n".into(),
+        };
+        let (user, text) = match message.kind() {
+            Kind::Chat(chat) => (chat.user(), chat.message()),
+            kind => panic!("{:?}", kind),
+        };
+        assert_eq!(user, "+xfix");
+        assert_eq!(text, "Hello");
+    }
+
+    #[test]
+    fn parse_room_users() {
+        let message = Message {
+            raw: ">lobby\n|init|chat\n|title|Lobby\n|users|4,@alice,+bob@!, carol".into(),
+        };
+        match message.kind() {
+            Kind::RoomInit(init) => {
+                assert_eq!(init.users.count, 4);
+                let names: Vec<_> = init.users.users.iter().map(|u| u.name()).collect();
+                assert_eq!(names, ["alice", "bob", "carol"]);
+                assert!(init.users.users[1].is_away());
+            }
+            kind => panic!("{:?}", kind),
+        }
+    }
+
+    #[test]
+    fn parse_lowercase_leave() {
+        assert!(matches!(
+            Message {
+                raw: "|l|+xfix".into()
+            }
+            .kind(),
+            Kind::Leave(user) if user.name() == "xfix"
         ));
     }
+
+    #[test]
+    fn parse_all_lines() {
+        let message = Message {
+            raw: ">battle-gen9ou-1\n|init|battle\n|title|xfix vs. Someone\n|users|1,☆xfix\n|j|☆xfix\n|\n|turn|1\n|move|p1a: Pikachu|Thunderbolt|p2a: Garchomp\n|-immune|p2a: Garchomp\n"
+                .into(),
+        };
+        let kinds: Vec<_> = message.kinds().collect();
+        assert_eq!(
+            kinds,
+            [
+                Kind::RoomInit(RoomInit {
+                    room_type: RoomType::Battle,
+                    title: "xfix vs. Someone".into(),
+                    users: RoomUsers {
+                        count: 1,
+                        users: vec![User::new("☆xfix")],
+                    },
+                }),
+                Kind::Join(User::new("☆xfix")),
+                parse_line("|"),
+                Kind::Battle(BattleEvent::Turn(1)),
+                parse_line("|move|p1a: Pikachu|Thunderbolt|p2a: Garchomp"),
+                parse_line("|-immune|p2a: Garchomp"),
+            ],
+        );
+    }
+
+    #[test]
+    fn into_owned_outlives_message() {
+        fn assert_send<T: Send + Clone + 'static>(_: &T) {}
+        let message = Message {
+            raw: ">battle-gen9ou-1\n|move|p1a: Pikachu|Thunderbolt|p2a: Garchomp|[miss]".into(),
+        };
+        assert_send(&message);
+        let kind = message.kind().into_owned();
+        drop(message);
+        assert_send(&kind);
+        match kind {
+            Kind::Battle(BattleEvent::Move {
+                source,
+                name,
+                target,
+                tags,
+            }) => {
+                assert_eq!(source.name, "Pikachu");
+                assert_eq!(name, "Thunderbolt");
+                assert_eq!(target.unwrap().name, "Garchomp");
+                assert!(tags.contains("miss"));
+            }
+            kind => panic!("{:?}", kind),
+        }
+    }
 }
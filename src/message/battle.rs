@@ -0,0 +1,1631 @@
+use super::owned;
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::fmt::{self, Display, Formatter};
+use std::str::{self, FromStr};
+
+/// Battle protocol message.
+///
+/// Those are the lines sent to battle rooms, as described in Pokémon
+/// Showdown's `SIM-PROTOCOL.md`. Arguments that are not needed to
+/// understand a message (like `[from] item: Leftovers`) are available
+/// through [`Tags`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serialize",
+    serde(tag = "type", content = "data", rename_all = "snake_case")
+)]
+pub enum BattleEvent<'a> {
+    Player {
+        side: Side,
+        username: Cow<'a, str>,
+        avatar: Cow<'a, str>,
+        rating: Option<Cow<'a, str>>,
+    },
+    TeamSize {
+        side: Side,
+        size: u8,
+    },
+    GameType(GameType),
+    Gen(u8),
+    Tier(Cow<'a, str>),
+    Rated(Option<Cow<'a, str>>),
+    Rule(Cow<'a, str>),
+    ClearPoke,
+    Poke {
+        side: Side,
+        details: Details<'a>,
+        has_item: bool,
+    },
+    TeamPreview(Option<u8>),
+    Start,
+    Turn(u32),
+    Win(Cow<'a, str>),
+    Tie,
+    Upkeep,
+    Timestamp(i64),
+    Inactive(Cow<'a, str>),
+    InactiveOff(Cow<'a, str>),
+    Move {
+        source: Pokemon<'a>,
+        name: Cow<'a, str>,
+        target: Option<Pokemon<'a>>,
+        tags: Tags<'a>,
+    },
+    Switch(Switch<'a>),
+    Drag(Switch<'a>),
+    Replace(Switch<'a>),
+    DetailsChange(Switch<'a>),
+    FormeChange {
+        pokemon: Pokemon<'a>,
+        species: Cow<'a, str>,
+        hp_status: Option<HpStatus>,
+        tags: Tags<'a>,
+    },
+    Swap {
+        pokemon: Pokemon<'a>,
+        position: u8,
+        tags: Tags<'a>,
+    },
+    Cant {
+        pokemon: Pokemon<'a>,
+        reason: Cow<'a, str>,
+        move_name: Option<Cow<'a, str>>,
+        tags: Tags<'a>,
+    },
+    Faint(Pokemon<'a>),
+    Fail {
+        pokemon: Pokemon<'a>,
+        action: Option<Cow<'a, str>>,
+        tags: Tags<'a>,
+    },
+    Block {
+        pokemon: Pokemon<'a>,
+        effect: Cow<'a, str>,
+        tags: Tags<'a>,
+    },
+    NoTarget(Option<Pokemon<'a>>),
+    Miss {
+        source: Pokemon<'a>,
+        target: Option<Pokemon<'a>>,
+        tags: Tags<'a>,
+    },
+    Damage {
+        pokemon: Pokemon<'a>,
+        hp_status: HpStatus,
+        tags: Tags<'a>,
+    },
+    Heal {
+        pokemon: Pokemon<'a>,
+        hp_status: HpStatus,
+        tags: Tags<'a>,
+    },
+    SetHp {
+        pokemon: Pokemon<'a>,
+        hp_status: HpStatus,
+        tags: Tags<'a>,
+    },
+    Status {
+        pokemon: Pokemon<'a>,
+        status: Status,
+        tags: Tags<'a>,
+    },
+    CureStatus {
+        pokemon: Pokemon<'a>,
+        status: Status,
+        tags: Tags<'a>,
+    },
+    CureTeam {
+        pokemon: Pokemon<'a>,
+        tags: Tags<'a>,
+    },
+    Boost(Boost<'a>),
+    Unboost(Boost<'a>),
+    SetBoost(Boost<'a>),
+    SwapBoost {
+        source: Pokemon<'a>,
+        target: Pokemon<'a>,
+        stats: Option<Cow<'a, str>>,
+        tags: Tags<'a>,
+    },
+    InvertBoost {
+        pokemon: Pokemon<'a>,
+        tags: Tags<'a>,
+    },
+    ClearBoost {
+        pokemon: Pokemon<'a>,
+        tags: Tags<'a>,
+    },
+    ClearAllBoost {
+        tags: Tags<'a>,
+    },
+    ClearPositiveBoost {
+        target: Pokemon<'a>,
+        pokemon: Pokemon<'a>,
+        effect: Cow<'a, str>,
+        tags: Tags<'a>,
+    },
+    ClearNegativeBoost {
+        pokemon: Pokemon<'a>,
+        tags: Tags<'a>,
+    },
+    CopyBoost {
+        source: Pokemon<'a>,
+        target: Pokemon<'a>,
+        tags: Tags<'a>,
+    },
+    Weather {
+        weather: Cow<'a, str>,
+        tags: Tags<'a>,
+    },
+    FieldStart {
+        condition: Cow<'a, str>,
+        tags: Tags<'a>,
+    },
+    FieldEnd {
+        condition: Cow<'a, str>,
+        tags: Tags<'a>,
+    },
+    SideStart {
+        side: Side,
+        /// Name of a player the side belongs to.
+        player: Cow<'a, str>,
+        condition: Cow<'a, str>,
+        tags: Tags<'a>,
+    },
+    SideEnd {
+        side: Side,
+        /// Name of a player the side belongs to.
+        player: Cow<'a, str>,
+        condition: Cow<'a, str>,
+        tags: Tags<'a>,
+    },
+    SwapSideConditions,
+    /// Volatile status started, `|-start|`.
+    StartEffect {
+        pokemon: Pokemon<'a>,
+        effect: Cow<'a, str>,
+        tags: Tags<'a>,
+    },
+    /// Volatile status ended, `|-end|`.
+    EndEffect {
+        pokemon: Pokemon<'a>,
+        effect: Cow<'a, str>,
+        tags: Tags<'a>,
+    },
+    Crit(Pokemon<'a>),
+    SuperEffective(Pokemon<'a>),
+    Resisted(Pokemon<'a>),
+    Immune {
+        pokemon: Pokemon<'a>,
+        tags: Tags<'a>,
+    },
+    Item {
+        pokemon: Pokemon<'a>,
+        item: Cow<'a, str>,
+        tags: Tags<'a>,
+    },
+    EndItem {
+        pokemon: Pokemon<'a>,
+        item: Cow<'a, str>,
+        tags: Tags<'a>,
+    },
+    Ability {
+        pokemon: Pokemon<'a>,
+        ability: Cow<'a, str>,
+        tags: Tags<'a>,
+    },
+    EndAbility {
+        pokemon: Pokemon<'a>,
+        tags: Tags<'a>,
+    },
+    Transform {
+        pokemon: Pokemon<'a>,
+        target: Pokemon<'a>,
+        tags: Tags<'a>,
+    },
+    Mega {
+        pokemon: Pokemon<'a>,
+        mega_stone: Option<Cow<'a, str>>,
+    },
+    Primal(Pokemon<'a>),
+    Burst {
+        pokemon: Pokemon<'a>,
+        species: Cow<'a, str>,
+        item: Option<Cow<'a, str>>,
+    },
+    ZPower(Pokemon<'a>),
+    ZBroken(Pokemon<'a>),
+    Terastallize {
+        pokemon: Pokemon<'a>,
+        tera_type: Cow<'a, str>,
+    },
+    Activate {
+        pokemon: Option<Pokemon<'a>>,
+        effect: Cow<'a, str>,
+        tags: Tags<'a>,
+    },
+    Hint(Cow<'a, str>),
+    Center,
+    Message(Cow<'a, str>),
+    Combine,
+    Waiting {
+        source: Pokemon<'a>,
+        target: Pokemon<'a>,
+    },
+    Prepare {
+        attacker: Pokemon<'a>,
+        move_name: Cow<'a, str>,
+        defender: Option<Pokemon<'a>>,
+    },
+    MustRecharge(Pokemon<'a>),
+    Nothing,
+    HitCount {
+        pokemon: Pokemon<'a>,
+        count: u8,
+    },
+    SingleMove {
+        pokemon: Pokemon<'a>,
+        move_name: Cow<'a, str>,
+        tags: Tags<'a>,
+    },
+    SingleTurn {
+        pokemon: Pokemon<'a>,
+        move_name: Cow<'a, str>,
+        tags: Tags<'a>,
+    },
+}
+
+impl<'a> BattleEvent<'a> {
+    pub(super) fn parse(command: &str, arguments: &'a str) -> Option<Self> {
+        let arguments = arguments.split('\n').next().unwrap();
+        // Those messages may contain brackets, so they aren't split into tags
+        match command {
+            "inactive" => return Some(BattleEvent::Inactive(arguments.into())),
+            "inactiveoff" => return Some(BattleEvent::InactiveOff(arguments.into())),
+            "-hint" => return Some(BattleEvent::Hint(arguments.into())),
+            "-message" => return Some(BattleEvent::Message(arguments.into())),
+            "rule" => return Some(BattleEvent::Rule(arguments.into())),
+            "tier" => return Some(BattleEvent::Tier(arguments.into())),
+            "win" => return Some(BattleEvent::Win(arguments.into())),
+            _ => {}
+        }
+        let (positional, tags) = split_tags(arguments);
+        let mut args = Args(positional.split('|'));
+        Some(match command {
+            "player" => BattleEvent::Player {
+                side: args.parse()?,
+                username: args.next().unwrap_or("").into(),
+                avatar: args.next().unwrap_or("").into(),
+                rating: args.optional_text(),
+            },
+            "teamsize" => BattleEvent::TeamSize {
+                side: args.parse()?,
+                size: args.parse()?,
+            },
+            "gametype" => BattleEvent::GameType(args.parse()?),
+            "gen" => BattleEvent::Gen(args.parse()?),
+            "rated" => BattleEvent::Rated(args.optional_text()),
+            "clearpoke" => BattleEvent::ClearPoke,
+            "poke" => BattleEvent::Poke {
+                side: args.parse()?,
+                details: args.parse_with(Details::parse)?,
+                has_item: args.optional() == Some("item"),
+            },
+            "teampreview" => BattleEvent::TeamPreview(args.optional_parse()?),
+            "start" => BattleEvent::Start,
+            "turn" => BattleEvent::Turn(args.parse()?),
+            "tie" => BattleEvent::Tie,
+            "upkeep" => BattleEvent::Upkeep,
+            "t:" => BattleEvent::Timestamp(args.parse()?),
+            "move" => BattleEvent::Move {
+                source: args.pokemon()?,
+                name: args.text()?,
+                target: args.optional_pokemon()?,
+                tags,
+            },
+            "switch" => BattleEvent::Switch(Switch::parse(args)?),
+            "drag" => BattleEvent::Drag(Switch::parse(args)?),
+            "replace" => BattleEvent::Replace(Switch::parse(args)?),
+            "detailschange" => BattleEvent::DetailsChange(Switch::parse(args)?),
+            "-formechange" => BattleEvent::FormeChange {
+                pokemon: args.pokemon()?,
+                species: args.text()?,
+                hp_status: args.optional_hp_status()?,
+                tags,
+            },
+            "swap" => BattleEvent::Swap {
+                pokemon: args.pokemon()?,
+                position: args.parse()?,
+                tags,
+            },
+            "cant" => BattleEvent::Cant {
+                pokemon: args.pokemon()?,
+                reason: args.text()?,
+                move_name: args.optional_text(),
+                tags,
+            },
+            "faint" => BattleEvent::Faint(args.pokemon()?),
+            "-fail" => BattleEvent::Fail {
+                pokemon: args.pokemon()?,
+                action: args.optional_text(),
+                tags,
+            },
+            "-block" => BattleEvent::Block {
+                pokemon: args.pokemon()?,
+                effect: args.text()?,
+                tags,
+            },
+            "-notarget" => BattleEvent::NoTarget(args.optional_pokemon()?),
+            "-miss" => BattleEvent::Miss {
+                source: args.pokemon()?,
+                target: args.optional_pokemon()?,
+                tags,
+            },
+            "-damage" => BattleEvent::Damage {
+                pokemon: args.pokemon()?,
+                hp_status: args.parse_with(HpStatus::parse)?,
+                tags,
+            },
+            "-heal" => BattleEvent::Heal {
+                pokemon: args.pokemon()?,
+                hp_status: args.parse_with(HpStatus::parse)?,
+                tags,
+            },
+            "-sethp" => BattleEvent::SetHp {
+                pokemon: args.pokemon()?,
+                hp_status: args.parse_with(HpStatus::parse)?,
+                tags,
+            },
+            "-status" => BattleEvent::Status {
+                pokemon: args.pokemon()?,
+                status: args.parse()?,
+                tags,
+            },
+            "-curestatus" => BattleEvent::CureStatus {
+                pokemon: args.pokemon()?,
+                status: args.parse()?,
+                tags,
+            },
+            "-cureteam" => BattleEvent::CureTeam {
+                pokemon: args.pokemon()?,
+                tags,
+            },
+            "-boost" => BattleEvent::Boost(Boost::parse(args, tags)?),
+            "-unboost" => BattleEvent::Unboost(Boost::parse(args, tags)?),
+            "-setboost" => BattleEvent::SetBoost(Boost::parse(args, tags)?),
+            "-swapboost" => BattleEvent::SwapBoost {
+                source: args.pokemon()?,
+                target: args.pokemon()?,
+                stats: args.optional_text(),
+                tags,
+            },
+            "-invertboost" => BattleEvent::InvertBoost {
+                pokemon: args.pokemon()?,
+                tags,
+            },
+            "-clearboost" => BattleEvent::ClearBoost {
+                pokemon: args.pokemon()?,
+                tags,
+            },
+            "-clearallboost" => BattleEvent::ClearAllBoost { tags },
+            "-clearpositiveboost" => BattleEvent::ClearPositiveBoost {
+                target: args.pokemon()?,
+                pokemon: args.pokemon()?,
+                effect: args.text()?,
+                tags,
+            },
+            "-clearnegativeboost" => BattleEvent::ClearNegativeBoost {
+                pokemon: args.pokemon()?,
+                tags,
+            },
+            "-copyboost" => BattleEvent::CopyBoost {
+                source: args.pokemon()?,
+                target: args.pokemon()?,
+                tags,
+            },
+            "-weather" => BattleEvent::Weather {
+                weather: args.text()?,
+                tags,
+            },
+            "-fieldstart" => BattleEvent::FieldStart {
+                condition: args.text()?,
+                tags,
+            },
+            "-fieldend" => BattleEvent::FieldEnd {
+                condition: args.text()?,
+                tags,
+            },
+            "-sidestart" => {
+                let (side, player) = args.parse_with(Side::parse_player)?;
+                BattleEvent::SideStart {
+                    side,
+                    player: player.into(),
+                    condition: args.text()?,
+                    tags,
+                }
+            }
+            "-sideend" => {
+                let (side, player) = args.parse_with(Side::parse_player)?;
+                BattleEvent::SideEnd {
+                    side,
+                    player: player.into(),
+                    condition: args.text()?,
+                    tags,
+                }
+            }
+            "-swapsideconditions" => BattleEvent::SwapSideConditions,
+            "-start" => BattleEvent::StartEffect {
+                pokemon: args.pokemon()?,
+                effect: args.text()?,
+                tags,
+            },
+            "-end" => BattleEvent::EndEffect {
+                pokemon: args.pokemon()?,
+                effect: args.text()?,
+                tags,
+            },
+            "-crit" => BattleEvent::Crit(args.pokemon()?),
+            "-supereffective" => BattleEvent::SuperEffective(args.pokemon()?),
+            "-resisted" => BattleEvent::Resisted(args.pokemon()?),
+            "-immune" => BattleEvent::Immune {
+                pokemon: args.pokemon()?,
+                tags,
+            },
+            "-item" => BattleEvent::Item {
+                pokemon: args.pokemon()?,
+                item: args.text()?,
+                tags,
+            },
+            "-enditem" => BattleEvent::EndItem {
+                pokemon: args.pokemon()?,
+                item: args.text()?,
+                tags,
+            },
+            "-ability" => BattleEvent::Ability {
+                pokemon: args.pokemon()?,
+                ability: args.text()?,
+                tags,
+            },
+            "-endability" => BattleEvent::EndAbility {
+                pokemon: args.pokemon()?,
+                tags,
+            },
+            "-transform" => BattleEvent::Transform {
+                pokemon: args.pokemon()?,
+                target: args.pokemon()?,
+                tags,
+            },
+            "-mega" => BattleEvent::Mega {
+                pokemon: args.pokemon()?,
+                mega_stone: args.optional_text(),
+            },
+            "-primal" => BattleEvent::Primal(args.pokemon()?),
+            "-burst" => BattleEvent::Burst {
+                pokemon: args.pokemon()?,
+                species: args.text()?,
+                item: args.optional_text(),
+            },
+            "-zpower" => BattleEvent::ZPower(args.pokemon()?),
+            "-zbroken" => BattleEvent::ZBroken(args.pokemon()?),
+            "-terastallize" => BattleEvent::Terastallize {
+                pokemon: args.pokemon()?,
+                tera_type: args.text()?,
+            },
+            "-activate" => {
+                let first = args.next()?;
+                match args.optional() {
+                    Some(effect) => BattleEvent::Activate {
+                        pokemon: optional_with(first, Pokemon::parse)?,
+                        effect: effect.into(),
+                        tags,
+                    },
+                    None => BattleEvent::Activate {
+                        pokemon: None,
+                        effect: first.into(),
+                        tags,
+                    },
+                }
+            }
+            "-center" => BattleEvent::Center,
+            "-combine" => BattleEvent::Combine,
+            "-waiting" => BattleEvent::Waiting {
+                source: args.pokemon()?,
+                target: args.pokemon()?,
+            },
+            "-prepare" => BattleEvent::Prepare {
+                attacker: args.pokemon()?,
+                move_name: args.text()?,
+                defender: args.optional_pokemon()?,
+            },
+            "-mustrecharge" => BattleEvent::MustRecharge(args.pokemon()?),
+            "-nothing" => BattleEvent::Nothing,
+            "-hitcount" => BattleEvent::HitCount {
+                pokemon: args.pokemon()?,
+                count: args.parse()?,
+            },
+            "-singlemove" => BattleEvent::SingleMove {
+                pokemon: args.pokemon()?,
+                move_name: args.text()?,
+                tags,
+            },
+            "-singleturn" => BattleEvent::SingleTurn {
+                pokemon: args.pokemon()?,
+                move_name: args.text()?,
+                tags,
+            },
+            _ => return None,
+        })
+    }
+
+    /// Converts borrowed strings into owned ones, so that the event can
+    /// outlive the [`Message`](super::Message) it was parsed from.
+    pub fn into_owned(self) -> BattleEvent<'static> {
+        match self {
+            BattleEvent::Player {
+                side,
+                username,
+                avatar,
+                rating,
+            } => BattleEvent::Player {
+                side,
+                username: owned(username),
+                avatar: owned(avatar),
+                rating: rating.map(owned),
+            },
+            BattleEvent::TeamSize { side, size } => BattleEvent::TeamSize { side, size },
+            BattleEvent::GameType(x) => BattleEvent::GameType(x),
+            BattleEvent::Gen(x) => BattleEvent::Gen(x),
+            BattleEvent::Tier(x) => BattleEvent::Tier(owned(x)),
+            BattleEvent::Rated(x) => BattleEvent::Rated(x.map(owned)),
+            BattleEvent::Rule(x) => BattleEvent::Rule(owned(x)),
+            BattleEvent::ClearPoke => BattleEvent::ClearPoke,
+            BattleEvent::Poke {
+                side,
+                details,
+                has_item,
+            } => BattleEvent::Poke {
+                side,
+                details: details.into_owned(),
+                has_item,
+            },
+            BattleEvent::TeamPreview(x) => BattleEvent::TeamPreview(x),
+            BattleEvent::Start => BattleEvent::Start,
+            BattleEvent::Turn(x) => BattleEvent::Turn(x),
+            BattleEvent::Win(x) => BattleEvent::Win(owned(x)),
+            BattleEvent::Tie => BattleEvent::Tie,
+            BattleEvent::Upkeep => BattleEvent::Upkeep,
+            BattleEvent::Timestamp(x) => BattleEvent::Timestamp(x),
+            BattleEvent::Inactive(x) => BattleEvent::Inactive(owned(x)),
+            BattleEvent::InactiveOff(x) => BattleEvent::InactiveOff(owned(x)),
+            BattleEvent::Move {
+                source,
+                name,
+                target,
+                tags,
+            } => BattleEvent::Move {
+                source: source.into_owned(),
+                name: owned(name),
+                target: target.map(Pokemon::into_owned),
+                tags: tags.into_owned(),
+            },
+            BattleEvent::Switch(x) => BattleEvent::Switch(x.into_owned()),
+            BattleEvent::Drag(x) => BattleEvent::Drag(x.into_owned()),
+            BattleEvent::Replace(x) => BattleEvent::Replace(x.into_owned()),
+            BattleEvent::DetailsChange(x) => BattleEvent::DetailsChange(x.into_owned()),
+            BattleEvent::FormeChange {
+                pokemon,
+                species,
+                hp_status,
+                tags,
+            } => BattleEvent::FormeChange {
+                pokemon: pokemon.into_owned(),
+                species: owned(species),
+                hp_status,
+                tags: tags.into_owned(),
+            },
+            BattleEvent::Swap {
+                pokemon,
+                position,
+                tags,
+            } => BattleEvent::Swap {
+                pokemon: pokemon.into_owned(),
+                position,
+                tags: tags.into_owned(),
+            },
+            BattleEvent::Cant {
+                pokemon,
+                reason,
+                move_name,
+                tags,
+            } => BattleEvent::Cant {
+                pokemon: pokemon.into_owned(),
+                reason: owned(reason),
+                move_name: move_name.map(owned),
+                tags: tags.into_owned(),
+            },
+            BattleEvent::Faint(x) => BattleEvent::Faint(x.into_owned()),
+            BattleEvent::Fail {
+                pokemon,
+                action,
+                tags,
+            } => BattleEvent::Fail {
+                pokemon: pokemon.into_owned(),
+                action: action.map(owned),
+                tags: tags.into_owned(),
+            },
+            BattleEvent::Block {
+                pokemon,
+                effect,
+                tags,
+            } => BattleEvent::Block {
+                pokemon: pokemon.into_owned(),
+                effect: owned(effect),
+                tags: tags.into_owned(),
+            },
+            BattleEvent::NoTarget(x) => BattleEvent::NoTarget(x.map(Pokemon::into_owned)),
+            BattleEvent::Miss {
+                source,
+                target,
+                tags,
+            } => BattleEvent::Miss {
+                source: source.into_owned(),
+                target: target.map(Pokemon::into_owned),
+                tags: tags.into_owned(),
+            },
+            BattleEvent::Damage {
+                pokemon,
+                hp_status,
+                tags,
+            } => BattleEvent::Damage {
+                pokemon: pokemon.into_owned(),
+                hp_status,
+                tags: tags.into_owned(),
+            },
+            BattleEvent::Heal {
+                pokemon,
+                hp_status,
+                tags,
+            } => BattleEvent::Heal {
+                pokemon: pokemon.into_owned(),
+                hp_status,
+                tags: tags.into_owned(),
+            },
+            BattleEvent::SetHp {
+                pokemon,
+                hp_status,
+                tags,
+            } => BattleEvent::SetHp {
+                pokemon: pokemon.into_owned(),
+                hp_status,
+                tags: tags.into_owned(),
+            },
+            BattleEvent::Status {
+                pokemon,
+                status,
+                tags,
+            } => BattleEvent::Status {
+                pokemon: pokemon.into_owned(),
+                status,
+                tags: tags.into_owned(),
+            },
+            BattleEvent::CureStatus {
+                pokemon,
+                status,
+                tags,
+            } => BattleEvent::CureStatus {
+                pokemon: pokemon.into_owned(),
+                status,
+                tags: tags.into_owned(),
+            },
+            BattleEvent::CureTeam { pokemon, tags } => BattleEvent::CureTeam {
+                pokemon: pokemon.into_owned(),
+                tags: tags.into_owned(),
+            },
+            BattleEvent::Boost(x) => BattleEvent::Boost(x.into_owned()),
+            BattleEvent::Unboost(x) => BattleEvent::Unboost(x.into_owned()),
+            BattleEvent::SetBoost(x) => BattleEvent::SetBoost(x.into_owned()),
+            BattleEvent::SwapBoost {
+                source,
+                target,
+                stats,
+                tags,
+            } => BattleEvent::SwapBoost {
+                source: source.into_owned(),
+                target: target.into_owned(),
+                stats: stats.map(owned),
+                tags: tags.into_owned(),
+            },
+            BattleEvent::InvertBoost { pokemon, tags } => BattleEvent::InvertBoost {
+                pokemon: pokemon.into_owned(),
+                tags: tags.into_owned(),
+            },
+            BattleEvent::ClearBoost { pokemon, tags } => BattleEvent::ClearBoost {
+                pokemon: pokemon.into_owned(),
+                tags: tags.into_owned(),
+            },
+            BattleEvent::ClearAllBoost { tags } => BattleEvent::ClearAllBoost {
+                tags: tags.into_owned(),
+            },
+            BattleEvent::ClearPositiveBoost {
+                target,
+                pokemon,
+                effect,
+                tags,
+            } => BattleEvent::ClearPositiveBoost {
+                target: target.into_owned(),
+                pokemon: pokemon.into_owned(),
+                effect: owned(effect),
+                tags: tags.into_owned(),
+            },
+            BattleEvent::ClearNegativeBoost { pokemon, tags } => BattleEvent::ClearNegativeBoost {
+                pokemon: pokemon.into_owned(),
+                tags: tags.into_owned(),
+            },
+            BattleEvent::CopyBoost {
+                source,
+                target,
+                tags,
+            } => BattleEvent::CopyBoost {
+                source: source.into_owned(),
+                target: target.into_owned(),
+                tags: tags.into_owned(),
+            },
+            BattleEvent::Weather { weather, tags } => BattleEvent::Weather {
+                weather: owned(weather),
+                tags: tags.into_owned(),
+            },
+            BattleEvent::FieldStart { condition, tags } => BattleEvent::FieldStart {
+                condition: owned(condition),
+                tags: tags.into_owned(),
+            },
+            BattleEvent::FieldEnd { condition, tags } => BattleEvent::FieldEnd {
+                condition: owned(condition),
+                tags: tags.into_owned(),
+            },
+            BattleEvent::SideStart {
+                side,
+                player,
+                condition,
+                tags,
+            } => BattleEvent::SideStart {
+                side,
+                player: owned(player),
+                condition: owned(condition),
+                tags: tags.into_owned(),
+            },
+            BattleEvent::SideEnd {
+                side,
+                player,
+                condition,
+                tags,
+            } => BattleEvent::SideEnd {
+                side,
+                player: owned(player),
+                condition: owned(condition),
+                tags: tags.into_owned(),
+            },
+            BattleEvent::SwapSideConditions => BattleEvent::SwapSideConditions,
+            BattleEvent::StartEffect {
+                pokemon,
+                effect,
+                tags,
+            } => BattleEvent::StartEffect {
+                pokemon: pokemon.into_owned(),
+                effect: owned(effect),
+                tags: tags.into_owned(),
+            },
+            BattleEvent::EndEffect {
+                pokemon,
+                effect,
+                tags,
+            } => BattleEvent::EndEffect {
+                pokemon: pokemon.into_owned(),
+                effect: owned(effect),
+                tags: tags.into_owned(),
+            },
+            BattleEvent::Crit(x) => BattleEvent::Crit(x.into_owned()),
+            BattleEvent::SuperEffective(x) => BattleEvent::SuperEffective(x.into_owned()),
+            BattleEvent::Resisted(x) => BattleEvent::Resisted(x.into_owned()),
+            BattleEvent::Immune { pokemon, tags } => BattleEvent::Immune {
+                pokemon: pokemon.into_owned(),
+                tags: tags.into_owned(),
+            },
+            BattleEvent::Item {
+                pokemon,
+                item,
+                tags,
+            } => BattleEvent::Item {
+                pokemon: pokemon.into_owned(),
+                item: owned(item),
+                tags: tags.into_owned(),
+            },
+            BattleEvent::EndItem {
+                pokemon,
+                item,
+                tags,
+            } => BattleEvent::EndItem {
+                pokemon: pokemon.into_owned(),
+                item: owned(item),
+                tags: tags.into_owned(),
+            },
+            BattleEvent::Ability {
+                pokemon,
+                ability,
+                tags,
+            } => BattleEvent::Ability {
+                pokemon: pokemon.into_owned(),
+                ability: owned(ability),
+                tags: tags.into_owned(),
+            },
+            BattleEvent::EndAbility { pokemon, tags } => BattleEvent::EndAbility {
+                pokemon: pokemon.into_owned(),
+                tags: tags.into_owned(),
+            },
+            BattleEvent::Transform {
+                pokemon,
+                target,
+                tags,
+            } => BattleEvent::Transform {
+                pokemon: pokemon.into_owned(),
+                target: target.into_owned(),
+                tags: tags.into_owned(),
+            },
+            BattleEvent::Mega {
+                pokemon,
+                mega_stone,
+            } => BattleEvent::Mega {
+                pokemon: pokemon.into_owned(),
+                mega_stone: mega_stone.map(owned),
+            },
+            BattleEvent::Primal(x) => BattleEvent::Primal(x.into_owned()),
+            BattleEvent::Burst {
+                pokemon,
+                species,
+                item,
+            } => BattleEvent::Burst {
+                pokemon: pokemon.into_owned(),
+                species: owned(species),
+                item: item.map(owned),
+            },
+            BattleEvent::ZPower(x) => BattleEvent::ZPower(x.into_owned()),
+            BattleEvent::ZBroken(x) => BattleEvent::ZBroken(x.into_owned()),
+            BattleEvent::Terastallize { pokemon, tera_type } => BattleEvent::Terastallize {
+                pokemon: pokemon.into_owned(),
+                tera_type: owned(tera_type),
+            },
+            BattleEvent::Activate {
+                pokemon,
+                effect,
+                tags,
+            } => BattleEvent::Activate {
+                pokemon: pokemon.map(Pokemon::into_owned),
+                effect: owned(effect),
+                tags: tags.into_owned(),
+            },
+            BattleEvent::Hint(x) => BattleEvent::Hint(owned(x)),
+            BattleEvent::Center => BattleEvent::Center,
+            BattleEvent::Message(x) => BattleEvent::Message(owned(x)),
+            BattleEvent::Combine => BattleEvent::Combine,
+            BattleEvent::Waiting { source, target } => BattleEvent::Waiting {
+                source: source.into_owned(),
+                target: target.into_owned(),
+            },
+            BattleEvent::Prepare {
+                attacker,
+                move_name,
+                defender,
+            } => BattleEvent::Prepare {
+                attacker: attacker.into_owned(),
+                move_name: owned(move_name),
+                defender: defender.map(Pokemon::into_owned),
+            },
+            BattleEvent::MustRecharge(x) => BattleEvent::MustRecharge(x.into_owned()),
+            BattleEvent::Nothing => BattleEvent::Nothing,
+            BattleEvent::HitCount { pokemon, count } => BattleEvent::HitCount {
+                pokemon: pokemon.into_owned(),
+                count,
+            },
+            BattleEvent::SingleMove {
+                pokemon,
+                move_name,
+                tags,
+            } => BattleEvent::SingleMove {
+                pokemon: pokemon.into_owned(),
+                move_name: owned(move_name),
+                tags: tags.into_owned(),
+            },
+            BattleEvent::SingleTurn {
+                pokemon,
+                move_name,
+                tags,
+            } => BattleEvent::SingleTurn {
+                pokemon: pokemon.into_owned(),
+                move_name: owned(move_name),
+                tags: tags.into_owned(),
+            },
+        }
+    }
+}
+
+/// Positional arguments of a battle message.
+struct Args<'a>(str::Split<'a, char>);
+
+impl<'a> Args<'a> {
+    fn next(&mut self) -> Option<&'a str> {
+        self.0.next()
+    }
+
+    fn optional(&mut self) -> Option<&'a str> {
+        self.next().and_then(optional)
+    }
+
+    fn text(&mut self) -> Option<Cow<'a, str>> {
+        self.next().map(Cow::Borrowed)
+    }
+
+    fn optional_text(&mut self) -> Option<Cow<'a, str>> {
+        self.optional().map(Cow::Borrowed)
+    }
+
+    fn parse<T: FromStr>(&mut self) -> Option<T> {
+        self.next()?.parse().ok()
+    }
+
+    fn optional_parse<T: FromStr>(&mut self) -> Option<Option<T>> {
+        self.optional().map(str::parse).transpose().ok()
+    }
+
+    fn parse_with<T>(&mut self, f: impl FnOnce(&'a str) -> Option<T>) -> Option<T> {
+        f(self.next()?)
+    }
+
+    fn pokemon(&mut self) -> Option<Pokemon<'a>> {
+        self.parse_with(Pokemon::parse)
+    }
+
+    fn optional_pokemon(&mut self) -> Option<Option<Pokemon<'a>>> {
+        optional_with(self.next().unwrap_or(""), Pokemon::parse)
+    }
+
+    fn optional_hp_status(&mut self) -> Option<Option<HpStatus>> {
+        optional_with(self.next().unwrap_or(""), HpStatus::parse)
+    }
+}
+
+fn optional(argument: &str) -> Option<&str> {
+    Some(argument).filter(|a| !a.is_empty())
+}
+
+/// Parses an argument that may be empty, failing only when a non-empty
+/// argument is invalid.
+fn optional_with<'a, T>(
+    argument: &'a str,
+    f: impl FnOnce(&'a str) -> Option<T>,
+) -> Option<Option<T>> {
+    match optional(argument) {
+        Some(argument) => f(argument).map(Some),
+        None => Some(None),
+    }
+}
+
+/// Splits arguments into positional arguments and `[tag]` arguments.
+fn split_tags(arguments: &str) -> (&str, Tags<'_>) {
+    let mut offset = 0;
+    for part in arguments.split('|') {
+        if part.starts_with('[') {
+            let positional = arguments[..offset].strip_suffix('|').unwrap_or("");
+            return (positional, Tags(arguments[offset..].into()));
+        }
+        offset += part.len() + 1;
+    }
+    (arguments, Tags::default())
+}
+
+/// Keyword arguments of a battle message, like `[from] ability: Intimidate`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct Tags<'a>(Cow<'a, str>);
+
+impl<'a> Tags<'a> {
+    /// Creates tags from their raw form, like
+    /// `[from] item: Leftovers|[of] p1a: Pikachu`.
+    pub fn new(tags: impl Into<Cow<'a, str>>) -> Self {
+        Self(tags.into())
+    }
+
+    /// Iterates over tag names and their values.
+    ///
+    /// Tags without values, like `[still]`, have an empty value.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> + '_ {
+        self.0.split('|').filter_map(|tag| {
+            let (name, value) = tag.strip_prefix('[')?.split_once(']')?;
+            Some((name, value.trim_start()))
+        })
+    }
+
+    /// Gets a value of a tag.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use showdown::message::{BattleEvent, Kind, Message};
+    ///
+    /// fn healed_by_leftovers(message: &Message) -> bool {
+    ///     match message.kind() {
+    ///         Kind::Battle(BattleEvent::Heal { tags, .. }) => {
+    ///             tags.get("from") == Some("item: Leftovers")
+    ///         }
+    ///         _ => false,
+    ///     }
+    /// }
+    /// ```
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.iter()
+            .find(|&(n, _)| n == name)
+            .map(|(_, value)| value)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn into_owned(self) -> Tags<'static> {
+        Tags(owned(self.0))
+    }
+}
+
+impl Display for Tags<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Player side, like `p1`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Ord, PartialOrd)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serialize", serde(rename_all = "snake_case"))]
+pub enum Side {
+    P1,
+    P2,
+    P3,
+    P4,
+}
+
+impl Side {
+    /// Parses a side followed by a player name, like `p1: Username`.
+    fn parse_player(argument: &str) -> Option<(Self, &str)> {
+        let (side, player) = argument.split_once(": ")?;
+        Some((side.parse().ok()?, player))
+    }
+}
+
+impl FromStr for Side {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        Ok(match s {
+            "p1" => Side::P1,
+            "p2" => Side::P2,
+            "p3" => Side::P3,
+            "p4" => Side::P4,
+            _ => return Err(()),
+        })
+    }
+}
+
+impl Display for Side {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Side::P1 => "p1",
+            Side::P2 => "p2",
+            Side::P3 => "p3",
+            Side::P4 => "p4",
+        })
+    }
+}
+
+/// Pokémon identifier, like `p1a: Pikachu`.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct Pokemon<'a> {
+    pub side: Side,
+    /// Active slot, `None` when the Pokémon is not active.
+    pub slot: Option<u8>,
+    pub name: Cow<'a, str>,
+}
+
+impl<'a> Pokemon<'a> {
+    pub(crate) fn parse(argument: &'a str) -> Option<Self> {
+        let (position, name) = argument.split_once(": ")?;
+        let side = position.get(..2)?.parse().ok()?;
+        let slot = match position.as_bytes()[2..] {
+            [] => None,
+            [slot @ b'a'..=b'z'] => Some(slot - b'a'),
+            _ => return None,
+        };
+        Some(Self {
+            side,
+            slot,
+            name: name.into(),
+        })
+    }
+
+    pub fn into_owned(self) -> Pokemon<'static> {
+        Pokemon {
+            side: self.side,
+            slot: self.slot,
+            name: owned(self.name),
+        }
+    }
+}
+
+impl Display for Pokemon<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.side)?;
+        if let Some(slot) = self.slot {
+            write!(f, "{}", char::from(b'a' + slot))?;
+        }
+        write!(f, ": {}", self.name)
+    }
+}
+
+/// Pokémon details, like `Pikachu, L50, F, shiny`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct Details<'a> {
+    pub species: Cow<'a, str>,
+    pub level: u8,
+    pub gender: Option<Gender>,
+    pub shiny: bool,
+    pub tera_type: Option<Cow<'a, str>>,
+}
+
+impl<'a> Details<'a> {
+    pub(crate) fn parse(argument: &'a str) -> Option<Self> {
+        let mut parts = argument.split(", ");
+        let mut details = Self {
+            species: parts.next()?.into(),
+            level: 100,
+            gender: None,
+            shiny: false,
+            tera_type: None,
+        };
+        for part in parts {
+            match part {
+                "M" => details.gender = Some(Gender::Male),
+                "F" => details.gender = Some(Gender::Female),
+                "shiny" => details.shiny = true,
+                _ => {
+                    if let Some(level) = part.strip_prefix('L') {
+                        details.level = level.parse().ok()?;
+                    } else if let Some(tera_type) = part.strip_prefix("tera:") {
+                        details.tera_type = Some(tera_type.into());
+                    }
+                }
+            }
+        }
+        Some(details)
+    }
+
+    pub fn into_owned(self) -> Details<'static> {
+        Details {
+            species: owned(self.species),
+            tera_type: self.tera_type.map(owned),
+            ..self
+        }
+    }
+}
+
+impl Display for Details<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.species)?;
+        if self.level != 100 {
+            write!(f, ", L{}", self.level)?;
+        }
+        match self.gender {
+            Some(Gender::Male) => f.write_str(", M")?,
+            Some(Gender::Female) => f.write_str(", F")?,
+            None => {}
+        }
+        if self.shiny {
+            f.write_str(", shiny")?;
+        }
+        if let Some(tera_type) = &self.tera_type {
+            write!(f, ", tera:{}", tera_type)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serialize", serde(rename_all = "snake_case"))]
+pub enum Gender {
+    Male,
+    Female,
+}
+
+/// Pokémon HP and status, like `100/100 par`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct HpStatus {
+    pub hp: u32,
+    /// Maximum HP, `None` for fainted Pokémon.
+    pub max_hp: Option<u32>,
+    pub status: Option<Status>,
+}
+
+impl HpStatus {
+    pub(crate) fn parse(argument: &str) -> Option<Self> {
+        let (hp, status) = match argument.split_once(' ') {
+            Some((hp, status)) => (hp, Some(status.parse().ok()?)),
+            None => (argument, None),
+        };
+        let (hp, max_hp) = match hp.split_once('/') {
+            Some((hp, max_hp)) => (hp, Some(max_hp.parse().ok()?)),
+            None => (hp, None),
+        };
+        Some(Self {
+            hp: hp.parse().ok()?,
+            max_hp,
+            status,
+        })
+    }
+
+    pub fn is_fainted(&self) -> bool {
+        self.status == Some(Status::Fainted)
+    }
+}
+
+impl Display for HpStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.hp)?;
+        if let Some(max_hp) = self.max_hp {
+            write!(f, "/{}", max_hp)?;
+        }
+        if let Some(status) = self.status {
+            write!(f, " {}", status)?;
+        }
+        Ok(())
+    }
+}
+
+/// Non-volatile status condition.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serialize", serde(rename_all = "snake_case"))]
+pub enum Status {
+    Burn,
+    Freeze,
+    Paralysis,
+    Poison,
+    Toxic,
+    Sleep,
+    Fainted,
+}
+
+impl FromStr for Status {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        Ok(match s {
+            "brn" => Status::Burn,
+            "frz" => Status::Freeze,
+            "par" => Status::Paralysis,
+            "psn" => Status::Poison,
+            "tox" => Status::Toxic,
+            "slp" => Status::Sleep,
+            "fnt" => Status::Fainted,
+            _ => return Err(()),
+        })
+    }
+}
+
+impl Display for Status {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Status::Burn => "brn",
+            Status::Freeze => "frz",
+            Status::Paralysis => "par",
+            Status::Poison => "psn",
+            Status::Toxic => "tox",
+            Status::Sleep => "slp",
+            Status::Fainted => "fnt",
+        })
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serialize", serde(rename_all = "snake_case"))]
+pub enum Stat {
+    Attack,
+    Defense,
+    SpecialAttack,
+    SpecialDefense,
+    Speed,
+    Accuracy,
+    Evasion,
+}
+
+impl FromStr for Stat {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        Ok(match s {
+            "atk" => Stat::Attack,
+            "def" => Stat::Defense,
+            "spa" => Stat::SpecialAttack,
+            "spd" => Stat::SpecialDefense,
+            "spe" => Stat::Speed,
+            "accuracy" => Stat::Accuracy,
+            "evasion" => Stat::Evasion,
+            _ => return Err(()),
+        })
+    }
+}
+
+impl Display for Stat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Stat::Attack => "atk",
+            Stat::Defense => "def",
+            Stat::SpecialAttack => "spa",
+            Stat::SpecialDefense => "spd",
+            Stat::Speed => "spe",
+            Stat::Accuracy => "accuracy",
+            Stat::Evasion => "evasion",
+        })
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serialize", serde(rename_all = "snake_case"))]
+pub enum GameType {
+    Singles,
+    Doubles,
+    Triples,
+    Multi,
+    FreeForAll,
+}
+
+impl FromStr for GameType {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        Ok(match s {
+            "singles" => GameType::Singles,
+            "doubles" => GameType::Doubles,
+            "triples" => GameType::Triples,
+            "multi" => GameType::Multi,
+            "freeforall" => GameType::FreeForAll,
+            _ => return Err(()),
+        })
+    }
+}
+
+impl Display for GameType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            GameType::Singles => "singles",
+            GameType::Doubles => "doubles",
+            GameType::Triples => "triples",
+            GameType::Multi => "multi",
+            GameType::FreeForAll => "freeforall",
+        })
+    }
+}
+
+/// Pokémon entering the field, used by `|switch|`, `|drag|`, `|replace|`
+/// and `|detailschange|`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct Switch<'a> {
+    pub pokemon: Pokemon<'a>,
+    pub details: Details<'a>,
+    pub hp_status: Option<HpStatus>,
+}
+
+impl<'a> Switch<'a> {
+    fn parse(mut args: Args<'a>) -> Option<Self> {
+        Some(Self {
+            pokemon: args.pokemon()?,
+            details: args.parse_with(Details::parse)?,
+            hp_status: args.optional_hp_status()?,
+        })
+    }
+
+    pub fn into_owned(self) -> Switch<'static> {
+        Switch {
+            pokemon: self.pokemon.into_owned(),
+            details: self.details.into_owned(),
+            hp_status: self.hp_status,
+        }
+    }
+}
+
+/// Stat stage change, used by `|-boost|`, `|-unboost|` and `|-setboost|`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct Boost<'a> {
+    pub pokemon: Pokemon<'a>,
+    pub stat: Stat,
+    pub amount: i8,
+    pub tags: Tags<'a>,
+}
+
+impl<'a> Boost<'a> {
+    fn parse(mut args: Args<'a>, tags: Tags<'a>) -> Option<Self> {
+        Some(Self {
+            pokemon: args.pokemon()?,
+            stat: args.parse()?,
+            amount: args.parse()?,
+            tags,
+        })
+    }
+
+    pub fn into_owned(self) -> Boost<'static> {
+        Boost {
+            pokemon: self.pokemon.into_owned(),
+            stat: self.stat,
+            amount: self.amount,
+            tags: self.tags.into_owned(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{BattleEvent, Details, Gender, HpStatus, Pokemon, Side, Stat, Status, Switch};
+    use crate::message::{Kind, Message};
+
+    fn parse(raw: &str) -> Message {
+        Message {
+            raw: format!(">battle-gen9ou-1\n{}", raw),
+        }
+    }
+
+    #[test]
+    fn parse_pokemon() {
+        assert_eq!(
+            Pokemon::parse("p2b: Mr. Mime: The Clown"),
+            Some(Pokemon {
+                side: Side::P2,
+                slot: Some(1),
+                name: "Mr. Mime: The Clown".into(),
+            }),
+        );
+        assert_eq!(
+            Pokemon::parse("p1: Pikachu"),
+            Some(Pokemon {
+                side: Side::P1,
+                slot: None,
+                name: "Pikachu".into(),
+            }),
+        );
+        assert_eq!(Pokemon::parse("Pikachu"), None);
+    }
+
+    #[test]
+    fn parse_details() {
+        assert_eq!(
+            Details::parse("Pikachu, L50, F, shiny, tera:Electric"),
+            Some(Details {
+                species: "Pikachu".into(),
+                level: 50,
+                gender: Some(Gender::Female),
+                shiny: true,
+                tera_type: Some("Electric".into()),
+            }),
+        );
+    }
+
+    #[test]
+    fn parse_hp_status() {
+        assert_eq!(
+            HpStatus::parse("42/100 par"),
+            Some(HpStatus {
+                hp: 42,
+                max_hp: Some(100),
+                status: Some(Status::Paralysis),
+            }),
+        );
+        assert!(HpStatus::parse("0 fnt").unwrap().is_fainted());
+    }
+
+    #[test]
+    fn parse_switch() {
+        let message = parse("|switch|p1a: Sparky|Pikachu, L50, M|100/100");
+        assert_eq!(message.room().0, "battle-gen9ou-1");
+        match message.kind() {
+            Kind::Battle(BattleEvent::Switch(Switch {
+                pokemon,
+                details,
+                hp_status,
+            })) => {
+                assert_eq!(pokemon.name, "Sparky");
+                assert_eq!(details.species, "Pikachu");
+                assert_eq!(hp_status.unwrap().hp, 100);
+            }
+            kind => panic!("{:?}", kind),
+        }
+    }
+
+    #[test]
+    fn parse_move_with_tags() {
+        match parse("|move|p1a: Pikachu|Thunderbolt||[still]|[miss]").kind() {
+            Kind::Battle(BattleEvent::Move {
+                source,
+                name,
+                target: None,
+                tags,
+            }) if name == "Thunderbolt" => {
+                assert_eq!(source.name, "Pikachu");
+                assert!(tags.contains("still"));
+                assert!(tags.contains("miss"));
+                assert!(!tags.contains("from"));
+            }
+            kind => panic!("{:?}", kind),
+        }
+    }
+
+    #[test]
+    fn parse_damage_from_item() {
+        match parse("|-damage|p2a: Garchomp|30/100|[from] item: Life Orb").kind() {
+            Kind::Battle(BattleEvent::Damage { tags, .. }) => {
+                assert_eq!(tags.get("from"), Some("item: Life Orb"));
+            }
+            kind => panic!("{:?}", kind),
+        }
+    }
+
+    #[test]
+    fn parse_boost() {
+        assert!(matches!(
+            parse("|-unboost|p2a: Garchomp|atk|1|[from] ability: Intimidate").kind(),
+            Kind::Battle(BattleEvent::Unboost(super::Boost {
+                stat: Stat::Attack,
+                amount: 1,
+                ..
+            }))
+        ));
+    }
+
+    #[test]
+    fn parse_side_start() {
+        assert!(matches!(
+            parse("|-sidestart|p2: Someone|move: Stealth Rock").kind(),
+            Kind::Battle(BattleEvent::SideStart {
+                side: Side::P2,
+                player,
+                condition,
+                ..
+            }) if player == "Someone" && condition == "move: Stealth Rock"
+        ));
+    }
+
+    #[test]
+    fn parse_turn() {
+        assert!(matches!(
+            parse("|turn|12\n|move|p1a: Pikachu|Thunderbolt|p2a: Garchomp").kind(),
+            Kind::Battle(BattleEvent::Turn(12))
+        ));
+    }
+
+    #[test]
+    fn invalid_battle_message_is_unrecognized() {
+        assert!(matches!(
+            parse("|turn|twelve").kind(),
+            Kind::Unrecognized(_)
+        ));
+    }
+}
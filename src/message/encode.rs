@@ -0,0 +1,1738 @@
+use super::{
+    ActiveRequest, BattleEvent, BattleRequest, Boost, BracketData, BracketNode, BracketState, Chat,
+    FormatList, Kind, Message, NoInit, NoInitKind, Private, QueryResponse, RequestPokemon, Room,
+    RoomInit, RoomType, RoomUsers, RoomsList, Switch, Tags, TournamentEvent, TournamentResult,
+    TournamentUpdate, UpdateUser,
+};
+use crate::RoomId;
+use serde_json::{json, Map, Value};
+use std::fmt::{self, Display, Formatter};
+
+/// Message sent by a server, containing one or more protocol lines.
+///
+/// This is the reverse of [`Message`], useful for writing test servers
+/// or rewriting logs.
+///
+/// # Examples
+///
+/// ```
+/// use showdown::message::ServerMessage;
+/// use showdown::RoomId;
+///
+/// let message = ServerMessage::room(RoomId("lobby"))
+///     .line("|J|+xfix")
+///     .line("|c:|1634571729|+xfix|Hello")
+///     .into_message();
+/// assert_eq!(message.room().0, "lobby");
+/// assert_eq!(message.kinds().count(), 2);
+/// let join = message.kinds().next().unwrap();
+/// assert_eq!(ServerMessage::new().kind(&join).to_string(), "|J|+xfix");
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ServerMessage {
+    raw: String,
+}
+
+impl ServerMessage {
+    /// Creates a message without a room, which is treated as sent to
+    /// the lobby.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a message sent to a given room.
+    pub fn room(room: RoomId<'_>) -> Self {
+        Self {
+            raw: format!(">{}", room.0),
+        }
+    }
+
+    /// Appends an encoded [`Kind`].
+    pub fn kind(self, kind: &Kind<'_>) -> Self {
+        self.line(kind)
+    }
+
+    /// Appends a raw protocol line, like `|J|+xfix`.
+    pub fn line(mut self, line: impl Display) -> Self {
+        if !self.raw.is_empty() {
+            self.raw.push('\n');
+        }
+        self.raw += &line.to_string();
+        self
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.raw
+    }
+
+    /// Converts into a [`Message`], as if it was received from a server.
+    pub fn into_message(self) -> Message {
+        Message { raw: self.raw }
+    }
+}
+
+impl Display for ServerMessage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.raw)
+    }
+}
+
+impl From<ServerMessage> for Message {
+    fn from(message: ServerMessage) -> Self {
+        message.into_message()
+    }
+}
+
+/// Writes `|`-separated arguments of a protocol line.
+///
+/// Empty optional arguments are only written when followed by another
+/// positional argument, like the server does.
+struct Line<'a, 'b> {
+    f: &'a mut Formatter<'b>,
+    skipped: usize,
+}
+
+impl<'a, 'b> Line<'a, 'b> {
+    fn new(f: &'a mut Formatter<'b>, command: &str) -> Result<Self, fmt::Error> {
+        write!(f, "|{}", command)?;
+        Ok(Self { f, skipped: 0 })
+    }
+
+    fn arg(mut self, arg: impl Display) -> Result<Self, fmt::Error> {
+        for _ in 0..self.skipped {
+            self.f.write_str("|")?;
+        }
+        self.skipped = 0;
+        write!(self.f, "|{}", arg)?;
+        Ok(self)
+    }
+
+    fn optional(mut self, arg: Option<impl Display>) -> Result<Self, fmt::Error> {
+        match arg {
+            Some(arg) => self.arg(arg),
+            None => {
+                self.skipped += 1;
+                Ok(self)
+            }
+        }
+    }
+
+    fn tags(self, tags: &Tags<'_>) -> fmt::Result {
+        if tags.is_empty() {
+            Ok(())
+        } else {
+            write!(self.f, "|{}", tags)
+        }
+    }
+
+    /// Writes tags, keeping empty slots of skipped optional arguments,
+    /// like in `|move|p1a: Pikachu|Protect||[still]`.
+    fn slotted_tags(self, tags: &Tags<'_>) -> fmt::Result {
+        if tags.is_empty() {
+            Ok(())
+        } else {
+            for _ in 0..self.skipped {
+                self.f.write_str("|")?;
+            }
+            write!(self.f, "|{}", tags)
+        }
+    }
+
+    fn end(self) -> fmt::Result {
+        Ok(())
+    }
+}
+
+impl Display for Kind<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Kind::Chat(chat) => chat.fmt(f),
+            Kind::Private(private) => private.fmt(f),
+            Kind::Join(user) => write!(f, "|J|{}", user),
+            Kind::NicknameChange(new, old) => write!(f, "|N|{}|{}", new, old),
+            Kind::Leave(user) => write!(f, "|L|{}", user),
+            Kind::Challenge(challenge) => write!(f, "|challstr|{}", challenge.0),
+            Kind::Html(html) => write!(f, "|html|{}", html),
+            Kind::NoInit(no_init) => no_init.fmt(f),
+            Kind::RoomInit(room_init) => room_init.fmt(f),
+            Kind::QueryResponse(response) => response.fmt(f),
+            Kind::UpdateUser(update_user) => update_user.fmt(f),
+            Kind::UpdateChallenges(challenges) => {
+                let challenge_to = challenges
+                    .challenge_to
+                    .as_ref()
+                    .map(|challenge| json!({ "to": challenge.to, "format": challenge.format }));
+                let challenges = json!({
+                    "challengesFrom": challenges.challenges_from,
+                    "challengeTo": challenge_to,
+                });
+                write!(f, "|updatechallenges|{}", challenges)
+            }
+            Kind::UpdateSearch(search) => {
+                let search = json!({ "searching": search.searching, "games": search.games });
+                write!(f, "|updatesearch|{}", search)
+            }
+            Kind::Formats(formats) => formats.fmt(f),
+            Kind::Tournament(event) => event.fmt(f),
+            Kind::Battle(event) => event.fmt(f),
+            Kind::Request(request) => request.fmt(f),
+            Kind::Unrecognized(message) => f.write_str(&message.0),
+        }
+    }
+}
+
+impl Display for Chat<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "|c:|{}|{}|{}", self.timestamp, self.user, self.message)
+    }
+}
+
+impl Display for Private<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "|pm|{}|{}|{}", self.from, self.to, self.message)
+    }
+}
+
+impl Display for NoInit<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let kind = match self.kind {
+            NoInitKind::Nonexistent => "nonexistent",
+            NoInitKind::JoinFailed => "joinfailed",
+            NoInitKind::NameRequired => "namerequired",
+        };
+        write!(f, "|noinit|{}|{}", kind, self.reason)
+    }
+}
+
+impl Display for RoomUsers<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.count)?;
+        for user in &self.users {
+            write!(f, ",{}", user)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for RoomInit<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let room_type = match self.room_type {
+            RoomType::Chat => "chat",
+            RoomType::Battle => "battle",
+        };
+        write!(
+            f,
+            "|init|{}\n|title|{}\n|users|{}",
+            room_type, self.title, self.users
+        )
+    }
+}
+
+impl Display for QueryResponse<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            QueryResponse::Rooms(rooms) => write!(f, "|queryresponse|rooms|{}", rooms_json(rooms)),
+        }
+    }
+}
+
+impl Display for UpdateUser<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "|updateuser|{}|{}|{}",
+            self.username,
+            u8::from(self.named),
+            self.avatar
+        )
+    }
+}
+
+impl Display for FormatList<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("|formats")?;
+        for section in &self.sections {
+            write!(f, "|,{}|{}", section.column, section.name)?;
+            for format in &section.formats {
+                write!(f, "|{},{:x}", format.name, format.flags)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Display for TournamentEvent<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("|tournament")?;
+        match self {
+            TournamentEvent::Create {
+                format,
+                generator,
+                player_cap,
+            } => Line::new(f, "create")?
+                .arg(format)?
+                .arg(generator)?
+                .arg(player_cap.unwrap_or(0))?
+                .end(),
+            TournamentEvent::Update(update) => {
+                write!(f, "|update|{}", tournament_update_json(update))
+            }
+            TournamentEvent::UpdateEnd => f.write_str("|updateEnd"),
+            TournamentEvent::Error(error) => write!(f, "|error|{}", error),
+            TournamentEvent::Join(user) => write!(f, "|join|{}", user),
+            TournamentEvent::Leave(user) => write!(f, "|leave|{}", user),
+            TournamentEvent::Replace { old, new } => write!(f, "|replace|{}|{}", old, new),
+            TournamentEvent::Start(players) => write!(f, "|start|{}", players),
+            TournamentEvent::Disqualify(user) => write!(f, "|disqualify|{}", user),
+            TournamentEvent::BattleStart {
+                player1,
+                player2,
+                room,
+            } => write!(f, "|battlestart|{}|{}|{}", player1, player2, room),
+            TournamentEvent::BattleEnd {
+                player1,
+                player2,
+                result,
+                score,
+                recorded,
+                room,
+            } => {
+                write!(f, "|battleend|{}|{}|{}|", player1, player2, result.as_str())?;
+                for (i, points) in score.iter().enumerate() {
+                    if i != 0 {
+                        f.write_str(",")?;
+                    }
+                    write!(f, "{}", points)?;
+                }
+                let recorded = if *recorded { "success" } else { "fail" };
+                write!(f, "|{}|{}", recorded, room)
+            }
+            TournamentEvent::End(end) => {
+                let end = json!({
+                    "results": end.results,
+                    "format": end.format,
+                    "generator": end.generator,
+                    "bracketData": bracket_json(&end.bracket_data),
+                });
+                write!(f, "|end|{}", end)
+            }
+            TournamentEvent::ForceEnd => f.write_str("|forceend"),
+        }
+    }
+}
+
+impl Display for BattleRequest<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "|request|{}", request_json(self))
+    }
+}
+
+// JSON is built by hand, as `Serialize` is only implemented with
+// `serialize` feature.
+
+fn tournament_update_json(update: &TournamentUpdate) -> Value {
+    let mut json = Map::new();
+    let mut insert = |key: &str, value: Option<Value>| {
+        if let Some(value) = value {
+            json.insert(key.into(), value);
+        }
+    };
+    insert("format", update.format.as_ref().map(|v| json!(v)));
+    insert(
+        "teambuilderFormat",
+        update.teambuilder_format.as_ref().map(|v| json!(v)),
+    );
+    insert("generator", update.generator.as_ref().map(|v| json!(v)));
+    insert(
+        "playerCap",
+        update.player_cap.map(|v| json!(v.unwrap_or(0))),
+    );
+    insert("isStarted", update.is_started.map(|v| json!(v)));
+    insert("isJoined", update.is_joined.map(|v| json!(v)));
+    insert(
+        "bracketData",
+        update.bracket_data.as_ref().map(bracket_json),
+    );
+    insert("challenges", update.challenges.as_ref().map(|v| json!(v)));
+    insert(
+        "challengeBys",
+        update.challenge_bys.as_ref().map(|v| json!(v)),
+    );
+    insert("challenging", update.challenging.as_ref().map(|v| json!(v)));
+    insert("challenged", update.challenged.as_ref().map(|v| json!(v)));
+    Value::Object(json)
+}
+
+fn bracket_json(bracket: &BracketData) -> Value {
+    match bracket {
+        BracketData::Tree(tree) => json!({
+            "type": "tree",
+            "rootNode": tree.root_node.as_ref().map(bracket_node_json),
+        }),
+        BracketData::Table(table) => {
+            let contents: Value = table
+                .table_contents
+                .iter()
+                .map(|row| {
+                    row.iter()
+                        .map(|cell| {
+                            cell.as_ref().map(|cell| {
+                                json!({
+                                    "state": cell.state.as_str(),
+                                    "result": cell.result.map(TournamentResult::as_str),
+                                    "score": cell.score,
+                                    "room": cell.room,
+                                })
+                            })
+                        })
+                        .collect::<Value>()
+                })
+                .collect();
+            json!({
+                "type": "table",
+                "tableHeaders": {
+                    "cols": table.table_headers.cols,
+                    "rows": table.table_headers.rows,
+                },
+                "tableContents": contents,
+                "scores": table.scores,
+            })
+        }
+    }
+}
+
+fn bracket_node_json(node: &BracketNode) -> Value {
+    json!({
+        "team": node.team,
+        "children": node.children.iter().map(bracket_node_json).collect::<Value>(),
+        "state": node.state.map(BracketState::as_str),
+        "result": node.result.map(TournamentResult::as_str),
+        "score": node.score,
+        "room": node.room,
+    })
+}
+
+fn rooms_json(rooms: &RoomsList<'_>) -> Value {
+    fn list(rooms: &[Room<'_>]) -> Value {
+        rooms
+            .iter()
+            .map(|room| {
+                json!({
+                    "title": room.title,
+                    "desc": room.desc,
+                    "userCount": room.user_count,
+                    "subRooms": room.sub_rooms,
+                })
+            })
+            .collect()
+    }
+    json!({
+        "official": list(&rooms.official),
+        "pspl": list(&rooms.pspl),
+        "chat": list(&rooms.chat),
+        "userCount": rooms.user_count,
+        "battleCount": rooms.battle_count,
+    })
+}
+
+fn request_json(request: &BattleRequest<'_>) -> Value {
+    json!({
+        "active": request.active.iter().map(active_json).collect::<Value>(),
+        "side": {
+            "name": request.side.name,
+            "id": request.side.id.to_string(),
+            "pokemon": request.side.pokemon.iter().map(request_pokemon_json).collect::<Value>(),
+        },
+        "rqid": request.rqid,
+        "forceSwitch": request.force_switch,
+        "wait": request.wait,
+        "teamPreview": request.team_preview,
+        "maxChosenTeamSize": request.max_team_size,
+        "noCancel": request.no_cancel,
+    })
+}
+
+fn active_json(active: &ActiveRequest<'_>) -> Value {
+    let moves: Value = active
+        .moves
+        .iter()
+        .map(|slot| {
+            json!({
+                "move": slot.name,
+                "id": slot.id,
+                "pp": slot.pp,
+                "maxpp": slot.maxpp,
+                "target": slot.target,
+                "disabled": slot.disabled,
+            })
+        })
+        .collect();
+    let z_moves = active.can_z_move.as_ref().map(|z_moves| {
+        z_moves
+            .iter()
+            .map(|z_move| {
+                z_move
+                    .as_ref()
+                    .map(|z_move| json!({ "move": z_move.name, "target": z_move.target }))
+            })
+            .collect::<Value>()
+    });
+    let max_moves = active.max_moves.as_ref().map(|max_moves| {
+        let moves: Value = max_moves
+            .max_moves
+            .iter()
+            .map(|max_move| {
+                json!({
+                    "move": max_move.name,
+                    "target": max_move.target,
+                    "disabled": max_move.disabled,
+                })
+            })
+            .collect();
+        json!({ "maxMoves": moves, "gigantamax": max_moves.gigantamax })
+    });
+    json!({
+        "moves": moves,
+        "trapped": active.trapped,
+        "maybeTrapped": active.maybe_trapped,
+        "canMegaEvo": active.can_mega_evo,
+        "canUltraBurst": active.can_ultra_burst,
+        "canZMove": z_moves,
+        "canDynamax": active.can_dynamax,
+        "maxMoves": max_moves,
+        "canTerastallize": active.can_terastallize,
+    })
+}
+
+fn request_pokemon_json(pokemon: &RequestPokemon<'_>) -> Value {
+    let stats = pokemon.stats;
+    json!({
+        "ident": pokemon.ident,
+        "details": pokemon.details,
+        "condition": pokemon.condition,
+        "active": pokemon.active,
+        "stats": {
+            "atk": stats.atk,
+            "def": stats.def,
+            "spa": stats.spa,
+            "spd": stats.spd,
+            "spe": stats.spe,
+        },
+        "moves": pokemon.moves,
+        "baseAbility": pokemon.base_ability,
+        "item": pokemon.item,
+        "pokeball": pokemon.pokeball,
+        "ability": pokemon.ability,
+        "teraType": pokemon.tera_type,
+        "terastallized": pokemon.terastallized,
+    })
+}
+
+impl Display for BattleEvent<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            BattleEvent::Player {
+                side,
+                username,
+                avatar,
+                rating,
+            } => Line::new(f, "player")?
+                .arg(side)?
+                .arg(username)?
+                .arg(avatar)?
+                .optional(rating.as_ref())?
+                .end(),
+            BattleEvent::TeamSize { side, size } => {
+                Line::new(f, "teamsize")?.arg(side)?.arg(size)?.end()
+            }
+            BattleEvent::GameType(game_type) => Line::new(f, "gametype")?.arg(game_type)?.end(),
+            BattleEvent::Gen(gen) => Line::new(f, "gen")?.arg(gen)?.end(),
+            BattleEvent::Tier(tier) => Line::new(f, "tier")?.arg(tier)?.end(),
+            BattleEvent::Rated(message) => Line::new(f, "rated")?.optional(message.as_ref())?.end(),
+            BattleEvent::Rule(rule) => Line::new(f, "rule")?.arg(rule)?.end(),
+            BattleEvent::ClearPoke => Line::new(f, "clearpoke")?.end(),
+            BattleEvent::Poke {
+                side,
+                details,
+                has_item,
+            } => Line::new(f, "poke")?
+                .arg(side)?
+                .arg(details)?
+                .optional(Some("item").filter(|_| *has_item))?
+                .end(),
+            BattleEvent::TeamPreview(count) => {
+                Line::new(f, "teampreview")?.optional(count.as_ref())?.end()
+            }
+            BattleEvent::Start => Line::new(f, "start")?.end(),
+            BattleEvent::Turn(turn) => Line::new(f, "turn")?.arg(turn)?.end(),
+            BattleEvent::Win(user) => Line::new(f, "win")?.arg(user)?.end(),
+            BattleEvent::Tie => Line::new(f, "tie")?.end(),
+            BattleEvent::Upkeep => Line::new(f, "upkeep")?.end(),
+            BattleEvent::Timestamp(timestamp) => Line::new(f, "t:")?.arg(timestamp)?.end(),
+            BattleEvent::Inactive(message) => Line::new(f, "inactive")?.arg(message)?.end(),
+            BattleEvent::InactiveOff(message) => Line::new(f, "inactiveoff")?.arg(message)?.end(),
+            BattleEvent::Move {
+                source,
+                name,
+                target,
+                tags,
+            } => Line::new(f, "move")?
+                .arg(source)?
+                .arg(name)?
+                .optional(target.as_ref())?
+                .slotted_tags(tags),
+            BattleEvent::Switch(switch) => switch_line(f, "switch", switch),
+            BattleEvent::Drag(switch) => switch_line(f, "drag", switch),
+            BattleEvent::Replace(switch) => switch_line(f, "replace", switch),
+            BattleEvent::DetailsChange(switch) => switch_line(f, "detailschange", switch),
+            BattleEvent::FormeChange {
+                pokemon,
+                species,
+                hp_status,
+                tags,
+            } => Line::new(f, "-formechange")?
+                .arg(pokemon)?
+                .arg(species)?
+                .optional(hp_status.as_ref())?
+                .tags(tags),
+            BattleEvent::Swap {
+                pokemon,
+                position,
+                tags,
+            } => Line::new(f, "swap")?
+                .arg(pokemon)?
+                .arg(position)?
+                .tags(tags),
+            BattleEvent::Cant {
+                pokemon,
+                reason,
+                move_name,
+                tags,
+            } => Line::new(f, "cant")?
+                .arg(pokemon)?
+                .arg(reason)?
+                .optional(move_name.as_ref())?
+                .slotted_tags(tags),
+            BattleEvent::Faint(pokemon) => Line::new(f, "faint")?.arg(pokemon)?.end(),
+            BattleEvent::Fail {
+                pokemon,
+                action,
+                tags,
+            } => Line::new(f, "-fail")?
+                .arg(pokemon)?
+                .optional(action.as_ref())?
+                .tags(tags),
+            BattleEvent::Block {
+                pokemon,
+                effect,
+                tags,
+            } => Line::new(f, "-block")?
+                .arg(pokemon)?
+                .arg(effect)?
+                .tags(tags),
+            BattleEvent::NoTarget(pokemon) => {
+                Line::new(f, "-notarget")?.optional(pokemon.as_ref())?.end()
+            }
+            BattleEvent::Miss {
+                source,
+                target,
+                tags,
+            } => Line::new(f, "-miss")?
+                .arg(source)?
+                .optional(target.as_ref())?
+                .tags(tags),
+            BattleEvent::Damage {
+                pokemon,
+                hp_status,
+                tags,
+            } => Line::new(f, "-damage")?
+                .arg(pokemon)?
+                .arg(hp_status)?
+                .tags(tags),
+            BattleEvent::Heal {
+                pokemon,
+                hp_status,
+                tags,
+            } => Line::new(f, "-heal")?
+                .arg(pokemon)?
+                .arg(hp_status)?
+                .tags(tags),
+            BattleEvent::SetHp {
+                pokemon,
+                hp_status,
+                tags,
+            } => Line::new(f, "-sethp")?
+                .arg(pokemon)?
+                .arg(hp_status)?
+                .tags(tags),
+            BattleEvent::Status {
+                pokemon,
+                status,
+                tags,
+            } => Line::new(f, "-status")?
+                .arg(pokemon)?
+                .arg(status)?
+                .tags(tags),
+            BattleEvent::CureStatus {
+                pokemon,
+                status,
+                tags,
+            } => Line::new(f, "-curestatus")?
+                .arg(pokemon)?
+                .arg(status)?
+                .tags(tags),
+            BattleEvent::CureTeam { pokemon, tags } => {
+                Line::new(f, "-cureteam")?.arg(pokemon)?.tags(tags)
+            }
+            BattleEvent::Boost(boost) => boost_line(f, "-boost", boost),
+            BattleEvent::Unboost(boost) => boost_line(f, "-unboost", boost),
+            BattleEvent::SetBoost(boost) => boost_line(f, "-setboost", boost),
+            BattleEvent::SwapBoost {
+                source,
+                target,
+                stats,
+                tags,
+            } => Line::new(f, "-swapboost")?
+                .arg(source)?
+                .arg(target)?
+                .optional(stats.as_ref())?
+                .tags(tags),
+            BattleEvent::InvertBoost { pokemon, tags } => {
+                Line::new(f, "-invertboost")?.arg(pokemon)?.tags(tags)
+            }
+            BattleEvent::ClearBoost { pokemon, tags } => {
+                Line::new(f, "-clearboost")?.arg(pokemon)?.tags(tags)
+            }
+            BattleEvent::ClearAllBoost { tags } => Line::new(f, "-clearallboost")?.tags(tags),
+            BattleEvent::ClearPositiveBoost {
+                target,
+                pokemon,
+                effect,
+                tags,
+            } => Line::new(f, "-clearpositiveboost")?
+                .arg(target)?
+                .arg(pokemon)?
+                .arg(effect)?
+                .tags(tags),
+            BattleEvent::ClearNegativeBoost { pokemon, tags } => {
+                Line::new(f, "-clearnegativeboost")?
+                    .arg(pokemon)?
+                    .tags(tags)
+            }
+            BattleEvent::CopyBoost {
+                source,
+                target,
+                tags,
+            } => Line::new(f, "-copyboost")?
+                .arg(source)?
+                .arg(target)?
+                .tags(tags),
+            BattleEvent::Weather { weather, tags } => {
+                Line::new(f, "-weather")?.arg(weather)?.tags(tags)
+            }
+            BattleEvent::FieldStart { condition, tags } => {
+                Line::new(f, "-fieldstart")?.arg(condition)?.tags(tags)
+            }
+            BattleEvent::FieldEnd { condition, tags } => {
+                Line::new(f, "-fieldend")?.arg(condition)?.tags(tags)
+            }
+            BattleEvent::SideStart {
+                side,
+                player,
+                condition,
+                tags,
+            } => Line::new(f, "-sidestart")?
+                .arg(format_args!("{}: {}", side, player))?
+                .arg(condition)?
+                .tags(tags),
+            BattleEvent::SideEnd {
+                side,
+                player,
+                condition,
+                tags,
+            } => Line::new(f, "-sideend")?
+                .arg(format_args!("{}: {}", side, player))?
+                .arg(condition)?
+                .tags(tags),
+            BattleEvent::SwapSideConditions => Line::new(f, "-swapsideconditions")?.end(),
+            BattleEvent::StartEffect {
+                pokemon,
+                effect,
+                tags,
+            } => Line::new(f, "-start")?
+                .arg(pokemon)?
+                .arg(effect)?
+                .tags(tags),
+            BattleEvent::EndEffect {
+                pokemon,
+                effect,
+                tags,
+            } => Line::new(f, "-end")?.arg(pokemon)?.arg(effect)?.tags(tags),
+            BattleEvent::Crit(pokemon) => Line::new(f, "-crit")?.arg(pokemon)?.end(),
+            BattleEvent::SuperEffective(pokemon) => {
+                Line::new(f, "-supereffective")?.arg(pokemon)?.end()
+            }
+            BattleEvent::Resisted(pokemon) => Line::new(f, "-resisted")?.arg(pokemon)?.end(),
+            BattleEvent::Immune { pokemon, tags } => {
+                Line::new(f, "-immune")?.arg(pokemon)?.tags(tags)
+            }
+            BattleEvent::Item {
+                pokemon,
+                item,
+                tags,
+            } => Line::new(f, "-item")?.arg(pokemon)?.arg(item)?.tags(tags),
+            BattleEvent::EndItem {
+                pokemon,
+                item,
+                tags,
+            } => Line::new(f, "-enditem")?
+                .arg(pokemon)?
+                .arg(item)?
+                .tags(tags),
+            BattleEvent::Ability {
+                pokemon,
+                ability,
+                tags,
+            } => Line::new(f, "-ability")?
+                .arg(pokemon)?
+                .arg(ability)?
+                .tags(tags),
+            BattleEvent::EndAbility { pokemon, tags } => {
+                Line::new(f, "-endability")?.arg(pokemon)?.tags(tags)
+            }
+            BattleEvent::Transform {
+                pokemon,
+                target,
+                tags,
+            } => Line::new(f, "-transform")?
+                .arg(pokemon)?
+                .arg(target)?
+                .tags(tags),
+            BattleEvent::Mega {
+                pokemon,
+                mega_stone,
+            } => Line::new(f, "-mega")?
+                .arg(pokemon)?
+                .optional(mega_stone.as_ref())?
+                .end(),
+            BattleEvent::Primal(pokemon) => Line::new(f, "-primal")?.arg(pokemon)?.end(),
+            BattleEvent::Burst {
+                pokemon,
+                species,
+                item,
+            } => Line::new(f, "-burst")?
+                .arg(pokemon)?
+                .arg(species)?
+                .optional(item.as_ref())?
+                .end(),
+            BattleEvent::ZPower(pokemon) => Line::new(f, "-zpower")?.arg(pokemon)?.end(),
+            BattleEvent::ZBroken(pokemon) => Line::new(f, "-zbroken")?.arg(pokemon)?.end(),
+            BattleEvent::Terastallize { pokemon, tera_type } => Line::new(f, "-terastallize")?
+                .arg(pokemon)?
+                .arg(tera_type)?
+                .end(),
+            BattleEvent::Activate {
+                pokemon,
+                effect,
+                tags,
+            } => Line::new(f, "-activate")?
+                .optional(pokemon.as_ref())?
+                .arg(effect)?
+                .tags(tags),
+            BattleEvent::Hint(hint) => Line::new(f, "-hint")?.arg(hint)?.end(),
+            BattleEvent::Center => Line::new(f, "-center")?.end(),
+            BattleEvent::Message(message) => Line::new(f, "-message")?.arg(message)?.end(),
+            BattleEvent::Combine => Line::new(f, "-combine")?.end(),
+            BattleEvent::Waiting { source, target } => {
+                Line::new(f, "-waiting")?.arg(source)?.arg(target)?.end()
+            }
+            BattleEvent::Prepare {
+                attacker,
+                move_name,
+                defender,
+            } => Line::new(f, "-prepare")?
+                .arg(attacker)?
+                .arg(move_name)?
+                .optional(defender.as_ref())?
+                .end(),
+            BattleEvent::MustRecharge(pokemon) => {
+                Line::new(f, "-mustrecharge")?.arg(pokemon)?.end()
+            }
+            BattleEvent::Nothing => Line::new(f, "-nothing")?.end(),
+            BattleEvent::HitCount { pokemon, count } => {
+                Line::new(f, "-hitcount")?.arg(pokemon)?.arg(count)?.end()
+            }
+            BattleEvent::SingleMove {
+                pokemon,
+                move_name,
+                tags,
+            } => Line::new(f, "-singlemove")?
+                .arg(pokemon)?
+                .arg(move_name)?
+                .tags(tags),
+            BattleEvent::SingleTurn {
+                pokemon,
+                move_name,
+                tags,
+            } => Line::new(f, "-singleturn")?
+                .arg(pokemon)?
+                .arg(move_name)?
+                .tags(tags),
+        }
+    }
+}
+
+fn switch_line(f: &mut Formatter<'_>, command: &str, switch: &Switch<'_>) -> fmt::Result {
+    Line::new(f, command)?
+        .arg(&switch.pokemon)?
+        .arg(&switch.details)?
+        .optional(switch.hp_status.as_ref())?
+        .end()
+}
+
+fn boost_line(f: &mut Formatter<'_>, command: &str, boost: &Boost<'_>) -> fmt::Result {
+    Line::new(f, command)?
+        .arg(&boost.pokemon)?
+        .arg(boost.stat)?
+        .arg(boost.amount)?
+        .tags(&boost.tags)
+}
+
+#[cfg(test)]
+mod test {
+    use super::ServerMessage;
+    use crate::message::{
+        ActiveRequest, BattleRequest, Challenge, ChallengeTo, Format, FormatList, FormatSection,
+        MaxMove, MaxMoves, MoveSlot, QueryResponse, RequestPokemon, RequestSide, Room, RoomsList,
+        Stats, TournamentEvent, TournamentResult, TournamentUpdate, UnrecognizedMessage,
+        UpdateChallenges, UpdateSearch, ZMove,
+    };
+    use crate::message::{
+        BattleEvent, Boost, Chat, Details, GameType, Gender, HpStatus, Kind, NoInit, NoInitKind,
+        Pokemon, Private, RoomInit, RoomType, RoomUsers, Side, Stat, Status, Switch, Tags,
+        UpdateUser, User,
+    };
+    use crate::RoomId;
+    use proptest::collection::{btree_map, vec};
+    use proptest::option;
+    use proptest::prelude::*;
+    use proptest::strategy::Union;
+    use std::borrow::Cow;
+
+    type Text = Cow<'static, str>;
+
+    /// Positional argument, which cannot contain separators and cannot
+    /// look like a tag.
+    fn arg() -> impl Strategy<Value = Text> {
+        "[^|\n\\[][^|\n]{0,12}".prop_map(Cow::Owned)
+    }
+
+    /// Argument taking the rest of a line.
+    fn rest() -> impl Strategy<Value = Text> {
+        "[^\n]{0,20}".prop_map(Cow::Owned)
+    }
+
+    fn room_users() -> impl Strategy<Value = RoomUsers<'static>> {
+        (
+            any::<u32>(),
+            proptest::collection::vec("[^|\n,]{1,12}", 0..4),
+        )
+            .prop_map(|(count, users)| RoomUsers {
+                count,
+                users: users.into_iter().map(User::new).collect(),
+            })
+    }
+
+    fn side() -> impl Strategy<Value = Side> {
+        prop_oneof![
+            Just(Side::P1),
+            Just(Side::P2),
+            Just(Side::P3),
+            Just(Side::P4)
+        ]
+    }
+
+    fn pokemon() -> impl Strategy<Value = Pokemon<'static>> {
+        (side(), option::of(0..26u8), rest()).prop_map(|(side, slot, name)| Pokemon {
+            side,
+            slot,
+            name: Cow::Owned(name.replace('|', "")),
+        })
+    }
+
+    fn tags() -> impl Strategy<Value = Tags<'static>> {
+        proptest::collection::vec("\\[[a-z]{1,6}\\]( [^|\n]{1,10})?", 0..3)
+            .prop_map(|tags| Tags::new(tags.join("|")))
+    }
+
+    fn details() -> impl Strategy<Value = Details<'static>> {
+        (
+            "[A-Za-z][A-Za-z -]{0,10}",
+            1..=100u8,
+            option::of(prop_oneof![Just(Gender::Male), Just(Gender::Female)]),
+            any::<bool>(),
+            option::of("[A-Z][a-z]{1,8}"),
+        )
+            .prop_map(|(species, level, gender, shiny, tera_type)| Details {
+                species: Cow::Owned(species.trim_end().into()),
+                level,
+                gender,
+                shiny,
+                tera_type: tera_type.map(Cow::Owned),
+            })
+    }
+
+    fn status() -> impl Strategy<Value = Status> {
+        prop_oneof![
+            Just(Status::Burn),
+            Just(Status::Freeze),
+            Just(Status::Paralysis),
+            Just(Status::Poison),
+            Just(Status::Toxic),
+            Just(Status::Sleep),
+            Just(Status::Fainted),
+        ]
+    }
+
+    fn hp_status() -> impl Strategy<Value = HpStatus> {
+        (any::<u32>(), option::of(any::<u32>()), option::of(status()))
+            .prop_map(|(hp, max_hp, status)| HpStatus { hp, max_hp, status })
+    }
+
+    fn stat() -> impl Strategy<Value = Stat> {
+        prop_oneof![
+            Just(Stat::Attack),
+            Just(Stat::Defense),
+            Just(Stat::SpecialAttack),
+            Just(Stat::SpecialDefense),
+            Just(Stat::Speed),
+            Just(Stat::Accuracy),
+            Just(Stat::Evasion),
+        ]
+    }
+
+    fn switch() -> impl Strategy<Value = Switch<'static>> {
+        (pokemon(), details(), option::of(hp_status())).prop_map(|(pokemon, details, hp_status)| {
+            Switch {
+                pokemon,
+                details,
+                hp_status,
+            }
+        })
+    }
+
+    fn boost() -> impl Strategy<Value = Boost<'static>> {
+        (pokemon(), stat(), any::<i8>(), tags()).prop_map(|(pokemon, stat, amount, tags)| Boost {
+            pokemon,
+            stat,
+            amount,
+            tags,
+        })
+    }
+
+    fn battle_event() -> impl Strategy<Value = BattleEvent<'static>> {
+        use BattleEvent as E;
+        Union::new(vec![
+            (side(), arg(), arg(), option::of(arg()))
+                .prop_map(|(side, username, avatar, rating)| E::Player {
+                    side,
+                    username,
+                    avatar,
+                    rating,
+                })
+                .boxed(),
+            (side(), any::<u8>())
+                .prop_map(|(side, size)| E::TeamSize { side, size })
+                .boxed(),
+            prop_oneof![
+                Just(GameType::Singles),
+                Just(GameType::Doubles),
+                Just(GameType::Triples),
+                Just(GameType::Multi),
+                Just(GameType::FreeForAll),
+            ]
+            .prop_map(E::GameType)
+            .boxed(),
+            any::<u8>().prop_map(E::Gen).boxed(),
+            any::<u32>().prop_map(E::Turn).boxed(),
+            any::<i64>().prop_map(E::Timestamp).boxed(),
+            option::of(any::<u8>()).prop_map(E::TeamPreview).boxed(),
+            option::of(arg()).prop_map(E::Rated).boxed(),
+            (rest(), 0..7)
+                .prop_map(|(text, i)| match i {
+                    0 => E::Tier(text),
+                    1 => E::Rule(text),
+                    2 => E::Win(text),
+                    3 => E::Inactive(text),
+                    4 => E::InactiveOff(text),
+                    5 => E::Hint(text),
+                    _ => E::Message(text),
+                })
+                .boxed(),
+            prop_oneof![
+                Just(E::ClearPoke),
+                Just(E::Start),
+                Just(E::Tie),
+                Just(E::Upkeep),
+                Just(E::SwapSideConditions),
+                Just(E::Center),
+                Just(E::Combine),
+                Just(E::Nothing),
+            ]
+            .boxed(),
+            (side(), details(), any::<bool>())
+                .prop_map(|(side, details, has_item)| E::Poke {
+                    side,
+                    details,
+                    has_item,
+                })
+                .boxed(),
+            (pokemon(), arg(), option::of(pokemon()), tags())
+                .prop_map(|(source, name, target, tags)| E::Move {
+                    source,
+                    name,
+                    target,
+                    tags,
+                })
+                .boxed(),
+            (switch(), 0..4)
+                .prop_map(|(switch, i)| match i {
+                    0 => E::Switch(switch),
+                    1 => E::Drag(switch),
+                    2 => E::Replace(switch),
+                    _ => E::DetailsChange(switch),
+                })
+                .boxed(),
+            (pokemon(), arg(), option::of(hp_status()), tags())
+                .prop_map(|(pokemon, species, hp_status, tags)| E::FormeChange {
+                    pokemon,
+                    species,
+                    hp_status,
+                    tags,
+                })
+                .boxed(),
+            (pokemon(), any::<u8>(), tags())
+                .prop_map(|(pokemon, position, tags)| E::Swap {
+                    pokemon,
+                    position,
+                    tags,
+                })
+                .boxed(),
+            (pokemon(), arg(), option::of(arg()), tags())
+                .prop_map(|(pokemon, reason, move_name, tags)| E::Cant {
+                    pokemon,
+                    reason,
+                    move_name,
+                    tags,
+                })
+                .boxed(),
+            (pokemon(), 0..8)
+                .prop_map(|(pokemon, i)| match i {
+                    0 => E::Faint(pokemon),
+                    1 => E::Crit(pokemon),
+                    2 => E::SuperEffective(pokemon),
+                    3 => E::Resisted(pokemon),
+                    4 => E::Primal(pokemon),
+                    5 => E::ZPower(pokemon),
+                    6 => E::ZBroken(pokemon),
+                    _ => E::MustRecharge(pokemon),
+                })
+                .boxed(),
+            (pokemon(), tags(), 0..6)
+                .prop_map(|(pokemon, tags, i)| match i {
+                    0 => E::CureTeam { pokemon, tags },
+                    1 => E::InvertBoost { pokemon, tags },
+                    2 => E::ClearBoost { pokemon, tags },
+                    3 => E::ClearNegativeBoost { pokemon, tags },
+                    4 => E::Immune { pokemon, tags },
+                    _ => E::EndAbility { pokemon, tags },
+                })
+                .boxed(),
+            (pokemon(), arg(), tags(), 0..8)
+                .prop_map(|(pokemon, text, tags, i)| match i {
+                    0 => E::Block {
+                        pokemon,
+                        effect: text,
+                        tags,
+                    },
+                    1 => E::StartEffect {
+                        pokemon,
+                        effect: text,
+                        tags,
+                    },
+                    2 => E::EndEffect {
+                        pokemon,
+                        effect: text,
+                        tags,
+                    },
+                    3 => E::Item {
+                        pokemon,
+                        item: text,
+                        tags,
+                    },
+                    4 => E::EndItem {
+                        pokemon,
+                        item: text,
+                        tags,
+                    },
+                    5 => E::Ability {
+                        pokemon,
+                        ability: text,
+                        tags,
+                    },
+                    6 => E::SingleMove {
+                        pokemon,
+                        move_name: text,
+                        tags,
+                    },
+                    _ => E::SingleTurn {
+                        pokemon,
+                        move_name: text,
+                        tags,
+                    },
+                })
+                .boxed(),
+            (pokemon(), option::of(arg()), tags())
+                .prop_map(|(pokemon, action, tags)| E::Fail {
+                    pokemon,
+                    action,
+                    tags,
+                })
+                .boxed(),
+            option::of(pokemon()).prop_map(E::NoTarget).boxed(),
+            (pokemon(), option::of(pokemon()), tags())
+                .prop_map(|(source, target, tags)| E::Miss {
+                    source,
+                    target,
+                    tags,
+                })
+                .boxed(),
+            (pokemon(), hp_status(), tags(), 0..3)
+                .prop_map(|(pokemon, hp_status, tags, i)| match i {
+                    0 => E::Damage {
+                        pokemon,
+                        hp_status,
+                        tags,
+                    },
+                    1 => E::Heal {
+                        pokemon,
+                        hp_status,
+                        tags,
+                    },
+                    _ => E::SetHp {
+                        pokemon,
+                        hp_status,
+                        tags,
+                    },
+                })
+                .boxed(),
+            (pokemon(), status(), tags(), any::<bool>())
+                .prop_map(|(pokemon, status, tags, cure)| {
+                    if cure {
+                        E::CureStatus {
+                            pokemon,
+                            status,
+                            tags,
+                        }
+                    } else {
+                        E::Status {
+                            pokemon,
+                            status,
+                            tags,
+                        }
+                    }
+                })
+                .boxed(),
+            (boost(), 0..3)
+                .prop_map(|(boost, i)| match i {
+                    0 => E::Boost(boost),
+                    1 => E::Unboost(boost),
+                    _ => E::SetBoost(boost),
+                })
+                .boxed(),
+            (pokemon(), pokemon(), option::of(arg()), tags())
+                .prop_map(|(source, target, stats, tags)| E::SwapBoost {
+                    source,
+                    target,
+                    stats,
+                    tags,
+                })
+                .boxed(),
+            tags().prop_map(|tags| E::ClearAllBoost { tags }).boxed(),
+            (pokemon(), pokemon(), arg(), tags())
+                .prop_map(|(target, pokemon, effect, tags)| E::ClearPositiveBoost {
+                    target,
+                    pokemon,
+                    effect,
+                    tags,
+                })
+                .boxed(),
+            (pokemon(), pokemon(), tags(), 0..3)
+                .prop_map(|(source, target, tags, i)| match i {
+                    0 => E::CopyBoost {
+                        source,
+                        target,
+                        tags,
+                    },
+                    1 => E::Transform {
+                        pokemon: source,
+                        target,
+                        tags,
+                    },
+                    _ => E::Waiting { source, target },
+                })
+                .boxed(),
+            (arg(), tags(), 0..3)
+                .prop_map(|(text, tags, i)| match i {
+                    0 => E::Weather {
+                        weather: text,
+                        tags,
+                    },
+                    1 => E::FieldStart {
+                        condition: text,
+                        tags,
+                    },
+                    _ => E::FieldEnd {
+                        condition: text,
+                        tags,
+                    },
+                })
+                .boxed(),
+            (side(), arg(), arg(), tags(), any::<bool>())
+                .prop_map(|(side, player, condition, tags, end)| {
+                    if end {
+                        E::SideEnd {
+                            side,
+                            player,
+                            condition,
+                            tags,
+                        }
+                    } else {
+                        E::SideStart {
+                            side,
+                            player,
+                            condition,
+                            tags,
+                        }
+                    }
+                })
+                .boxed(),
+            (pokemon(), option::of(arg()))
+                .prop_map(|(pokemon, mega_stone)| E::Mega {
+                    pokemon,
+                    mega_stone,
+                })
+                .boxed(),
+            (pokemon(), arg(), option::of(arg()))
+                .prop_map(|(pokemon, species, item)| E::Burst {
+                    pokemon,
+                    species,
+                    item,
+                })
+                .boxed(),
+            (pokemon(), arg())
+                .prop_map(|(pokemon, tera_type)| E::Terastallize { pokemon, tera_type })
+                .boxed(),
+            (option::of(pokemon()), arg(), tags())
+                .prop_map(|(pokemon, effect, tags)| E::Activate {
+                    pokemon,
+                    effect,
+                    tags,
+                })
+                .boxed(),
+            (pokemon(), arg(), option::of(pokemon()))
+                .prop_map(|(attacker, move_name, defender)| E::Prepare {
+                    attacker,
+                    move_name,
+                    defender,
+                })
+                .boxed(),
+            (pokemon(), any::<u8>())
+                .prop_map(|(pokemon, count)| E::HitCount { pokemon, count })
+                .boxed(),
+        ])
+    }
+
+    fn kind() -> impl Strategy<Value = Kind<'static>> {
+        Union::new(vec![
+            ("[0-9]{1,10}", arg(), rest())
+                .prop_map(|(timestamp, user, message)| {
+                    Kind::Chat(Chat {
+                        timestamp: Cow::Owned(timestamp),
+                        user,
+                        message,
+                    })
+                })
+                .boxed(),
+            (arg(), arg(), rest())
+                .prop_map(|(from, to, message)| {
+                    Kind::Private(Private {
+                        from: User::new(from),
+                        to: User::new(to),
+                        message,
+                    })
+                })
+                .boxed(),
+            rest().prop_map(|user| Kind::Join(User::new(user))).boxed(),
+            rest().prop_map(|user| Kind::Leave(User::new(user))).boxed(),
+            rest().prop_map(Kind::Html).boxed(),
+            (arg(), rest())
+                .prop_map(|(new, old)| Kind::NicknameChange(User::new(new), old))
+                .boxed(),
+            (
+                prop_oneof![
+                    Just(NoInitKind::Nonexistent),
+                    Just(NoInitKind::JoinFailed),
+                    Just(NoInitKind::NameRequired),
+                ],
+                rest(),
+            )
+                .prop_map(|(kind, reason)| Kind::NoInit(NoInit { kind, reason }))
+                .boxed(),
+            (any::<bool>(), rest(), room_users())
+                .prop_map(|(battle, title, users)| {
+                    Kind::RoomInit(RoomInit {
+                        room_type: if battle {
+                            RoomType::Battle
+                        } else {
+                            RoomType::Chat
+                        },
+                        title,
+                        users,
+                    })
+                })
+                .boxed(),
+            (arg(), any::<bool>(), arg())
+                .prop_map(|(username, named, avatar)| {
+                    Kind::UpdateUser(UpdateUser {
+                        username,
+                        named,
+                        avatar,
+                    })
+                })
+                .boxed(),
+            (
+                btree_map(rest(), rest(), 0..3),
+                option::of((rest(), rest()).prop_map(|(to, format)| ChallengeTo { to, format })),
+            )
+                .prop_map(|(challenges_from, challenge_to)| {
+                    Kind::UpdateChallenges(UpdateChallenges {
+                        challenges_from,
+                        challenge_to,
+                    })
+                })
+                .boxed(),
+            (
+                vec(rest(), 0..3),
+                option::of(btree_map(rest(), rest(), 0..3)),
+            )
+                .prop_map(|(searching, games)| {
+                    Kind::UpdateSearch(UpdateSearch { searching, games })
+                })
+                .boxed(),
+            vec(format_section(), 0..3)
+                .prop_map(|sections| Kind::Formats(FormatList { sections }))
+                .boxed(),
+            tournament_event().prop_map(Kind::Tournament).boxed(),
+            battle_event().prop_map(Kind::Battle).boxed(),
+            battle_request().prop_map(Kind::Request).boxed(),
+            rooms_list()
+                .prop_map(|rooms| Kind::QueryResponse(QueryResponse::Rooms(rooms)))
+                .boxed(),
+            rest()
+                .prop_map(|challenge| Kind::Challenge(Challenge(challenge)))
+                .boxed(),
+            "[^|>\n][^\n]{0,20}"
+                .prop_map(|line| Kind::Unrecognized(UnrecognizedMessage(Cow::Owned(line))))
+                .boxed(),
+        ])
+    }
+
+    fn rooms_list() -> impl Strategy<Value = RoomsList<'static>> {
+        let rooms = || {
+            let room = (rest(), rest(), any::<u32>(), vec(rest(), 0..3)).prop_map(
+                |(title, desc, user_count, sub_rooms)| Room {
+                    title,
+                    desc,
+                    user_count,
+                    sub_rooms,
+                },
+            );
+            vec(room, 0..3)
+        };
+        (rooms(), rooms(), rooms(), any::<u32>(), any::<u32>()).prop_map(
+            |(official, pspl, chat, user_count, battle_count)| RoomsList {
+                official,
+                pspl,
+                chat,
+                user_count,
+                battle_count,
+            },
+        )
+    }
+
+    fn battle_request() -> impl Strategy<Value = BattleRequest<'static>> {
+        let side = (rest(), side(), vec(request_pokemon(), 0..3))
+            .prop_map(|(name, id, pokemon)| RequestSide { name, id, pokemon });
+        (
+            vec(active_request(), 0..3),
+            side,
+            option::of(any::<u32>()),
+            vec(any::<bool>(), 0..3),
+            any::<(bool, bool, bool)>(),
+            option::of(any::<u8>()),
+        )
+            .prop_map(
+                |(
+                    active,
+                    side,
+                    rqid,
+                    force_switch,
+                    (wait, team_preview, no_cancel),
+                    max_team_size,
+                )| {
+                    BattleRequest {
+                        active,
+                        side,
+                        rqid,
+                        force_switch,
+                        wait,
+                        team_preview,
+                        max_team_size,
+                        no_cancel,
+                    }
+                },
+            )
+    }
+
+    fn active_request() -> impl Strategy<Value = ActiveRequest<'static>> {
+        let move_slot = (
+            rest(),
+            rest(),
+            option::of(any::<u8>()),
+            option::of(any::<u8>()),
+            option::of(rest()),
+            any::<bool>(),
+        )
+            .prop_map(|(name, id, pp, maxpp, target, disabled)| MoveSlot {
+                name,
+                id,
+                pp,
+                maxpp,
+                target,
+                disabled,
+            });
+        let z_move = (rest(), rest()).prop_map(|(name, target)| ZMove { name, target });
+        let max_move =
+            (rest(), rest(), any::<bool>()).prop_map(|(name, target, disabled)| MaxMove {
+                name,
+                target,
+                disabled,
+            });
+        let max_moves =
+            (vec(max_move, 0..3), option::of(rest())).prop_map(|(max_moves, gigantamax)| {
+                MaxMoves {
+                    max_moves,
+                    gigantamax,
+                }
+            });
+        (
+            vec(move_slot, 0..3),
+            any::<(bool, bool, bool, bool, bool)>(),
+            option::of(vec(option::of(z_move), 0..3)),
+            option::of(max_moves),
+            option::of(rest()),
+        )
+            .prop_map(
+                |(
+                    moves,
+                    (trapped, maybe_trapped, can_mega_evo, can_ultra_burst, can_dynamax),
+                    can_z_move,
+                    max_moves,
+                    can_terastallize,
+                )| ActiveRequest {
+                    moves,
+                    trapped,
+                    maybe_trapped,
+                    can_mega_evo,
+                    can_ultra_burst,
+                    can_z_move,
+                    can_dynamax,
+                    max_moves,
+                    can_terastallize,
+                },
+            )
+    }
+
+    fn request_pokemon() -> impl Strategy<Value = RequestPokemon<'static>> {
+        (
+            (rest(), rest(), rest(), any::<bool>()),
+            any::<(u16, u16, u16, u16, u16)>(),
+            vec(rest(), 0..4),
+            (rest(), rest(), rest()),
+            (option::of(rest()), option::of(rest()), option::of(rest())),
+        )
+            .prop_map(
+                |(
+                    (ident, details, condition, active),
+                    (atk, def, spa, spd, spe),
+                    moves,
+                    (base_ability, item, pokeball),
+                    (ability, tera_type, terastallized),
+                )| RequestPokemon {
+                    ident,
+                    details,
+                    condition,
+                    active,
+                    stats: Stats {
+                        atk,
+                        def,
+                        spa,
+                        spd,
+                        spe,
+                    },
+                    moves,
+                    base_ability,
+                    item,
+                    pokeball,
+                    ability,
+                    tera_type,
+                    terastallized,
+                },
+            )
+    }
+
+    fn tournament_event() -> impl Strategy<Value = TournamentEvent<'static>> {
+        type E = TournamentEvent<'static>;
+        let result = prop_oneof![
+            Just(TournamentResult::Win),
+            Just(TournamentResult::Loss),
+            Just(TournamentResult::Draw),
+        ];
+        prop_oneof![
+            (arg(), arg(), option::of(1..=u32::MAX)).prop_map(|(format, generator, player_cap)| {
+                E::Create {
+                    format,
+                    generator,
+                    player_cap,
+                }
+            }),
+            (
+                option::of("[^\n]{0,12}"),
+                option::of(any::<bool>()),
+                option::of(option::of("[^\n]{0,12}")),
+            )
+                .prop_map(|(format, is_joined, challenged)| {
+                    E::Update(TournamentUpdate {
+                        format,
+                        is_joined,
+                        challenged,
+                        ..TournamentUpdate::default()
+                    })
+                }),
+            Just(E::UpdateEnd),
+            rest().prop_map(E::Error),
+            arg().prop_map(E::Join),
+            arg().prop_map(E::Leave),
+            (arg(), arg()).prop_map(|(old, new)| E::Replace { old, new }),
+            any::<u32>().prop_map(E::Start),
+            arg().prop_map(E::Disqualify),
+            (arg(), arg(), arg()).prop_map(|(player1, player2, room)| E::BattleStart {
+                player1,
+                player2,
+                room,
+            }),
+            (
+                (arg(), arg(), result),
+                (vec(any::<u32>(), 1..3), any::<bool>(), arg()),
+            )
+                .prop_map(|((player1, player2, result), (score, recorded, room))| {
+                    E::BattleEnd {
+                        player1,
+                        player2,
+                        result,
+                        score,
+                        recorded,
+                        room,
+                    }
+                }),
+            Just(E::ForceEnd),
+        ]
+    }
+
+    fn format_section() -> impl Strategy<Value = FormatSection<'static>> {
+        let name = || "[^|\n,][^|\n]{0,12}".prop_map(Cow::Owned);
+        (
+            any::<u32>(),
+            "[^|\n]{0,12}".prop_map(Cow::Owned),
+            vec((name(), any::<u32>()), 0..3),
+        )
+            .prop_map(|(column, name, formats)| FormatSection {
+                column,
+                name,
+                formats: formats
+                    .into_iter()
+                    .map(|(name, flags)| Format { name, flags })
+                    .collect(),
+            })
+    }
+
+    proptest! {
+        #[test]
+        fn kind_round_trip(kind in kind()) {
+            let message = ServerMessage::room(RoomId("battle-gen9ou-1")).kind(&kind).into_message();
+            prop_assert_eq!(message.kind(), kind);
+        }
+    }
+
+    #[test]
+    fn encode_exact_lines() {
+        for line in [
+            "|move|p1a: Pikachu|Thunderbolt||[still]|[miss]",
+            "|move|p1a: Pikachu|Thunderbolt|p2a: Garchomp",
+            "|cant|p2a: Garchomp|par||[of] p1a: Pikachu",
+            "|cant|p2a: Garchomp|flinch",
+            "|-sidestart|p1: xfix|move: Stealth Rock",
+            "|-sideend|p2: Someone|Reflect|[from] move: Defog|[of] p1a: Pikachu",
+        ] {
+            let message = ServerMessage::room(RoomId("battle-gen9ou-1"))
+                .line(line)
+                .into_message();
+            assert_eq!(message.kind().to_string(), line);
+        }
+    }
+
+    #[test]
+    fn request_round_trip() {
+        let raw = r#"|request|{"active":[{"moves":[{"move":"Protect","id":"protect","pp":0,"maxpp":16,"target":"self","disabled":"Imprison"}],"canTerastallize":"Electric"}],"side":{"name":"xfix","id":"p1","pokemon":[{"ident":"p1: Pikachu","details":"Pikachu, L50, M","condition":"95/120 par","active":true,"stats":{"atk":80,"def":60,"spa":75,"spd":70,"spe":110},"moves":["protect"],"baseAbility":"static","item":"lightball","pokeball":"pokeball"}]},"rqid":3}"#;
+        let message = ServerMessage::new().line(raw).into_message();
+        let kind = message.kind();
+        assert!(matches!(kind, Kind::Request(_)));
+        let encoded = ServerMessage::new().kind(&kind).into_message();
+        assert_eq!(encoded.kind(), kind);
+    }
+
+    #[test]
+    fn rooms_round_trip() {
+        let raw = r#"|queryresponse|rooms|{"official":[{"title":"Lobby","desc":"Chat \"here\"","userCount":42,"subRooms":["Help"]}],"pspl":[],"chat":[],"userCount":100,"battleCount":12}"#;
+        let message = ServerMessage::new().line(raw).into_message();
+        let kind = message.kind();
+        assert!(matches!(kind, Kind::QueryResponse(_)));
+        let encoded = ServerMessage::new().kind(&kind).into_message();
+        assert_eq!(encoded.kind(), kind);
+    }
+
+    #[test]
+    fn tournament_round_trip() {
+        for raw in [
+            r#"|tournament|update|{"challenged":null,"playerCap":8,"bracketData":{"type":"tree","rootNode":{"children":[{"team":"Zarel"},{"team":"xfix"}],"state":"inprogress","room":"battle-gen9ou-1"}}}"#,
+            r#"|tournament|end|{"results":[["xfix"]],"format":"gen9ou","generator":"Round Robin","bracketData":{"type":"table","tableHeaders":{"cols":["Zarel","xfix"],"rows":["Zarel","xfix"]},"tableContents":[[null,{"state":"finished","result":"loss","score":[0,1]}],[{"state":"finished","result":"win","score":[1,0]},null]],"scores":[0,1.5]}}"#,
+        ] {
+            let message = ServerMessage::new().line(raw).into_message();
+            let kind = message.kind();
+            assert!(matches!(kind, Kind::Tournament(_)), "{}", raw);
+            let encoded = ServerMessage::new().kind(&kind).into_message();
+            assert_eq!(encoded.kind(), kind);
+        }
+    }
+}
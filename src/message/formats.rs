@@ -0,0 +1,203 @@
+use super::owned;
+use crate::to_id;
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+
+/// Formats available on a server, sent as `|formats|` after connecting.
+///
+/// # Examples
+///
+/// ```
+/// use showdown::message::{Kind, Message};
+///
+/// fn can_search(message: &Message, format: &str) -> Option<bool> {
+///     match message.kind() {
+///         Kind::Formats(formats) => Some(formats.get(format)?.is_searchable()),
+///         _ => None,
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct FormatList<'a> {
+    #[cfg_attr(feature = "serialize", serde(borrow))]
+    pub sections: Vec<FormatSection<'a>>,
+}
+
+impl<'a> FormatList<'a> {
+    pub(super) fn parse(arguments: &'a str) -> Self {
+        let mut list = FormatList::default();
+        let mut parts = arguments.split('\n').next().unwrap().split('|');
+        while let Some(part) = parts.next() {
+            if let Some(column) = part.strip_prefix(',') {
+                list.sections.push(FormatSection {
+                    column: column.parse().unwrap_or(0),
+                    name: parts.next().unwrap_or("").into(),
+                    formats: Vec::new(),
+                });
+            } else if !part.is_empty() {
+                if list.sections.is_empty() {
+                    list.sections.push(FormatSection::default());
+                }
+                list.sections
+                    .last_mut()
+                    .unwrap()
+                    .formats
+                    .push(Format::parse(part));
+            }
+        }
+        list
+    }
+
+    /// Iterates over formats in every section.
+    pub fn formats(&self) -> impl Iterator<Item = &Format<'a>> {
+        self.sections
+            .iter()
+            .flat_map(|section| section.formats.iter())
+    }
+
+    /// Gets a format by its ID or name, like `gen9ou` or `[Gen 9] OU`.
+    pub fn get(&self, id: &str) -> Option<&Format<'a>> {
+        let id = to_id(id);
+        self.formats().find(|format| format.id() == id)
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.get(id).is_some()
+    }
+
+    pub fn into_owned(self) -> FormatList<'static> {
+        FormatList {
+            sections: self
+                .sections
+                .into_iter()
+                .map(FormatSection::into_owned)
+                .collect(),
+        }
+    }
+}
+
+/// Group of formats, like `S/V Singles`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct FormatSection<'a> {
+    /// Column of the format selector the section is displayed in.
+    pub column: u32,
+    #[cfg_attr(feature = "serialize", serde(borrow))]
+    pub name: Cow<'a, str>,
+    #[cfg_attr(feature = "serialize", serde(borrow))]
+    pub formats: Vec<Format<'a>>,
+}
+
+impl FormatSection<'_> {
+    pub fn into_owned(self) -> FormatSection<'static> {
+        FormatSection {
+            column: self.column,
+            name: owned(self.name),
+            formats: self.formats.into_iter().map(Format::into_owned).collect(),
+        }
+    }
+}
+
+/// Format, like `[Gen 9] OU`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct Format<'a> {
+    #[cfg_attr(feature = "serialize", serde(borrow))]
+    pub name: Cow<'a, str>,
+    /// Flags sent by the server as a hexadecimal number.
+    pub flags: u32,
+}
+
+impl<'a> Format<'a> {
+    const PRESET_TEAM: u32 = 1;
+    const SEARCHABLE: u32 = 2;
+    const CHALLENGEABLE: u32 = 4;
+    const TOURNAMENT: u32 = 8;
+    const LEVEL_50: u32 = 16;
+
+    fn parse(part: &'a str) -> Self {
+        let flags = part
+            .rsplit_once(',')
+            .and_then(|(name, flags)| Some((name, u32::from_str_radix(flags, 16).ok()?)));
+        match flags {
+            Some((name, flags)) => Self {
+                name: name.into(),
+                flags,
+            },
+            None => Self {
+                name: part.into(),
+                flags: Self::SEARCHABLE | Self::CHALLENGEABLE | Self::TOURNAMENT,
+            },
+        }
+    }
+
+    pub fn id(&self) -> String {
+        to_id(&self.name)
+    }
+
+    /// Checks whether players need to provide a team, which is not the
+    /// case for formats with random teams.
+    pub fn requires_team(&self) -> bool {
+        self.flags & Self::PRESET_TEAM == 0
+    }
+
+    /// Checks whether the format can be searched for with `/search`.
+    pub fn is_searchable(&self) -> bool {
+        self.flags & Self::SEARCHABLE != 0
+    }
+
+    /// Checks whether users can be challenged in the format.
+    pub fn is_challengeable(&self) -> bool {
+        self.flags & Self::CHALLENGEABLE != 0
+    }
+
+    /// Checks whether tournaments can be created in the format.
+    pub fn is_tournament_allowed(&self) -> bool {
+        self.flags & Self::TOURNAMENT != 0
+    }
+
+    /// Checks whether the teambuilder should default to level 50.
+    pub fn is_level_50(&self) -> bool {
+        self.flags & Self::LEVEL_50 != 0
+    }
+
+    pub fn into_owned(self) -> Format<'static> {
+        Format {
+            name: owned(self.name),
+            flags: self.flags,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::message::{Kind, Message};
+
+    #[test]
+    fn parse_formats() {
+        let message = Message {
+            raw: "|formats|,1|S/V Singles|[Gen 9] Random Battle,f|[Gen 9] OU,e|[Gen 9] Custom Game,c|,1|S/V Doubles|[Gen 9] VGC 2024 Reg G,1e|,2|Past Gens OU|[Gen 8] OU,e".into(),
+        };
+        let formats = match message.kind() {
+            Kind::Formats(formats) => formats,
+            kind => panic!("Unexpected kind {:?}", kind),
+        };
+        assert_eq!(formats.sections.len(), 3);
+        assert_eq!(formats.sections[2].column, 2);
+        assert_eq!(formats.sections[1].name, "S/V Doubles");
+        assert_eq!(formats.formats().count(), 5);
+        let random = formats.get("gen9randombattle").unwrap();
+        assert!(!random.requires_team());
+        assert!(random.is_searchable());
+        let ou = formats.get("[Gen 9] OU").unwrap();
+        assert!(ou.requires_team());
+        assert!(ou.is_tournament_allowed());
+        let custom = formats.get("gen9customgame").unwrap();
+        assert!(!custom.is_searchable());
+        assert!(custom.is_challengeable());
+        assert!(formats.get("gen9vgc2024regg").unwrap().is_level_50());
+        assert!(!formats.contains("gen9ubers"));
+    }
+}
@@ -0,0 +1,146 @@
+use super::owned;
+use serde::Deserialize;
+#[cfg(feature = "serialize")]
+use serde::Serialize;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+/// Pending challenges, sent as `|updatechallenges|` whenever they
+/// change.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateChallenges<'a> {
+    /// Challenges received by the user, mapping userids of challengers
+    /// to formats.
+    #[serde(borrow, default)]
+    pub challenges_from: BTreeMap<Cow<'a, str>, Cow<'a, str>>,
+    /// Challenge sent by the user.
+    #[serde(borrow)]
+    pub challenge_to: Option<ChallengeTo<'a>>,
+}
+
+impl<'a> UpdateChallenges<'a> {
+    pub(super) fn parse(arguments: &'a str) -> Option<Self> {
+        serde_json::from_str(arguments).ok()
+    }
+
+    pub fn into_owned(self) -> UpdateChallenges<'static> {
+        UpdateChallenges {
+            challenges_from: self
+                .challenges_from
+                .into_iter()
+                .map(|(user, format)| (owned(user), owned(format)))
+                .collect(),
+            challenge_to: self.challenge_to.map(ChallengeTo::into_owned),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct ChallengeTo<'a> {
+    /// Userid of the challenged user.
+    #[serde(borrow)]
+    pub to: Cow<'a, str>,
+    #[serde(borrow)]
+    pub format: Cow<'a, str>,
+}
+
+impl ChallengeTo<'_> {
+    pub fn into_owned(self) -> ChallengeTo<'static> {
+        ChallengeTo {
+            to: owned(self.to),
+            format: owned(self.format),
+        }
+    }
+}
+
+/// Ladder searches and battles, sent as `|updatesearch|` whenever they
+/// change.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct UpdateSearch<'a> {
+    /// Formats the user is searching a battle in.
+    #[serde(borrow, default)]
+    pub searching: Vec<Cow<'a, str>>,
+    /// Battles the user is playing, mapping room IDs to titles.
+    #[serde(borrow, default)]
+    pub games: Option<BTreeMap<Cow<'a, str>, Cow<'a, str>>>,
+}
+
+impl<'a> UpdateSearch<'a> {
+    pub(super) fn parse(arguments: &'a str) -> Option<Self> {
+        serde_json::from_str(arguments).ok()
+    }
+
+    pub fn into_owned(self) -> UpdateSearch<'static> {
+        UpdateSearch {
+            searching: self.searching.into_iter().map(owned).collect(),
+            games: self.games.map(|games| {
+                games
+                    .into_iter()
+                    .map(|(room, title)| (owned(room), owned(title)))
+                    .collect()
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::message::{ChallengeTo, Kind, Message, UpdateChallenges, UpdateSearch};
+
+    fn kind(raw: &str) -> Kind<'static> {
+        Message { raw: raw.into() }.kind().into_owned()
+    }
+
+    #[test]
+    fn parse_update_challenges() {
+        let parsed = kind(
+            r#"|updatechallenges|{"challengesFrom":{"zarel":"gen9ou"},"challengeTo":{"to":"xfix","format":"gen9randombattle"}}"#,
+        );
+        let challenges = match parsed {
+            Kind::UpdateChallenges(challenges) => challenges,
+            kind => panic!("Unexpected kind {:?}", kind),
+        };
+        assert_eq!(challenges.challenges_from["zarel"], "gen9ou");
+        assert_eq!(
+            challenges.challenge_to,
+            Some(ChallengeTo {
+                to: "xfix".into(),
+                format: "gen9randombattle".into(),
+            }),
+        );
+        assert_eq!(
+            kind(r#"|updatechallenges|{"challengesFrom":{},"challengeTo":null}"#),
+            Kind::UpdateChallenges(UpdateChallenges {
+                challenges_from: Default::default(),
+                challenge_to: None,
+            }),
+        );
+    }
+
+    #[test]
+    fn parse_update_search() {
+        let parsed = kind(
+            r#"|updatesearch|{"searching":["gen9ou"],"games":{"battle-gen9ou-1":"[Gen 9] OU Battle"}}"#,
+        );
+        let search = match parsed {
+            Kind::UpdateSearch(search) => search,
+            kind => panic!("Unexpected kind {:?}", kind),
+        };
+        assert_eq!(search.searching, ["gen9ou"]);
+        assert_eq!(
+            search.games.unwrap()["battle-gen9ou-1"],
+            "[Gen 9] OU Battle"
+        );
+        assert_eq!(
+            kind(r#"|updatesearch|{"searching":[],"games":null}"#),
+            Kind::UpdateSearch(UpdateSearch {
+                searching: Vec::new(),
+                games: None,
+            }),
+        );
+    }
+}
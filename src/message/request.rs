@@ -0,0 +1,396 @@
+use super::{owned, Details, HpStatus, Pokemon, Side};
+#[cfg(feature = "serialize")]
+use serde::Serialize;
+use serde::{Deserialize, Deserializer};
+use std::borrow::Cow;
+
+/// Decision request, sent as `|request|` to players of a battle.
+///
+/// Exactly one of [`active`](Self::active), [`force_switch`](Self::force_switch),
+/// [`team_preview`](Self::team_preview) or [`wait`](Self::wait) describes
+/// what kind of decision is expected.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[serde(rename_all = "camelCase")]
+pub struct BattleRequest<'a> {
+    /// Active Pokémon and their available moves, one per active slot.
+    #[serde(borrow, default)]
+    pub active: Vec<ActiveRequest<'a>>,
+    #[serde(borrow)]
+    pub side: RequestSide<'a>,
+    /// Request ID, used to make sure the decision is for this request.
+    pub rqid: Option<u32>,
+    /// Which active slots need to switch in a new Pokémon.
+    #[serde(default)]
+    pub force_switch: Vec<bool>,
+    /// No decision is needed, the opponent is deciding.
+    #[serde(default)]
+    pub wait: bool,
+    #[serde(default)]
+    pub team_preview: bool,
+    /// How many Pokémon can be brought to the battle in team preview,
+    /// like 4 in VGC.
+    #[serde(rename = "maxChosenTeamSize")]
+    pub max_team_size: Option<u8>,
+    #[serde(default)]
+    pub no_cancel: bool,
+}
+
+impl<'a> BattleRequest<'a> {
+    pub(super) fn parse(arguments: &'a str) -> Option<Self> {
+        serde_json::from_str(arguments.split('\n').next().unwrap()).ok()
+    }
+
+    pub fn into_owned(self) -> BattleRequest<'static> {
+        BattleRequest {
+            active: self
+                .active
+                .into_iter()
+                .map(ActiveRequest::into_owned)
+                .collect(),
+            side: self.side.into_owned(),
+            rqid: self.rqid,
+            force_switch: self.force_switch,
+            wait: self.wait,
+            team_preview: self.team_preview,
+            max_team_size: self.max_team_size,
+            no_cancel: self.no_cancel,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[serde(rename_all = "camelCase")]
+pub struct ActiveRequest<'a> {
+    #[serde(borrow)]
+    pub moves: Vec<MoveSlot<'a>>,
+    #[serde(default)]
+    pub trapped: bool,
+    #[serde(default)]
+    pub maybe_trapped: bool,
+    #[serde(default)]
+    pub can_mega_evo: bool,
+    #[serde(default)]
+    pub can_ultra_burst: bool,
+    /// Z-Moves available for each move slot.
+    #[serde(borrow, default)]
+    pub can_z_move: Option<Vec<Option<ZMove<'a>>>>,
+    #[serde(default)]
+    pub can_dynamax: bool,
+    #[serde(borrow)]
+    pub max_moves: Option<MaxMoves<'a>>,
+    /// Type the Pokémon can terastallize into.
+    #[serde(borrow)]
+    pub can_terastallize: Option<Cow<'a, str>>,
+}
+
+impl ActiveRequest<'_> {
+    pub fn into_owned(self) -> ActiveRequest<'static> {
+        ActiveRequest {
+            moves: self.moves.into_iter().map(MoveSlot::into_owned).collect(),
+            trapped: self.trapped,
+            maybe_trapped: self.maybe_trapped,
+            can_mega_evo: self.can_mega_evo,
+            can_ultra_burst: self.can_ultra_burst,
+            can_z_move: self.can_z_move.map(|z_moves| {
+                z_moves
+                    .into_iter()
+                    .map(|z_move| z_move.map(ZMove::into_owned))
+                    .collect()
+            }),
+            can_dynamax: self.can_dynamax,
+            max_moves: self.max_moves.map(MaxMoves::into_owned),
+            can_terastallize: self.can_terastallize.map(owned),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct MoveSlot<'a> {
+    #[serde(borrow, rename = "move")]
+    pub name: Cow<'a, str>,
+    #[serde(borrow)]
+    pub id: Cow<'a, str>,
+    pub pp: Option<u8>,
+    pub maxpp: Option<u8>,
+    #[serde(borrow)]
+    pub target: Option<Cow<'a, str>>,
+    #[serde(default, deserialize_with = "disabled")]
+    pub disabled: bool,
+}
+
+impl MoveSlot<'_> {
+    pub fn into_owned(self) -> MoveSlot<'static> {
+        MoveSlot {
+            name: owned(self.name),
+            id: owned(self.id),
+            pp: self.pp,
+            maxpp: self.maxpp,
+            target: self.target.map(owned),
+            disabled: self.disabled,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct ZMove<'a> {
+    #[serde(borrow, rename = "move")]
+    pub name: Cow<'a, str>,
+    #[serde(borrow)]
+    pub target: Cow<'a, str>,
+}
+
+impl ZMove<'_> {
+    pub fn into_owned(self) -> ZMove<'static> {
+        ZMove {
+            name: owned(self.name),
+            target: owned(self.target),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[serde(rename_all = "camelCase")]
+pub struct MaxMoves<'a> {
+    #[serde(borrow)]
+    pub max_moves: Vec<MaxMove<'a>>,
+    /// Gigantamax species, if the Pokémon can gigantamax.
+    #[serde(borrow)]
+    pub gigantamax: Option<Cow<'a, str>>,
+}
+
+impl MaxMoves<'_> {
+    pub fn into_owned(self) -> MaxMoves<'static> {
+        MaxMoves {
+            max_moves: self
+                .max_moves
+                .into_iter()
+                .map(MaxMove::into_owned)
+                .collect(),
+            gigantamax: self.gigantamax.map(owned),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct MaxMove<'a> {
+    #[serde(borrow, rename = "move")]
+    pub name: Cow<'a, str>,
+    #[serde(borrow)]
+    pub target: Cow<'a, str>,
+    #[serde(default, deserialize_with = "disabled")]
+    pub disabled: bool,
+}
+
+impl MaxMove<'_> {
+    pub fn into_owned(self) -> MaxMove<'static> {
+        MaxMove {
+            name: owned(self.name),
+            target: owned(self.target),
+            disabled: self.disabled,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct RequestSide<'a> {
+    #[serde(borrow)]
+    pub name: Cow<'a, str>,
+    #[serde(deserialize_with = "side")]
+    pub id: Side,
+    #[serde(borrow)]
+    pub pokemon: Vec<RequestPokemon<'a>>,
+}
+
+impl RequestSide<'_> {
+    pub fn into_owned(self) -> RequestSide<'static> {
+        RequestSide {
+            name: owned(self.name),
+            id: self.id,
+            pokemon: self
+                .pokemon
+                .into_iter()
+                .map(RequestPokemon::into_owned)
+                .collect(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[serde(rename_all = "camelCase")]
+pub struct RequestPokemon<'a> {
+    #[serde(borrow)]
+    pub ident: Cow<'a, str>,
+    #[serde(borrow)]
+    pub details: Cow<'a, str>,
+    #[serde(borrow)]
+    pub condition: Cow<'a, str>,
+    pub active: bool,
+    pub stats: Stats,
+    #[serde(borrow)]
+    pub moves: Vec<Cow<'a, str>>,
+    #[serde(borrow)]
+    pub base_ability: Cow<'a, str>,
+    #[serde(borrow)]
+    pub item: Cow<'a, str>,
+    #[serde(borrow)]
+    pub pokeball: Cow<'a, str>,
+    #[serde(borrow)]
+    pub ability: Option<Cow<'a, str>>,
+    #[serde(borrow)]
+    pub tera_type: Option<Cow<'a, str>>,
+    #[serde(borrow)]
+    pub terastallized: Option<Cow<'a, str>>,
+}
+
+impl RequestPokemon<'_> {
+    pub fn into_owned(self) -> RequestPokemon<'static> {
+        RequestPokemon {
+            ident: owned(self.ident),
+            details: owned(self.details),
+            condition: owned(self.condition),
+            active: self.active,
+            stats: self.stats,
+            moves: self.moves.into_iter().map(owned).collect(),
+            base_ability: owned(self.base_ability),
+            item: owned(self.item),
+            pokeball: owned(self.pokeball),
+            ability: self.ability.map(owned),
+            tera_type: self.tera_type.map(owned),
+            terastallized: self.terastallized.map(owned),
+        }
+    }
+
+    /// Parses [`ident`](Self::ident).
+    pub fn pokemon(&self) -> Option<Pokemon<'_>> {
+        Pokemon::parse(&self.ident)
+    }
+
+    /// Parses [`details`](Self::details).
+    pub fn parsed_details(&self) -> Option<Details<'_>> {
+        Details::parse(&self.details)
+    }
+
+    /// Parses [`condition`](Self::condition).
+    pub fn hp_status(&self) -> Option<HpStatus> {
+        HpStatus::parse(&self.condition)
+    }
+}
+
+#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct Stats {
+    pub atk: u16,
+    pub def: u16,
+    pub spa: u16,
+    pub spd: u16,
+    pub spe: u16,
+}
+
+/// Deserializes `disabled` field which is either a boolean or a source of
+/// disabling (like `"Imprison"`).
+fn disabled<'de, D: Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Disabled<'a> {
+        Bool(bool),
+        Source(#[serde(borrow)] Cow<'a, str>),
+    }
+    Ok(match Disabled::deserialize(deserializer)? {
+        Disabled::Bool(disabled) => disabled,
+        Disabled::Source(source) => !source.is_empty(),
+    })
+}
+
+fn side<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Side, D::Error> {
+    let id = Cow::<str>::deserialize(deserializer)?;
+    id.parse()
+        .map_err(|()| serde::de::Error::custom(format_args!("invalid side {:?}", id)))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::message::{Kind, Message, Side};
+
+    #[test]
+    fn parse_move_request() {
+        let message = Message {
+            raw: r#">battle-gen9ou-1
+|request|{"active":[{"moves":[{"move":"Thunderbolt","id":"thunderbolt","pp":24,"maxpp":24,"target":"normal","disabled":false},{"move":"Protect","id":"protect","pp":0,"maxpp":16,"target":"self","disabled":"Imprison"}],"canTerastallize":"Electric"}],"side":{"name":"xfix","id":"p1","pokemon":[{"ident":"p1: Pikachu","details":"Pikachu, L50, M","condition":"95/120 par","active":true,"stats":{"atk":80,"def":60,"spa":75,"spd":70,"spe":110},"moves":["thunderbolt","protect"],"baseAbility":"static","item":"lightball","pokeball":"pokeball","ability":"static","teraType":"Electric","terastallized":""}]},"rqid":3}"#
+                .into(),
+        };
+        let request = match message.kind() {
+            Kind::Request(request) => request,
+            kind => panic!("{:?}", kind),
+        };
+        assert_eq!(request.rqid, Some(3));
+        assert!(!request.wait);
+        let moves = &request.active[0].moves;
+        assert_eq!(moves[0].name, "Thunderbolt");
+        assert!(!moves[0].disabled);
+        assert!(moves[1].disabled);
+        assert_eq!(
+            request.active[0].can_terastallize.as_deref(),
+            Some("Electric")
+        );
+        assert_eq!(request.side.id, Side::P1);
+        let pokemon = &request.side.pokemon[0];
+        assert_eq!(pokemon.pokemon().unwrap().name, "Pikachu");
+        assert_eq!(pokemon.parsed_details().unwrap().level, 50);
+        assert_eq!(pokemon.hp_status().unwrap().max_hp, Some(120));
+    }
+
+    #[test]
+    fn parse_force_switch_request() {
+        let message = Message {
+            raw: r#">battle-gen9ou-1
+|request|{"forceSwitch":[true],"side":{"name":"xfix","id":"p2","pokemon":[]},"noCancel":true,"rqid":7}"#
+                .into(),
+        };
+        match message.kind() {
+            Kind::Request(request) => {
+                assert_eq!(request.force_switch, [true]);
+                assert!(request.active.is_empty());
+                assert!(request.no_cancel);
+            }
+            kind => panic!("{:?}", kind),
+        }
+    }
+
+    #[test]
+    fn parse_team_preview_request() {
+        let pokemon = ["Flutter Mane", "Chien-Pao", "Urshifu", "Rillaboom", "Amoonguss", "Ogerpon"]
+            .iter()
+            .enumerate()
+            .map(|(i, species)| {
+                format!(
+                    r#"{{"ident":"p1: {0}","details":"{0}, L50","condition":"100/100","active":false,"stats":{{"atk":100,"def":100,"spa":100,"spd":100,"spe":100}},"moves":["protect"],"baseAbility":"ability{1}","item":"item{1}","pokeball":"pokeball","teraType":"Fairy","terastallized":""}}"#,
+                    species, i,
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+        let message = Message {
+            raw: format!(
+                r#">battle-gen9vgc2024regg-1
+|request|{{"teamPreview":true,"maxChosenTeamSize":4,"side":{{"name":"xfix","id":"p1","pokemon":[{}]}},"rqid":1}}"#,
+                pokemon,
+            ),
+        };
+        match message.kind() {
+            Kind::Request(request) => {
+                assert!(request.team_preview);
+                assert_eq!(request.max_team_size, Some(4));
+                assert_eq!(request.side.pokemon.len(), 6);
+            }
+            kind => panic!("{:?}", kind),
+        }
+    }
+}
@@ -0,0 +1,470 @@
+use super::owned;
+#[cfg(feature = "serialize")]
+use serde::Serialize;
+use serde::{Deserialize, Deserializer};
+use serde_json::Number;
+use std::borrow::Cow;
+
+/// Tournament message, sent as `|tournament|` to rooms with a
+/// tournament.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serialize",
+    serde(tag = "type", content = "data", rename_all = "snake_case")
+)]
+pub enum TournamentEvent<'a> {
+    Create {
+        format: Cow<'a, str>,
+        generator: Cow<'a, str>,
+        /// Maximum number of players, `None` when there is no limit.
+        player_cap: Option<u32>,
+    },
+    /// Changes to the tournament, followed by
+    /// [`UpdateEnd`](Self::UpdateEnd) once every change was sent.
+    Update(TournamentUpdate),
+    UpdateEnd,
+    Error(Cow<'a, str>),
+    Join(Cow<'a, str>),
+    Leave(Cow<'a, str>),
+    Replace {
+        old: Cow<'a, str>,
+        new: Cow<'a, str>,
+    },
+    /// Tournament started with a given number of players.
+    Start(u32),
+    Disqualify(Cow<'a, str>),
+    BattleStart {
+        player1: Cow<'a, str>,
+        player2: Cow<'a, str>,
+        room: Cow<'a, str>,
+    },
+    BattleEnd {
+        player1: Cow<'a, str>,
+        player2: Cow<'a, str>,
+        /// Result from the point of view of the first player.
+        result: TournamentResult,
+        score: Vec<u32>,
+        recorded: bool,
+        room: Cow<'a, str>,
+    },
+    End(TournamentEnd),
+    ForceEnd,
+}
+
+impl<'a> TournamentEvent<'a> {
+    pub(super) fn parse(arguments: &'a str) -> Option<Self> {
+        let arguments = arguments.split('\n').next().unwrap();
+        let mut args = arguments.split('|');
+        let command = args.next()?;
+        let mut next = || args.next().unwrap_or("");
+        Some(match command {
+            "create" => TournamentEvent::Create {
+                format: next().into(),
+                generator: next().into(),
+                player_cap: match next() {
+                    "" | "0" => None,
+                    cap => Some(cap.parse().ok()?),
+                },
+            },
+            "update" => {
+                let json = arguments.strip_prefix("update|")?;
+                TournamentEvent::Update(serde_json::from_str(json).ok()?)
+            }
+            "updateEnd" => TournamentEvent::UpdateEnd,
+            "error" => TournamentEvent::Error(arguments.strip_prefix("error|")?.into()),
+            "join" => TournamentEvent::Join(next().into()),
+            "leave" => TournamentEvent::Leave(next().into()),
+            "replace" => TournamentEvent::Replace {
+                old: next().into(),
+                new: next().into(),
+            },
+            "start" => TournamentEvent::Start(next().parse().ok()?),
+            "disqualify" => TournamentEvent::Disqualify(next().into()),
+            "battlestart" => TournamentEvent::BattleStart {
+                player1: next().into(),
+                player2: next().into(),
+                room: next().into(),
+            },
+            "battleend" => TournamentEvent::BattleEnd {
+                player1: next().into(),
+                player2: next().into(),
+                result: TournamentResult::parse(next())?,
+                score: next()
+                    .split(',')
+                    .map(str::parse)
+                    .collect::<Result<_, _>>()
+                    .ok()?,
+                recorded: match next() {
+                    "success" => true,
+                    "fail" => false,
+                    _ => return None,
+                },
+                room: next().into(),
+            },
+            "end" => {
+                let json = arguments.strip_prefix("end|")?;
+                TournamentEvent::End(serde_json::from_str(json).ok()?)
+            }
+            "forceend" => TournamentEvent::ForceEnd,
+            _ => return None,
+        })
+    }
+
+    pub fn into_owned(self) -> TournamentEvent<'static> {
+        match self {
+            TournamentEvent::Create {
+                format,
+                generator,
+                player_cap,
+            } => TournamentEvent::Create {
+                format: owned(format),
+                generator: owned(generator),
+                player_cap,
+            },
+            TournamentEvent::Update(update) => TournamentEvent::Update(update),
+            TournamentEvent::UpdateEnd => TournamentEvent::UpdateEnd,
+            TournamentEvent::Error(error) => TournamentEvent::Error(owned(error)),
+            TournamentEvent::Join(user) => TournamentEvent::Join(owned(user)),
+            TournamentEvent::Leave(user) => TournamentEvent::Leave(owned(user)),
+            TournamentEvent::Replace { old, new } => TournamentEvent::Replace {
+                old: owned(old),
+                new: owned(new),
+            },
+            TournamentEvent::Start(players) => TournamentEvent::Start(players),
+            TournamentEvent::Disqualify(user) => TournamentEvent::Disqualify(owned(user)),
+            TournamentEvent::BattleStart {
+                player1,
+                player2,
+                room,
+            } => TournamentEvent::BattleStart {
+                player1: owned(player1),
+                player2: owned(player2),
+                room: owned(room),
+            },
+            TournamentEvent::BattleEnd {
+                player1,
+                player2,
+                result,
+                score,
+                recorded,
+                room,
+            } => TournamentEvent::BattleEnd {
+                player1: owned(player1),
+                player2: owned(player2),
+                result,
+                score,
+                recorded,
+                room: owned(room),
+            },
+            TournamentEvent::End(end) => TournamentEvent::End(end),
+            TournamentEvent::ForceEnd => TournamentEvent::ForceEnd,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Deserialize, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[serde(rename_all = "lowercase")]
+pub enum TournamentResult {
+    Win,
+    Loss,
+    Draw,
+}
+
+impl TournamentResult {
+    fn parse(result: &str) -> Option<Self> {
+        Some(match result {
+            "win" => TournamentResult::Win,
+            "loss" => TournamentResult::Loss,
+            "draw" => TournamentResult::Draw,
+            _ => return None,
+        })
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TournamentResult::Win => "win",
+            TournamentResult::Loss => "loss",
+            TournamentResult::Draw => "draw",
+        }
+    }
+}
+
+/// Changed fields of a tournament, fields that didn't change are `None`.
+///
+/// Fields that can be cleared use nested options, with `Some(None)`
+/// meaning that the value was removed.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[serde(rename_all = "camelCase")]
+pub struct TournamentUpdate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub teambuilder_format: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub generator: Option<String>,
+    /// Maximum number of players, `Some(None)` when the limit was
+    /// removed.
+    #[serde(
+        default,
+        deserialize_with = "player_cap",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub player_cap: Option<Option<u32>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_started: Option<bool>,
+    /// Whether the user joined the tournament.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_joined: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bracket_data: Option<BracketData>,
+    /// Players the user can challenge.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub challenges: Option<Vec<String>>,
+    /// Players that can challenge the user.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub challenge_bys: Option<Vec<String>>,
+    /// Player challenged by the user.
+    #[serde(
+        default,
+        deserialize_with = "nullable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub challenging: Option<Option<String>>,
+    /// Player who challenged the user.
+    #[serde(
+        default,
+        deserialize_with = "nullable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub challenged: Option<Option<String>>,
+}
+
+fn nullable<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::deserialize(deserializer).map(Some)
+}
+
+/// Deserializes a player cap, where `0` means no limit.
+fn player_cap<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Option<u32>>, D::Error> {
+    let cap = Option::<u32>::deserialize(deserializer)?;
+    Ok(Some(cap.filter(|&cap| cap != 0)))
+}
+
+/// Final results of a tournament.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[serde(rename_all = "camelCase")]
+pub struct TournamentEnd {
+    /// Winners, multiple players may share a place.
+    pub results: Vec<Vec<String>>,
+    pub format: String,
+    pub generator: String,
+    pub bracket_data: BracketData,
+}
+
+/// Bracket of a tournament, depending on its generator.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum BracketData {
+    /// Elimination bracket.
+    Tree(TreeBracket),
+    /// Round robin table.
+    Table(TableBracket),
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[serde(rename_all = "camelCase")]
+pub struct TreeBracket {
+    /// Final battle, `None` before the tournament starts.
+    pub root_node: Option<BracketNode>,
+}
+
+/// Node of an elimination bracket.
+///
+/// Leaves are players, other nodes are battles between winners of their
+/// children.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[serde(rename_all = "camelCase")]
+pub struct BracketNode {
+    /// Player in this node, `None` when not yet known.
+    pub team: Option<String>,
+    #[serde(default)]
+    pub children: Vec<BracketNode>,
+    pub state: Option<BracketState>,
+    pub result: Option<TournamentResult>,
+    pub score: Option<Vec<u32>>,
+    pub room: Option<String>,
+}
+
+impl BracketNode {
+    /// Iterates over this node and its descendants, depth first.
+    pub fn iter(&self) -> impl Iterator<Item = &BracketNode> {
+        let mut stack = vec![self];
+        std::iter::from_fn(move || {
+            let node = stack.pop()?;
+            stack.extend(node.children.iter().rev());
+            Some(node)
+        })
+    }
+}
+
+#[derive(Copy, Clone, Debug, Deserialize, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[serde(rename_all = "lowercase")]
+pub enum BracketState {
+    Unavailable,
+    Available,
+    Challenging,
+    InProgress,
+    Finished,
+}
+
+impl BracketState {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            BracketState::Unavailable => "unavailable",
+            BracketState::Available => "available",
+            BracketState::Challenging => "challenging",
+            BracketState::InProgress => "inprogress",
+            BracketState::Finished => "finished",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[serde(rename_all = "camelCase")]
+pub struct TableBracket {
+    pub table_headers: TableHeaders,
+    /// Battles indexed by row and column, `None` for a player against
+    /// themselves.
+    pub table_contents: Vec<Vec<Option<TableCell>>>,
+    pub scores: Vec<Number>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct TableHeaders {
+    pub cols: Vec<String>,
+    pub rows: Vec<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct TableCell {
+    pub state: BracketState,
+    pub result: Option<TournamentResult>,
+    pub score: Option<Vec<u32>>,
+    pub room: Option<String>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::{BracketData, BracketState, TournamentEvent, TournamentResult};
+    use crate::message::{Kind, Message};
+
+    fn event(raw: &str) -> TournamentEvent<'static> {
+        match (Message { raw: raw.into() }).kind().into_owned() {
+            Kind::Tournament(event) => event,
+            kind => panic!("Unexpected kind {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn parse_events() {
+        assert_eq!(
+            event(">tours\n|tournament|create|gen9ou|Single Elimination|64"),
+            TournamentEvent::Create {
+                format: "gen9ou".into(),
+                generator: "Single Elimination".into(),
+                player_cap: Some(64),
+            },
+        );
+        assert_eq!(
+            event("|tournament|battleend|Zarel|xfix|loss|0,1|success|battle-gen9ou-1"),
+            TournamentEvent::BattleEnd {
+                player1: "Zarel".into(),
+                player2: "xfix".into(),
+                result: TournamentResult::Loss,
+                score: vec![0, 1],
+                recorded: true,
+                room: "battle-gen9ou-1".into(),
+            },
+        );
+        assert_eq!(
+            event(">tours\n|tournament|create|gen9ou|Single Elimination|0"),
+            TournamentEvent::Create {
+                format: "gen9ou".into(),
+                generator: "Single Elimination".into(),
+                player_cap: None,
+            },
+        );
+        assert_eq!(
+            event("|tournament|update|{\"playerCap\":0}"),
+            TournamentEvent::Update(super::TournamentUpdate {
+                player_cap: Some(None),
+                ..Default::default()
+            }),
+        );
+        assert_eq!(
+            event("|tournament|update|{\"isJoined\":true}\n|tournament|updateEnd"),
+            TournamentEvent::Update(super::TournamentUpdate {
+                is_joined: Some(true),
+                ..Default::default()
+            }),
+        );
+    }
+
+    #[test]
+    fn parse_tree_bracket() {
+        let update = event(
+            r#"|tournament|update|{"challenged":null,"bracketData":{"type":"tree","rootNode":{"children":[{"team":"Zarel"},{"team":"xfix"}],"state":"inprogress","room":"battle-gen9ou-1"}}}"#,
+        );
+        let update = match update {
+            TournamentEvent::Update(update) => update,
+            event => panic!("Unexpected event {:?}", event),
+        };
+        assert_eq!(update.challenged, Some(None));
+        assert_eq!(update.challenging, None);
+        let root = match update.bracket_data {
+            Some(BracketData::Tree(tree)) => tree.root_node.unwrap(),
+            bracket => panic!("Unexpected bracket {:?}", bracket),
+        };
+        assert_eq!(root.state, Some(BracketState::InProgress));
+        let players: Vec<_> = root
+            .iter()
+            .filter_map(|node| node.team.as_deref())
+            .collect();
+        assert_eq!(players, ["Zarel", "xfix"]);
+    }
+
+    #[test]
+    fn parse_end() {
+        let end = event(
+            r#"|tournament|end|{"results":[["xfix"]],"format":"gen9ou","generator":"Round Robin","bracketData":{"type":"table","tableHeaders":{"cols":["Zarel","xfix"],"rows":["Zarel","xfix"]},"tableContents":[[null,{"state":"finished","result":"loss","score":[0,1]}],[{"state":"finished","result":"win","score":[1,0]},null]],"scores":[0,1]}}"#,
+        );
+        let end = match end {
+            TournamentEvent::End(end) => end,
+            event => panic!("Unexpected event {:?}", event),
+        };
+        assert_eq!(end.results, [["xfix"]]);
+        let table = match end.bracket_data {
+            BracketData::Table(table) => table,
+            bracket => panic!("Unexpected bracket {:?}", bracket),
+        };
+        assert_eq!(
+            table.table_contents[1][0].as_ref().unwrap().result,
+            Some(TournamentResult::Win),
+        );
+    }
+}
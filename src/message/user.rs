@@ -0,0 +1,235 @@
+use super::owned;
+use crate::to_id;
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::fmt::{self, Display, Formatter};
+
+/// User as sent by the server, like `+xfix`, `@xfix@!` or ` Guest 123`.
+///
+/// The first character is a group symbol (space for regular users),
+/// followed by a display name and an optional status suffix after `@`.
+///
+/// # Examples
+///
+/// ```
+/// use showdown::message::User;
+///
+/// let user = User::new("@Xfix@!");
+/// assert_eq!(user.symbol(), '@');
+/// assert_eq!(user.name(), "Xfix");
+/// assert_eq!(user.status(), Some("!"));
+/// assert!(user.is_away());
+/// assert_eq!(user.id(), "xfix");
+/// ```
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct User<'a>(Cow<'a, str>);
+
+impl<'a> User<'a> {
+    /// Creates an user from its raw form.
+    pub fn new(user: impl Into<Cow<'a, str>>) -> Self {
+        Self(user.into())
+    }
+
+    /// Gets a group symbol, like `+` or `@`.
+    ///
+    /// Regular users have a space as their symbol.
+    pub fn symbol(&self) -> char {
+        self.0.chars().next().unwrap_or(' ')
+    }
+
+    /// Gets a display name.
+    pub fn name(&self) -> &str {
+        let rest = self.without_symbol();
+        rest.split_once('@').map_or(rest, |(name, _)| name)
+    }
+
+    /// Gets a rank from the group symbol.
+    ///
+    /// Unknown symbols are treated as [`Rank::Regular`].
+    pub fn rank(&self) -> Rank {
+        Rank::from_symbol(self.symbol()).unwrap_or(Rank::Regular)
+    }
+
+    /// Gets a status suffix, `!` for users who are away or busy.
+    pub fn status(&self) -> Option<&str> {
+        self.without_symbol()
+            .split_once('@')
+            .map(|(_, status)| status)
+    }
+
+    pub fn is_away(&self) -> bool {
+        self.status() == Some("!")
+    }
+
+    /// Gets an userid, see [`to_id`](crate::to_id).
+    pub fn id(&self) -> String {
+        to_id(self.name())
+    }
+
+    /// Gets the raw form of an user.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn into_owned(self) -> User<'static> {
+        User(owned(self.0))
+    }
+
+    fn without_symbol(&self) -> &str {
+        let mut chars = self.0.chars();
+        chars.next();
+        chars.as_str()
+    }
+}
+
+/// User group, ordered from the least to the most privileged.
+///
+/// The symbol in chat messages is a room rank, so the same user may have
+/// different ranks in different rooms.
+///
+/// # Examples
+///
+/// ```
+/// use showdown::message::{Rank, User};
+///
+/// let user = User::new("%xfix");
+/// assert_eq!(user.rank(), Rank::Driver);
+/// assert!(user.rank().has(Rank::Voice));
+/// assert!(!user.rank().has(Rank::Moderator));
+/// ```
+#[derive(Copy, Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serialize", serde(rename_all = "snake_case"))]
+pub enum Rank {
+    /// `‽`
+    Locked,
+    /// `!`
+    Muted,
+    /// Space
+    #[default]
+    Regular,
+    /// `+`
+    Voice,
+    /// `☆`, a player in a battle room.
+    Player,
+    /// `%`
+    Driver,
+    /// `@`
+    Moderator,
+    /// `*`
+    Bot,
+    /// `★`, a host of a room event.
+    Host,
+    /// `#`
+    RoomOwner,
+    /// `&`, also used for legacy `~`.
+    Administrator,
+}
+
+impl Rank {
+    pub fn from_symbol(symbol: char) -> Option<Self> {
+        Some(match symbol {
+            '‽' => Rank::Locked,
+            '!' => Rank::Muted,
+            ' ' => Rank::Regular,
+            '+' => Rank::Voice,
+            '☆' => Rank::Player,
+            '%' => Rank::Driver,
+            '@' => Rank::Moderator,
+            '*' => Rank::Bot,
+            '★' => Rank::Host,
+            '#' => Rank::RoomOwner,
+            '&' | '~' => Rank::Administrator,
+            _ => return None,
+        })
+    }
+
+    pub fn symbol(self) -> char {
+        match self {
+            Rank::Locked => '‽',
+            Rank::Muted => '!',
+            Rank::Regular => ' ',
+            Rank::Voice => '+',
+            Rank::Player => '☆',
+            Rank::Driver => '%',
+            Rank::Moderator => '@',
+            Rank::Bot => '*',
+            Rank::Host => '★',
+            Rank::RoomOwner => '#',
+            Rank::Administrator => '&',
+        }
+    }
+
+    /// Checks whether this rank is at least `required`.
+    pub fn has(self, required: Rank) -> bool {
+        self >= required
+    }
+
+    /// Checks whether the rank allows moderating a room, like muting
+    /// users.
+    pub fn is_staff(self) -> bool {
+        self.has(Rank::Driver)
+    }
+}
+
+impl Display for Rank {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.symbol())
+    }
+}
+
+impl Display for User<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl PartialEq<str> for User<'_> {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other
+    }
+}
+
+impl PartialEq<&str> for User<'_> {
+    fn eq(&self, other: &&str) -> bool {
+        self.0 == *other
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Rank, User};
+
+    #[test]
+    fn guest() {
+        let user = User::new(" Guest 123");
+        assert_eq!(user.symbol(), ' ');
+        assert_eq!(user.name(), "Guest 123");
+        assert_eq!(user.status(), None);
+        assert!(!user.is_away());
+        assert_eq!(user.id(), "guest123");
+    }
+
+    #[test]
+    fn rank_ordering() {
+        let ranks = "‽! +☆%@*★#&".chars().map(|c| Rank::from_symbol(c).unwrap());
+        let ranks: Vec<_> = ranks.collect();
+        let mut sorted = ranks.clone();
+        sorted.sort();
+        assert_eq!(ranks, sorted);
+        for rank in ranks {
+            assert_eq!(Rank::from_symbol(rank.symbol()), Some(rank));
+        }
+        assert_eq!(Rank::from_symbol('~'), Some(Rank::Administrator));
+        assert_eq!(User::new("?xfix").rank(), Rank::Regular);
+    }
+
+    #[test]
+    fn multibyte_symbol() {
+        let user = User::new("★Player");
+        assert_eq!(user.symbol(), '★');
+        assert_eq!(user.name(), "Player");
+    }
+}
@@ -0,0 +1,162 @@
+//! Tracking users present in rooms.
+
+use crate::message::{Kind, Message, User};
+use crate::{to_id, RoomId};
+use std::collections::BTreeMap;
+
+/// Members of every joined room, built from room messages.
+///
+/// Messages need to be passed to [`update`](Self::update) in order they
+/// were received.
+///
+/// # Examples
+///
+#[cfg_attr(feature = "__tls", doc = "```no_run")]
+#[cfg_attr(not(feature = "__tls"), doc = "```compile_fail")]
+/// use futures::{SinkExt, StreamExt};
+/// use showdown::presence::RoomPresence;
+/// use showdown::{Result, RoomId, SendMessage, Stream};
+///
+/// #[tokio::main]
+/// async fn main() -> Result<()> {
+///     let mut stream = Stream::connect("showdown").await?;
+///     stream.send(SendMessage::global_command("join lobby")).await?;
+///     let mut presence = RoomPresence::new();
+///     while let Some(message) = stream.next().await {
+///         presence.update(&message?);
+///         if presence.contains(RoomId::LOBBY, "zarel") {
+///             println!("Zarel is in the lobby");
+///         }
+///     }
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct RoomPresence {
+    rooms: BTreeMap<String, RoomMembers>,
+}
+
+#[derive(Clone, Debug, Default)]
+struct RoomMembers {
+    count: u32,
+    users: BTreeMap<String, User<'static>>,
+}
+
+impl RoomPresence {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Updates the state with every line of a message.
+    pub fn update(&mut self, message: &Message) {
+        let room = message.room().0;
+        if message.is_deinit() {
+            self.rooms.remove(room);
+            return;
+        }
+        for kind in message.kinds() {
+            match kind {
+                Kind::RoomInit(init) => {
+                    let users = init
+                        .users
+                        .users
+                        .into_iter()
+                        .map(|user| (user.id(), user.into_owned()))
+                        .collect();
+                    self.rooms.insert(
+                        room.into(),
+                        RoomMembers {
+                            count: init.users.count,
+                            users,
+                        },
+                    );
+                }
+                Kind::Join(user) => {
+                    if let Some(members) = self.rooms.get_mut(room) {
+                        if members.users.insert(user.id(), user.into_owned()).is_none() {
+                            members.count += 1;
+                        }
+                    }
+                }
+                Kind::Leave(user) => {
+                    if let Some(members) = self.rooms.get_mut(room) {
+                        if members.users.remove(&user.id()).is_some() {
+                            members.count = members.count.saturating_sub(1);
+                        }
+                    }
+                }
+                Kind::NicknameChange(user, old_id) => {
+                    if let Some(members) = self.rooms.get_mut(room) {
+                        members.users.remove(&to_id(&old_id));
+                        members.users.insert(user.id(), user.into_owned());
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Iterates over IDs of tracked rooms.
+    pub fn rooms(&self) -> impl Iterator<Item = &str> {
+        self.rooms.keys().map(String::as_str)
+    }
+
+    /// Iterates over users in a room, sorted by their userid.
+    ///
+    /// Returns `None` when the room isn't tracked.
+    pub fn users(&self, room: RoomId<'_>) -> Option<impl Iterator<Item = &User<'static>>> {
+        self.rooms.get(room.0).map(|members| members.users.values())
+    }
+
+    /// Gets a number of users in a room, including guests which aren't
+    /// listed by [`users`](Self::users).
+    pub fn count(&self, room: RoomId<'_>) -> Option<u32> {
+        self.rooms.get(room.0).map(|members| members.count)
+    }
+
+    /// Gets an user in a room by their name.
+    pub fn user(&self, room: RoomId<'_>, name: &str) -> Option<&User<'static>> {
+        self.rooms.get(room.0)?.users.get(&to_id(name))
+    }
+
+    /// Checks whether an user is in a room.
+    pub fn contains(&self, room: RoomId<'_>, name: &str) -> bool {
+        self.user(room, name).is_some()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::RoomPresence;
+    use crate::message::Message;
+    use crate::RoomId;
+
+    fn update(presence: &mut RoomPresence, raw: &str) {
+        presence.update(&Message { raw: raw.into() });
+    }
+
+    #[test]
+    fn tracks_members() {
+        let mut presence = RoomPresence::new();
+        let room = RoomId("botdev");
+        update(
+            &mut presence,
+            ">botdev\n|init|chat\n|title|Bot Development\n|users|3,@alice,+bob, carol",
+        );
+        update(&mut presence, ">botdev\n|j| Dave\n|L|+bob\n|n|%Carol|carol");
+        update(&mut presence, ">lobby\n|J|+bob");
+        assert_eq!(presence.count(room), Some(3));
+        let users: Vec<_> = presence
+            .users(room)
+            .unwrap()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(users, ["@alice", "%Carol", " Dave"]);
+        assert_eq!(presence.user(room, "CAROL").unwrap().symbol(), '%');
+        assert!(!presence.contains(room, "bob"));
+        assert!(presence.users(RoomId::LOBBY).is_none());
+        update(&mut presence, ">botdev\n|deinit");
+        assert!(presence.users(room).is_none());
+        assert_eq!(presence.rooms().count(), 0);
+    }
+}
@@ -0,0 +1,442 @@
+//! Automatic reconnection.
+
+#[cfg(feature = "__tls")]
+use crate::login::LoginClient;
+use crate::message::{Kind, Message};
+#[cfg(feature = "__tls")]
+use crate::server::ServerResolver;
+use crate::{Error, ErrorInner, Result, SendMessage, Stream};
+use futures_util::future::BoxFuture;
+use futures_util::sink::{Sink, SinkExt};
+use futures_util::stream::Stream as FuturesStream;
+use std::collections::{BTreeSet, VecDeque};
+use std::fmt::{self, Formatter};
+use std::future::Future;
+use std::mem;
+use std::pin::Pin;
+#[cfg(feature = "__tls")]
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::time::{self, Sleep};
+use url::Url;
+
+/// Message stream that reconnects when the connection is lost.
+///
+/// After reconnecting, [`Event::Reconnected`] is emitted, the user is
+/// logged in again (if [`login`](ReconnectingStreamBuilder::login) was used)
+/// and the rooms that were joined are joined again. Messages sent while
+/// reconnecting are queued.
+///
+/// # Examples
+///
+#[cfg_attr(feature = "__tls", doc = "```no_run")]
+#[cfg_attr(not(feature = "__tls"), doc = "```compile_fail")]
+/// use futures::{SinkExt, StreamExt};
+/// use showdown::message::Kind;
+/// use showdown::reconnect::{Event, ReconnectingStream};
+/// use showdown::{Result, SendMessage};
+///
+/// #[tokio::main]
+/// async fn main() -> Result<()> {
+///     let mut stream = ReconnectingStream::builder("showdown")
+///         .login("username", "password")
+///         .connect()
+///         .await?;
+///     stream.send(SendMessage::global_command("join lobby")).await?;
+///     while let Some(event) = stream.next().await {
+///         match event? {
+///             Event::Message(message) => {
+///                 if let Kind::Chat(chat) = message.kind() {
+///                     println!("{}: {}", chat.user(), chat.message());
+///                 }
+///             }
+///             Event::Reconnected => println!("Reconnected"),
+///         }
+///     }
+///     Ok(())
+/// }
+/// ```
+pub struct ReconnectingStream {
+    options: ReconnectingStreamBuilder,
+    state: State,
+    attempt: u32,
+    rooms: BTreeSet<String>,
+    pending: VecDeque<SendMessage>,
+    received: Option<Message>,
+}
+
+enum State {
+    Connected(Box<Stream>),
+    /// Logging in and rejoining rooms after receiving a challenge.
+    Preparing(BoxFuture<'static, (Stream, Message, Result<()>)>),
+    Waiting(Pin<Box<Sleep>>),
+    Connecting(BoxFuture<'static, Result<Stream>>),
+    Closed,
+}
+
+/// Event received from [`ReconnectingStream`].
+#[derive(Debug)]
+pub enum Event {
+    Message(Message),
+    /// Connection was established again. Messages received while
+    /// disconnected are lost, so state may need to be refreshed.
+    Reconnected,
+}
+
+impl ReconnectingStream {
+    /// Creates a builder for a named Showdown server.
+    ///
+    /// Server URL is fetched again on every reconnection.
+    ///
+    /// Requires `native-tls`, `native-tls-vendored` or `rustls-tls` feature.
+    #[cfg(feature = "__tls")]
+    pub fn builder(name: &str) -> ReconnectingStreamBuilder {
+        ReconnectingStreamBuilder::new(Server::Named(name.into()))
+    }
+
+    /// Creates a builder for an URL.
+    pub fn builder_for_url(url: Url) -> ReconnectingStreamBuilder {
+        ReconnectingStreamBuilder::new(Server::Url(url))
+    }
+
+    fn reconnect(&mut self) {
+        let delay = self.options.backoff.delay(self.attempt);
+        self.attempt = self.attempt.saturating_add(1);
+        self.state = State::Waiting(Box::pin(time::sleep(delay)));
+    }
+
+    fn prepare(&mut self, stream: Stream, message: Message) {
+        #[cfg(feature = "__tls")]
+        let credentials = self.options.credentials.clone();
+        #[cfg(feature = "__tls")]
+        let login_client = self.options.login_client.clone();
+        let rooms: Vec<_> = self.rooms.iter().cloned().collect();
+        self.state = State::Preparing(Box::pin(async move {
+            let mut stream = stream;
+            #[cfg(feature = "__tls")]
+            let result = match (credentials, message.kind()) {
+                (Some((login, password)), Kind::Challenge(challenge)) => {
+                    login_client
+                        .login_with_password(challenge, &mut stream, &login, &password)
+                        .await
+                }
+                _ => Ok(()),
+            };
+            #[cfg(not(feature = "__tls"))]
+            let result = Ok(());
+            let mut result = result;
+            if result.is_ok() {
+                for room in rooms {
+                    let command = SendMessage::global_command(format_args!("join {}", room));
+                    if let Err(e) = stream.send(command).await {
+                        result = Err(e);
+                        break;
+                    }
+                }
+            }
+            (stream, message, result)
+        }));
+    }
+
+    fn track_rooms(&mut self, message: &Message) {
+        if let Kind::RoomInit(_) = message.kind() {
+            self.rooms.insert(message.room().0.into());
+        } else if message.is_deinit() {
+            self.rooms.remove(message.room().0);
+        }
+    }
+
+    /// Sends queued messages.
+    fn poll_pending(&mut self, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        let stream = match &mut self.state {
+            State::Connected(stream) => stream,
+            _ => return Poll::Ready(Ok(())),
+        };
+        if self.pending.is_empty() {
+            return Poll::Ready(Ok(()));
+        }
+        while let Some(message) = self.pending.pop_front() {
+            if Pin::new(&mut **stream).poll_ready(cx)?.is_pending() {
+                self.pending.push_front(message);
+                return Poll::Pending;
+            }
+            Pin::new(&mut **stream).start_send(message)?;
+        }
+        match Pin::new(&mut **stream).poll_flush(cx) {
+            Poll::Ready(Err(e)) => Poll::Ready(Err(e)),
+            _ => Poll::Ready(Ok(())),
+        }
+    }
+}
+
+impl fmt::Debug for ReconnectingStream {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReconnectingStream")
+            .field("rooms", &self.rooms)
+            .finish()
+    }
+}
+
+impl FuturesStream for ReconnectingStream {
+    type Item = Result<Event>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if let Some(message) = self.received.take() {
+            return Poll::Ready(Some(Ok(Event::Message(message))));
+        }
+        loop {
+            let this = &mut *self;
+            match &mut this.state {
+                State::Connected(stream) => {
+                    let received = match Pin::new(&mut **stream).poll_next(cx) {
+                        Poll::Ready(received) => received,
+                        Poll::Pending => {
+                            return match this.poll_pending(cx) {
+                                Poll::Ready(Err(e)) => Poll::Ready(Some(Err(e))),
+                                _ => Poll::Pending,
+                            };
+                        }
+                    };
+                    match received {
+                        Some(Ok(message)) => {
+                            this.track_rooms(&message);
+                            if let Kind::Challenge(_) = message.kind() {
+                                if let State::Connected(stream) =
+                                    mem::replace(&mut this.state, State::Closed)
+                                {
+                                    this.prepare(*stream, message);
+                                }
+                                continue;
+                            }
+                            return Poll::Ready(Some(Ok(Event::Message(message))));
+                        }
+                        Some(Err(Error(ErrorInner::WebSocket(_)))) | None => this.reconnect(),
+                        Some(Err(e)) => return Poll::Ready(Some(Err(e))),
+                    }
+                }
+                State::Preparing(future) => {
+                    let (stream, message, result) = futures_util::ready!(future.as_mut().poll(cx));
+                    this.state = State::Connected(Box::new(stream));
+                    match result {
+                        Ok(()) => return Poll::Ready(Some(Ok(Event::Message(message)))),
+                        Err(e) => {
+                            this.received = Some(message);
+                            return Poll::Ready(Some(Err(e)));
+                        }
+                    }
+                }
+                State::Waiting(sleep) => {
+                    futures_util::ready!(sleep.as_mut().poll(cx));
+                    this.state = State::Connecting(this.options.connect_stream());
+                }
+                State::Connecting(future) => match futures_util::ready!(future.as_mut().poll(cx)) {
+                    Ok(stream) => {
+                        this.state = State::Connected(Box::new(stream));
+                        this.attempt = 0;
+                        return Poll::Ready(Some(Ok(Event::Reconnected)));
+                    }
+                    Err(e) => {
+                        if matches!(this.options.max_attempts, Some(max) if this.attempt >= max) {
+                            this.state = State::Closed;
+                            return Poll::Ready(Some(Err(e)));
+                        }
+                        this.reconnect();
+                    }
+                },
+                State::Closed => return Poll::Ready(None),
+            }
+        }
+    }
+}
+
+impl Sink<SendMessage> for ReconnectingStream {
+    type Error = Error;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        futures_util::ready!(self.poll_pending(cx))?;
+        match &mut self.state {
+            State::Connected(stream) => Pin::new(&mut **stream).poll_ready(cx),
+            _ => Poll::Ready(Ok(())),
+        }
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, item: SendMessage) -> Result<()> {
+        match &mut self.state {
+            State::Connected(stream) => Pin::new(&mut **stream).start_send(item),
+            _ => {
+                self.pending.push_back(item);
+                Ok(())
+            }
+        }
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        futures_util::ready!(self.poll_pending(cx))?;
+        match &mut self.state {
+            State::Connected(stream) => Pin::new(&mut **stream).poll_flush(cx),
+            _ => Poll::Ready(Ok(())),
+        }
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        let result = match &mut self.state {
+            State::Connected(stream) => {
+                futures_util::ready!(Pin::new(&mut **stream).poll_close(cx))
+            }
+            _ => Ok(()),
+        };
+        self.state = State::Closed;
+        Poll::Ready(result)
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Server {
+    #[cfg(feature = "__tls")]
+    Named(String),
+    Url(Url),
+}
+
+/// Builder for [`ReconnectingStream`].
+#[derive(Clone, Debug)]
+pub struct ReconnectingStreamBuilder {
+    server: Server,
+    #[cfg(feature = "__tls")]
+    credentials: Option<(String, String)>,
+    #[cfg(feature = "__tls")]
+    login_client: LoginClient,
+    #[cfg(feature = "__tls")]
+    resolver: Option<Arc<dyn ServerResolver>>,
+    backoff: Backoff,
+    max_attempts: Option<u32>,
+}
+
+impl ReconnectingStreamBuilder {
+    fn new(server: Server) -> Self {
+        Self {
+            server,
+            #[cfg(feature = "__tls")]
+            credentials: None,
+            #[cfg(feature = "__tls")]
+            login_client: LoginClient::default(),
+            #[cfg(feature = "__tls")]
+            resolver: None,
+            backoff: Backoff::default(),
+            max_attempts: None,
+        }
+    }
+
+    /// Logs in when a login challenge is received.
+    ///
+    /// Password may be empty for unregistered users.
+    ///
+    /// Requires `native-tls`, `native-tls-vendored` or `rustls-tls` feature.
+    #[cfg(feature = "__tls")]
+    pub fn login(mut self, login: &str, password: &str) -> Self {
+        self.credentials = Some((login.into(), password.into()));
+        self
+    }
+
+    /// Sets a login server used by [`login`](Self::login).
+    ///
+    /// By default, the official login server is used.
+    ///
+    /// Requires `native-tls`, `native-tls-vendored` or `rustls-tls` feature.
+    #[cfg(feature = "__tls")]
+    pub fn login_client(mut self, client: LoginClient) -> Self {
+        self.login_client = client;
+        self
+    }
+
+    /// Sets a resolver used to find a named server.
+    ///
+    /// Server is resolved again on every reconnection, so
+    /// [`CachingResolver`](crate::server::CachingResolver) can be used to
+    /// avoid repeated requests.
+    ///
+    /// Requires `native-tls`, `native-tls-vendored` or `rustls-tls` feature.
+    #[cfg(feature = "__tls")]
+    pub fn resolver(mut self, resolver: impl ServerResolver + 'static) -> Self {
+        self.resolver = Some(Arc::new(resolver));
+        self
+    }
+
+    pub fn backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Gives up after failing to reconnect given number of times in a row.
+    ///
+    /// By default, reconnection is attempted forever.
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = Some(max_attempts);
+        self
+    }
+
+    fn connect_stream(&self) -> BoxFuture<'static, Result<Stream>> {
+        let server = self.server.clone();
+        #[cfg(feature = "__tls")]
+        let resolver = self.resolver.clone();
+        Box::pin(async move {
+            match server {
+                #[cfg(feature = "__tls")]
+                Server::Named(name) => match resolver {
+                    Some(resolver) => Stream::builder(&name).resolver(resolver).connect().await,
+                    None => Stream::connect(&name).await,
+                },
+                Server::Url(url) => Stream::connect_to_url(&url).await,
+            }
+        })
+    }
+
+    /// Connects to the server.
+    ///
+    /// Failure to connect is returned as an error, reconnection is only
+    /// attempted after a connection is lost.
+    pub async fn connect(self) -> Result<ReconnectingStream> {
+        let stream = self.connect_stream().await?;
+        Ok(ReconnectingStream {
+            options: self,
+            state: State::Connected(Box::new(stream)),
+            attempt: 0,
+            rooms: BTreeSet::new(),
+            pending: VecDeque::new(),
+            received: None,
+        })
+    }
+}
+
+/// Delay between reconnection attempts.
+///
+/// Delay starts from initial delay and doubles on each failed attempt
+/// until reaching maximum delay.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Backoff {
+    initial: Duration,
+    max: Duration,
+}
+
+impl Backoff {
+    pub fn new(initial: Duration, max: Duration) -> Self {
+        Self { initial, max }
+    }
+
+    pub fn constant(delay: Duration) -> Self {
+        Self::new(delay, delay)
+    }
+
+    fn delay(&self, attempt: u32) -> Duration {
+        2u32.checked_pow(attempt)
+            .and_then(|factor| self.initial.checked_mul(factor))
+            .map_or(self.max, |delay| delay.min(self.max))
+    }
+}
+
+impl Default for Backoff {
+    /// One second, up to a minute.
+    fn default() -> Self {
+        Self::new(Duration::from_secs(1), Duration::from_secs(60))
+    }
+}
@@ -0,0 +1,315 @@
+//! Showdown server directory.
+
+use crate::{Error, ErrorInner, Result};
+#[cfg(feature = "__tls")]
+use futures_util::future::TryFutureExt;
+use futures_util::future::{self, BoxFuture};
+#[cfg(feature = "__tls")]
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::{self, Formatter};
+use std::iter::FromIterator;
+use std::sync::{Arc, Mutex};
+use url::Url;
+
+/// Server entry from `https://pokemonshowdown.com/servers/{id}.json`.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ServerInfo {
+    pub id: String,
+    pub name: String,
+    pub host: String,
+    /// Websocket port.
+    pub port: u16,
+    /// Port of HTTP server, if different from `port`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub httpport: Option<u16>,
+    /// Alternative port for networks blocking `port`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub altport: Option<u16>,
+    #[serde(default)]
+    pub registered: bool,
+    /// Whether the server must be accessed with TLS. Servers on port
+    /// 443 always use TLS.
+    #[serde(default)]
+    pub https: bool,
+}
+
+impl ServerInfo {
+    /// Main Showdown server.
+    pub fn showdown() -> Self {
+        Self {
+            id: "showdown".into(),
+            name: "Smogon University".into(),
+            host: "sim3.psim.us".into(),
+            port: 443,
+            httpport: None,
+            altport: None,
+            registered: true,
+            https: true,
+        }
+    }
+
+    /// Gets a websocket URL of the server.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use showdown::server::ServerInfo;
+    ///
+    /// assert_eq!(
+    ///     ServerInfo::showdown().websocket_url().unwrap().as_str(),
+    ///     "wss://sim3.psim.us/showdown/websocket",
+    /// );
+    /// ```
+    pub fn websocket_url(&self) -> Result<Url> {
+        let protocol = if self.https || self.port == 443 {
+            "wss"
+        } else {
+            "ws"
+        };
+        // Concatenation is fine, as it's also done by the official Showdown client
+        let url = format!(
+            "{}://{}:{}/showdown/websocket",
+            protocol, self.host, self.port,
+        );
+        Url::parse(&url).map_err(|e| Error(ErrorInner::Url(e)))
+    }
+}
+
+/// Finds servers by their ID.
+pub trait ServerResolver: fmt::Debug + Send + Sync {
+    fn resolve<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<ServerInfo>>;
+}
+
+impl<R: ServerResolver + ?Sized> ServerResolver for Arc<R> {
+    fn resolve<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<ServerInfo>> {
+        (**self).resolve(id)
+    }
+}
+
+/// Resolver using the official server directory.
+///
+/// `showdown` is resolved without a request.
+///
+/// Requires `native-tls`, `native-tls-vendored` or `rustls-tls` feature.
+#[cfg(feature = "__tls")]
+#[derive(Clone, Debug)]
+pub struct HttpResolver {
+    client: Client,
+    base_url: Url,
+}
+
+#[cfg(feature = "__tls")]
+impl HttpResolver {
+    /// Creates a resolver fetching `{id}.json` relative to `base_url`.
+    pub fn new(base_url: Url) -> Self {
+        Self::with_client(Client::new(), base_url)
+    }
+
+    /// Creates a resolver using an existing HTTP client.
+    pub fn with_client(client: Client, base_url: Url) -> Self {
+        Self { client, base_url }
+    }
+}
+
+#[cfg(feature = "__tls")]
+impl Default for HttpResolver {
+    fn default() -> Self {
+        Self::new(Url::parse("https://pokemonshowdown.com/servers/").unwrap())
+    }
+}
+
+#[cfg(feature = "__tls")]
+impl ServerResolver for HttpResolver {
+    fn resolve<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<ServerInfo>> {
+        Box::pin(async move {
+            if id == "showdown" {
+                return Ok(ServerInfo::showdown());
+            }
+            let url = self
+                .base_url
+                .join(&format!("{}.json", id))
+                .map_err(|e| Error(ErrorInner::Url(e)))?;
+            self.client
+                .get(url)
+                .send()
+                .and_then(|r| async { r.error_for_status()?.json().await })
+                .await
+                .map_err(|e| Error(ErrorInner::Reqwest(e)))
+        })
+    }
+}
+
+/// Resolver with a fixed list of servers.
+///
+/// # Examples
+///
+/// ```
+/// use showdown::server::{ServerResolver, StaticResolver};
+///
+/// # #[tokio::main]
+/// # async fn main() -> showdown::Result<()> {
+/// let resolver = StaticResolver::from_json(
+///     r#"[{"id": "local", "name": "Local", "host": "localhost", "port": 8000}]"#,
+/// )?;
+/// let url = resolver.resolve("local").await?.websocket_url()?;
+/// assert_eq!(url.as_str(), "ws://localhost:8000/showdown/websocket");
+/// assert!(resolver.resolve("showdown").await.is_err());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct StaticResolver {
+    servers: HashMap<String, ServerInfo>,
+}
+
+impl StaticResolver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses a JSON array of server entries, like one stored in a local
+    /// file.
+    pub fn from_json(json: &str) -> Result<Self> {
+        let servers: Vec<ServerInfo> =
+            serde_json::from_str(json).map_err(|e| Error(ErrorInner::ServerInfo(e)))?;
+        Ok(servers.into_iter().collect())
+    }
+
+    /// Adds a server, replacing a server with the same ID.
+    pub fn insert(&mut self, server: ServerInfo) {
+        self.servers.insert(server.id.clone(), server);
+    }
+}
+
+impl FromIterator<ServerInfo> for StaticResolver {
+    fn from_iter<I: IntoIterator<Item = ServerInfo>>(iter: I) -> Self {
+        let mut resolver = Self::new();
+        for server in iter {
+            resolver.insert(server);
+        }
+        resolver
+    }
+}
+
+impl ServerResolver for StaticResolver {
+    fn resolve<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<ServerInfo>> {
+        let result = self
+            .servers
+            .get(id)
+            .cloned()
+            .ok_or_else(|| Error(ErrorInner::UnknownServer(id.into())));
+        Box::pin(future::ready(result))
+    }
+}
+
+/// Resolver remembering servers resolved by another resolver.
+///
+/// Errors aren't cached.
+pub struct CachingResolver<R> {
+    inner: R,
+    cache: Mutex<HashMap<String, ServerInfo>>,
+}
+
+impl<R> CachingResolver<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Forgets all resolved servers.
+    pub fn clear(&self) {
+        self.cache.lock().unwrap().clear();
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: ServerResolver> ServerResolver for CachingResolver<R> {
+    fn resolve<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<ServerInfo>> {
+        Box::pin(async move {
+            if let Some(server) = self.cache.lock().unwrap().get(id) {
+                return Ok(server.clone());
+            }
+            let server = self.inner.resolve(id).await?;
+            self.cache.lock().unwrap().insert(id.into(), server.clone());
+            Ok(server)
+        })
+    }
+}
+
+impl<R: fmt::Debug> fmt::Debug for CachingResolver<R> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CachingResolver")
+            .field("inner", &self.inner)
+            .field("cached", &self.cache.lock().unwrap().len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{CachingResolver, ServerInfo, ServerResolver, StaticResolver};
+    use crate::Result;
+    use futures_util::future::BoxFuture;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    #[derive(Debug)]
+    struct Counting {
+        calls: AtomicU32,
+        inner: StaticResolver,
+    }
+
+    impl ServerResolver for Counting {
+        fn resolve<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<ServerInfo>> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            self.inner.resolve(id)
+        }
+    }
+
+    #[test]
+    fn parse_directory_entry() {
+        let json = r#"{
+            "id": "smogtours",
+            "name": "Smogon Tournaments",
+            "host": "sim3.psim.us",
+            "port": 8002,
+            "httpport": 8000,
+            "altport": 80,
+            "registered": true
+        }"#;
+        let server: ServerInfo = serde_json::from_str(json).unwrap();
+        assert_eq!(server.httpport, Some(8000));
+        assert_eq!(server.altport, Some(80));
+        assert!(server.registered);
+        assert!(!server.https);
+        assert_eq!(
+            server.websocket_url().unwrap().as_str(),
+            "ws://sim3.psim.us:8002/showdown/websocket",
+        );
+    }
+
+    #[tokio::test]
+    async fn caches_resolved_servers() {
+        let resolver = CachingResolver::new(Counting {
+            calls: AtomicU32::new(0),
+            inner: vec![ServerInfo::showdown()].into_iter().collect(),
+        });
+        for _ in 0..3 {
+            assert_eq!(
+                resolver.resolve("showdown").await.unwrap(),
+                ServerInfo::showdown(),
+            );
+        }
+        assert!(resolver.resolve("missing").await.is_err());
+        assert!(resolver.resolve("missing").await.is_err());
+        resolver.clear();
+        resolver.resolve("showdown").await.unwrap();
+        assert_eq!(resolver.into_inner().calls.into_inner(), 4);
+    }
+}
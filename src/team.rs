@@ -0,0 +1,170 @@
+//! Teams and Pokémon sets.
+
+mod export;
+mod packed;
+
+pub use self::export::ImportError;
+pub use self::packed::UnpackError;
+use crate::message::Gender;
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+use std::iter::FromIterator;
+use std::vec;
+
+/// Team of Pokémon sets.
+///
+/// Teams can be converted from and to the packed format used by
+/// `/utm`, see [`SendMessage::upload_team`](crate::SendMessage::upload_team),
+/// and the human-readable export format used by the teambuilder and
+/// PokePaste.
+///
+/// # Examples
+///
+/// ```
+/// use showdown::team::{PokemonSet, Team};
+/// use showdown::SendMessage;
+///
+/// let mut garchomp = PokemonSet::new("Garchomp");
+/// garchomp.item = Some("Choice Scarf".into());
+/// garchomp.moves = vec!["Earthquake".into(), "Outrage".into()];
+/// let team: Team = vec![garchomp].into();
+/// assert_eq!(team.pack(), "Garchomp||Choice Scarf||Earthquake,Outrage|||||||");
+/// let message = SendMessage::upload_team(team.pack());
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct Team {
+    pub pokemon: Vec<PokemonSet>,
+}
+
+impl Team {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl From<Vec<PokemonSet>> for Team {
+    fn from(pokemon: Vec<PokemonSet>) -> Self {
+        Self { pokemon }
+    }
+}
+
+impl FromIterator<PokemonSet> for Team {
+    fn from_iter<I: IntoIterator<Item = PokemonSet>>(iter: I) -> Self {
+        Self {
+            pokemon: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl IntoIterator for Team {
+    type Item = PokemonSet;
+    type IntoIter = vec::IntoIter<PokemonSet>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.pokemon.into_iter()
+    }
+}
+
+/// Pokémon set, with fields named like in Showdown's `PokemonSet`.
+///
+/// Names of species, items, abilities and moves are kept as written, so
+/// they can be either display names like `Choice Scarf` or IDs like
+/// `choicescarf`, Showdown accepts both.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct PokemonSet {
+    /// Nickname, same as the species for Pokémon without a nickname.
+    pub name: String,
+    pub species: String,
+    pub item: Option<String>,
+    pub ability: Option<String>,
+    pub moves: Vec<String>,
+    pub nature: Option<String>,
+    pub evs: StatsTable,
+    pub gender: Option<Gender>,
+    pub ivs: StatsTable,
+    pub shiny: bool,
+    pub level: u8,
+    pub happiness: u8,
+    pub hidden_power_type: Option<String>,
+    pub pokeball: Option<String>,
+    pub gigantamax: bool,
+    pub dynamax_level: u8,
+    pub tera_type: Option<String>,
+}
+
+impl PokemonSet {
+    /// Creates a set with no nickname and default values, like level 100
+    /// and perfect IVs.
+    pub fn new(species: impl Into<String>) -> Self {
+        let species = species.into();
+        Self {
+            name: species.clone(),
+            species,
+            item: None,
+            ability: None,
+            moves: Vec::new(),
+            nature: None,
+            evs: StatsTable::uniform(0),
+            gender: None,
+            ivs: StatsTable::uniform(31),
+            shiny: false,
+            level: 100,
+            happiness: 255,
+            hidden_power_type: None,
+            pokeball: None,
+            gigantamax: false,
+            dynamax_level: 10,
+            tera_type: None,
+        }
+    }
+
+    /// Gets a nickname, `None` when the name is the same as the species.
+    pub fn nickname(&self) -> Option<&str> {
+        Some(&*self.name).filter(|name| *name != self.species)
+    }
+}
+
+/// EVs or IVs of a set.
+#[derive(Copy, Clone, Debug, Default, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct StatsTable {
+    pub hp: u8,
+    pub atk: u8,
+    pub def: u8,
+    pub spa: u8,
+    pub spd: u8,
+    pub spe: u8,
+}
+
+impl StatsTable {
+    /// Creates a table with the same value for every stat.
+    pub const fn uniform(value: u8) -> Self {
+        Self {
+            hp: value,
+            atk: value,
+            def: value,
+            spa: value,
+            spd: value,
+            spe: value,
+        }
+    }
+
+    /// Gets values in the order used by Showdown, HP, Attack, Defense,
+    /// Special Attack, Special Defense and Speed.
+    pub fn to_array(self) -> [u8; 6] {
+        [self.hp, self.atk, self.def, self.spa, self.spd, self.spe]
+    }
+
+    pub fn from_array([hp, atk, def, spa, spd, spe]: [u8; 6]) -> Self {
+        Self {
+            hp,
+            atk,
+            def,
+            spa,
+            spd,
+            spe,
+        }
+    }
+}
@@ -0,0 +1,425 @@
+use super::{PokemonSet, StatsTable, Team};
+use crate::message::Gender;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+use thiserror::Error;
+
+/// Reason why a team in the export format couldn't be imported.
+///
+/// Line numbers start at 1 and count from the beginning of the whole
+/// imported text.
+#[derive(Clone, Debug, Eq, PartialEq, Error)]
+#[non_exhaustive]
+pub enum ImportError {
+    #[error("Line {line}: unrecognized line {text:?}")]
+    UnrecognizedLine { line: usize, text: String },
+    #[error("Line {line}: invalid {field} {value:?}")]
+    InvalidField {
+        line: usize,
+        field: &'static str,
+        value: String,
+    },
+}
+
+impl ImportError {
+    /// Gets a number of the line that failed to import.
+    pub fn line(&self) -> usize {
+        match *self {
+            ImportError::UnrecognizedLine { line, .. } | ImportError::InvalidField { line, .. } => {
+                line
+            }
+        }
+    }
+}
+
+const STAT_NAMES: [&str; 6] = ["HP", "Atk", "Def", "SpA", "SpD", "Spe"];
+
+impl Team {
+    /// Imports a team from the export format used by the teambuilder and
+    /// PokePaste, with sets separated by blank lines.
+    ///
+    /// Moves like `Hidden Power [Fire]` are imported as `Hidden Power Fire`,
+    /// and exported back with brackets.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use showdown::team::Team;
+    ///
+    /// let team = Team::import(
+    ///     "Garchomp @ Choice Scarf
+    ///      Ability: Rough Skin
+    ///      EVs: 252 Atk / 4 SpD / 252 Spe
+    ///      Jolly Nature
+    ///      - Earthquake
+    ///      - Outrage",
+    /// )
+    /// .unwrap();
+    /// assert_eq!(team.pokemon[0].evs.atk, 252);
+    /// assert_eq!(
+    ///     team.pack(),
+    ///     "Garchomp||Choice Scarf|Rough Skin|Earthquake,Outrage|Jolly|,252,,,4,252|||||",
+    /// );
+    ///
+    /// let error = Team::import("Garchomp\nEVs: 252 Attack").unwrap_err();
+    /// assert_eq!(error.line(), 2);
+    /// ```
+    pub fn import(text: &str) -> Result<Self, ImportError> {
+        let mut team = Self::new();
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line.trim()));
+        while let Some((line, head)) = lines.find(|(_, line)| !line.is_empty()) {
+            let mut set = parse_head(head);
+            set.check_head(line, head)?;
+            for (line, text) in lines.by_ref().take_while(|(_, line)| !line.is_empty()) {
+                parse_line(&mut set, line, text)?;
+            }
+            team.pokemon.push(set);
+        }
+        Ok(team)
+    }
+
+    /// Exports a team into the format used by the teambuilder and
+    /// PokePaste.
+    pub fn export(&self) -> String {
+        let sets: Vec<_> = self.pokemon.iter().map(PokemonSet::export).collect();
+        sets.join("\n")
+    }
+}
+
+impl PokemonSet {
+    /// Imports a single set from the export format.
+    pub fn import(text: &str) -> Result<Self, ImportError> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line.trim()));
+        let (line, head) =
+            lines
+                .find(|(_, line)| !line.is_empty())
+                .ok_or(ImportError::UnrecognizedLine {
+                    line: 1,
+                    text: String::new(),
+                })?;
+        let mut set = parse_head(head);
+        set.check_head(line, head)?;
+        for (line, text) in lines {
+            if text.is_empty() {
+                continue;
+            }
+            parse_line(&mut set, line, text)?;
+        }
+        Ok(set)
+    }
+
+    /// Exports a set into the format used by the teambuilder and
+    /// PokePaste.
+    pub fn export(&self) -> String {
+        Exported(self).to_string()
+    }
+
+    fn check_head(&self, line: usize, head: &str) -> Result<(), ImportError> {
+        if self.species.is_empty() || self.name.is_empty() {
+            return Err(ImportError::UnrecognizedLine {
+                line,
+                text: head.into(),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Parses a line like `Nickname (Species) (F) @ Item`.
+fn parse_head(head: &str) -> PokemonSet {
+    let (rest, item) = match head.split_once(" @ ") {
+        Some((rest, item)) => (rest.trim(), Some(item.trim())),
+        None => (head, None),
+    };
+    let (rest, gender) = if let Some(rest) = rest.strip_suffix(" (M)") {
+        (rest, Some(Gender::Male))
+    } else if let Some(rest) = rest.strip_suffix(" (F)") {
+        (rest, Some(Gender::Female))
+    } else {
+        (rest, None)
+    };
+    let nicknamed = rest
+        .strip_suffix(')')
+        .and_then(|rest| rest.rsplit_once(" ("));
+    let mut set = match nicknamed {
+        Some((name, species)) => {
+            let mut set = PokemonSet::new(species.trim());
+            set.name = name.trim().into();
+            set
+        }
+        None => PokemonSet::new(rest.trim()),
+    };
+    set.item = item.filter(|item| !item.is_empty()).map(String::from);
+    set.gender = gender;
+    set
+}
+
+fn parse_line(set: &mut PokemonSet, line: usize, text: &str) -> Result<(), ImportError> {
+    let invalid = |field, value: &str| ImportError::InvalidField {
+        line,
+        field,
+        value: value.into(),
+    };
+    let yes_no = |field, value: &str| match value {
+        "Yes" => Ok(true),
+        "No" => Ok(false),
+        _ => Err(invalid(field, value)),
+    };
+    if let Some(name) = text.strip_prefix('-') {
+        let name = name.trim();
+        // Brackets would break the packed format, so Hidden Power is stored
+        // like Showdown does.
+        let name = match name
+            .strip_prefix("Hidden Power [")
+            .and_then(|name| name.strip_suffix(']'))
+        {
+            Some(kind) => format!("Hidden Power {}", kind),
+            None => name.into(),
+        };
+        set.moves.push(name);
+        return Ok(());
+    }
+    if let Some(nature) = text.strip_suffix(" Nature") {
+        set.nature = Some(nature.trim().into());
+        return Ok(());
+    }
+    let (key, value) = text
+        .split_once(':')
+        .map(|(key, value)| (key.trim(), value.trim()))
+        .ok_or_else(|| ImportError::UnrecognizedLine {
+            line,
+            text: text.into(),
+        })?;
+    match key {
+        "Ability" => set.ability = Some(value.into()),
+        "Level" => set.level = number(value).ok_or_else(|| invalid("level", value))?,
+        "Shiny" => set.shiny = yes_no("shiny", value)?,
+        "Happiness" => set.happiness = number(value).ok_or_else(|| invalid("happiness", value))?,
+        "Pokeball" => set.pokeball = Some(value.into()),
+        "Hidden Power" => set.hidden_power_type = Some(value.into()),
+        "Dynamax Level" => {
+            set.dynamax_level = number(value).ok_or_else(|| invalid("dynamax level", value))?
+        }
+        "Gigantamax" => set.gigantamax = yes_no("gigantamax", value)?,
+        "Tera Type" => set.tera_type = Some(value.into()),
+        "EVs" => set.evs = stats(value, 0).ok_or_else(|| invalid("EVs", value))?,
+        "IVs" => set.ivs = stats(value, 31).ok_or_else(|| invalid("IVs", value))?,
+        _ => {
+            return Err(ImportError::UnrecognizedLine {
+                line,
+                text: text.into(),
+            })
+        }
+    }
+    Ok(())
+}
+
+fn number<T: FromStr>(value: &str) -> Option<T> {
+    value.parse().ok()
+}
+
+/// Parses stats like `252 Atk / 4 SpD / 252 Spe`.
+fn stats(value: &str, default: u8) -> Option<StatsTable> {
+    let mut values = [default; 6];
+    for stat in value.split('/') {
+        let (number, name) = stat.trim().split_once(' ')?;
+        let index = STAT_NAMES
+            .iter()
+            .position(|stat| stat.eq_ignore_ascii_case(name.trim()))?;
+        values[index] = number.parse().ok()?;
+    }
+    Some(StatsTable::from_array(values))
+}
+
+/// Exported form of a set, written like Showdown's `Teams.export`.
+struct Exported<'a>(&'a PokemonSet);
+
+impl Display for Exported<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let set = self.0;
+        match set.nickname() {
+            Some(name) => write!(f, "{} ({})", name, set.species)?,
+            None => f.write_str(&set.species)?,
+        }
+        match set.gender {
+            Some(Gender::Male) => f.write_str(" (M)")?,
+            Some(Gender::Female) => f.write_str(" (F)")?,
+            None => {}
+        }
+        if let Some(item) = &set.item {
+            write!(f, " @ {}", item)?;
+        }
+        writeln!(f)?;
+        if let Some(ability) = &set.ability {
+            writeln!(f, "Ability: {}", ability)?;
+        }
+        if set.level != 100 {
+            writeln!(f, "Level: {}", set.level)?;
+        }
+        if set.shiny {
+            writeln!(f, "Shiny: Yes")?;
+        }
+        if set.happiness != 255 {
+            writeln!(f, "Happiness: {}", set.happiness)?;
+        }
+        if let Some(pokeball) = &set.pokeball {
+            writeln!(f, "Pokeball: {}", pokeball)?;
+        }
+        if let Some(hidden_power_type) = &set.hidden_power_type {
+            writeln!(f, "Hidden Power: {}", hidden_power_type)?;
+        }
+        if set.dynamax_level != 10 {
+            writeln!(f, "Dynamax Level: {}", set.dynamax_level)?;
+        }
+        if set.gigantamax {
+            writeln!(f, "Gigantamax: Yes")?;
+        }
+        if let Some(tera_type) = &set.tera_type {
+            writeln!(f, "Tera Type: {}", tera_type)?;
+        }
+        write_stats(f, "EVs", set.evs, 0)?;
+        if let Some(nature) = &set.nature {
+            writeln!(f, "{} Nature", nature)?;
+        }
+        write_stats(f, "IVs", set.ivs, 31)?;
+        for name in &set.moves {
+            match name.strip_prefix("Hidden Power ") {
+                Some(kind) if !kind.starts_with('[') => writeln!(f, "- Hidden Power [{}]", kind)?,
+                _ => writeln!(f, "- {}", name)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+fn write_stats(f: &mut Formatter<'_>, label: &str, stats: StatsTable, default: u8) -> fmt::Result {
+    let values = stats.to_array();
+    let mut stats = STAT_NAMES
+        .iter()
+        .zip(values.iter())
+        .filter(|&(_, &value)| value != default);
+    if let Some((name, value)) = stats.next() {
+        write!(f, "{}: {} {}", label, value, name)?;
+        for (name, value) in stats {
+            write!(f, " / {} {}", value, name)?;
+        }
+        writeln!(f)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::ImportError;
+    use crate::message::Gender;
+    use crate::team::{PokemonSet, StatsTable, Team};
+
+    const TEAM: &str = "\
+Great Tusk @ Booster Energy
+Ability: Protosynthesis
+Tera Type: Ground
+EVs: 252 Atk / 4 Def / 252 Spe
+Jolly Nature
+- Headlong Rush
+- Ice Spinner
+- Rapid Spin
+- Knock Off
+
+Sparky (Iron Moth) @ Heavy-Duty Boots
+Ability: Quark Drive
+Shiny: Yes
+Tera Type: Fairy
+EVs: 252 SpA / 4 SpD / 252 Spe
+Timid Nature
+IVs: 0 Atk
+- Fiery Dance
+- Sludge Wave
+- Dazzling Gleam
+- U-turn
+
+Pikachu (F) @ Light Ball
+Ability: Static
+Level: 50
+Happiness: 0
+Pokeball: Cherish Ball
+Hidden Power: Ice
+Dynamax Level: 5
+Gigantamax: Yes
+- Volt Tackle
+- Hidden Power [Ice]
+";
+
+    #[test]
+    fn import_real_team() {
+        let team = Team::import(TEAM).unwrap();
+        assert_eq!(team.pokemon.len(), 3);
+        let tusk = &team.pokemon[0];
+        assert_eq!(tusk.name, "Great Tusk");
+        assert_eq!(tusk.item.as_deref(), Some("Booster Energy"));
+        assert_eq!(tusk.evs, StatsTable::from_array([0, 252, 4, 0, 0, 252]));
+        assert_eq!(
+            tusk.moves,
+            ["Headlong Rush", "Ice Spinner", "Rapid Spin", "Knock Off"]
+        );
+        let moth = &team.pokemon[1];
+        assert_eq!(moth.nickname(), Some("Sparky"));
+        assert_eq!(moth.species, "Iron Moth");
+        assert!(moth.shiny);
+        assert_eq!(moth.ivs.atk, 0);
+        assert_eq!(moth.ivs.spe, 31);
+        let pikachu = &team.pokemon[2];
+        assert_eq!(pikachu.gender, Some(Gender::Female));
+        assert_eq!(pikachu.level, 50);
+        assert_eq!(pikachu.happiness, 0);
+        assert_eq!(pikachu.hidden_power_type.as_deref(), Some("Ice"));
+        assert_eq!(pikachu.dynamax_level, 5);
+        assert!(pikachu.gigantamax);
+        assert_eq!(pikachu.moves[1], "Hidden Power Ice");
+        assert_eq!(Team::unpack(&team.pack()), Ok(team));
+    }
+
+    #[test]
+    fn export_round_trip() {
+        assert_eq!(Team::import(TEAM).unwrap().export(), TEAM);
+    }
+
+    #[test]
+    fn import_trims_whitespace() {
+        let set =
+            PokemonSet::import("\n  Chompy (Garchomp) (M)  @  Choice Scarf  \r\n  - Outrage  \n")
+                .unwrap();
+        assert_eq!(set.nickname(), Some("Chompy"));
+        assert_eq!(set.gender, Some(Gender::Male));
+        assert_eq!(set.item.as_deref(), Some("Choice Scarf"));
+        assert_eq!(set.moves, ["Outrage"]);
+    }
+
+    #[test]
+    fn import_errors() {
+        let text = "Garchomp\nAbility: Rough Skin\n\nPikachu\nEVs: 252 Atk / 4 Attack";
+        assert_eq!(
+            Team::import(text),
+            Err(ImportError::InvalidField {
+                line: 5,
+                field: "EVs",
+                value: "252 Atk / 4 Attack".into(),
+            }),
+        );
+        assert_eq!(
+            Team::import("Pikachu\nLevel: 101\nSpeed: fast"),
+            Err(ImportError::UnrecognizedLine {
+                line: 3,
+                text: "Speed: fast".into(),
+            }),
+        );
+        assert_eq!(Team::import("Pikachu\nShiny: Maybe").unwrap_err().line(), 2);
+        assert_eq!(Team::import("Chompy () @ Leftovers").unwrap_err().line(), 1);
+        assert_eq!(Team::import("\n\n"), Ok(Team::new()));
+    }
+}
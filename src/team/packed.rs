@@ -0,0 +1,375 @@
+use super::{PokemonSet, StatsTable, Team};
+use crate::message::Gender;
+use std::fmt::{self, Display, Formatter, Write};
+use std::str::FromStr;
+use thiserror::Error;
+
+/// Reason why a packed team couldn't be unpacked.
+#[derive(Clone, Debug, Eq, PartialEq, Error)]
+#[non_exhaustive]
+pub enum UnpackError {
+    #[error("Pokémon {pokemon} is missing the {field} field")]
+    MissingField { pokemon: usize, field: &'static str },
+    #[error("Pokémon {pokemon} has invalid {field} {value:?}")]
+    InvalidField {
+        pokemon: usize,
+        field: &'static str,
+        value: String,
+    },
+}
+
+const FIELDS: [&str; 11] = [
+    "name", "species", "item", "ability", "moves", "nature", "EVs", "gender", "IVs", "shiny",
+    "level",
+];
+
+impl Team {
+    /// Unpacks a team from the packed format, with sets separated by `]`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use showdown::team::Team;
+    ///
+    /// let packed = "Chompy|Garchomp|choicescarf|roughskin|earthquake,outrage|Jolly|,252,,,4,252|F||||,,,,,Ground";
+    /// let team = Team::unpack(packed).unwrap();
+    /// let garchomp = &team.pokemon[0];
+    /// assert_eq!(garchomp.nickname(), Some("Chompy"));
+    /// assert_eq!(garchomp.evs.spe, 252);
+    /// assert_eq!(garchomp.ivs.spe, 31);
+    /// assert_eq!(garchomp.tera_type.as_deref(), Some("Ground"));
+    /// assert_eq!(team.pack(), packed);
+    /// ```
+    pub fn unpack(packed: &str) -> Result<Self, UnpackError> {
+        if packed.is_empty() {
+            return Ok(Self::new());
+        }
+        packed
+            .split(']')
+            .enumerate()
+            .map(|(i, set)| unpack_set(set, i + 1))
+            .collect()
+    }
+
+    /// Packs a team into the format used by `/utm`.
+    pub fn pack(&self) -> String {
+        let mut packed = String::new();
+        for (i, set) in self.pokemon.iter().enumerate() {
+            if i != 0 {
+                packed.push(']');
+            }
+            write!(packed, "{}", Packed(set)).unwrap();
+        }
+        packed
+    }
+}
+
+impl PokemonSet {
+    /// Unpacks a single set from the packed format.
+    pub fn unpack(packed: &str) -> Result<Self, UnpackError> {
+        unpack_set(packed, 1)
+    }
+
+    /// Packs a set into the format used by `/utm`.
+    pub fn pack(&self) -> String {
+        Packed(self).to_string()
+    }
+}
+
+fn unpack_set(packed: &str, pokemon: usize) -> Result<PokemonSet, UnpackError> {
+    let mut parts = packed.splitn(12, '|');
+    let mut fields = [""; 11];
+    for (field, name) in fields.iter_mut().zip(FIELDS) {
+        *field = parts.next().ok_or(UnpackError::MissingField {
+            pokemon,
+            field: name,
+        })?;
+    }
+    let [name, species, item, ability, moves, nature, evs, gender, ivs, shiny, level] = fields;
+    let invalid = |field, value: &str| UnpackError::InvalidField {
+        pokemon,
+        field,
+        value: value.into(),
+    };
+    let mut set = PokemonSet::new(if species.is_empty() { name } else { species });
+    set.name = name.into();
+    set.item = optional(item);
+    set.ability = optional(ability);
+    set.moves = moves
+        .split(',')
+        .filter(|m| !m.is_empty())
+        .map(String::from)
+        .collect();
+    set.nature = optional(nature);
+    set.evs = stats(evs, 0).ok_or_else(|| invalid("EVs", evs))?;
+    set.gender = match gender {
+        "M" => Some(Gender::Male),
+        "F" => Some(Gender::Female),
+        // Genderless Pokémon are treated as having no gender set.
+        "" | "N" => None,
+        _ => return Err(invalid("gender", gender)),
+    };
+    set.ivs = stats(ivs, 31).ok_or_else(|| invalid("IVs", ivs))?;
+    set.shiny = match shiny {
+        "S" => true,
+        "" => false,
+        _ => return Err(invalid("shiny", shiny)),
+    };
+    set.level = number(level, 100).ok_or_else(|| invalid("level", level))?;
+    if let Some(misc) = parts.next() {
+        let mut misc = misc.splitn(6, ',');
+        let mut next = || misc.next().unwrap_or("");
+        // Showdown reads the Hidden Power type before the Poké Ball, even
+        // though its documentation lists them the other way around.
+        let happiness = next();
+        set.happiness = number(happiness, 255).ok_or_else(|| invalid("happiness", happiness))?;
+        set.hidden_power_type = optional(next());
+        set.pokeball = optional(next());
+        set.gigantamax = !next().is_empty();
+        let dynamax_level = next();
+        set.dynamax_level =
+            number(dynamax_level, 10).ok_or_else(|| invalid("dynamax level", dynamax_level))?;
+        set.tera_type = optional(next());
+    }
+    Ok(set)
+}
+
+fn optional(field: &str) -> Option<String> {
+    Some(field).filter(|f| !f.is_empty()).map(String::from)
+}
+
+fn number<T: FromStr>(field: &str, default: T) -> Option<T> {
+    if field.is_empty() {
+        Some(default)
+    } else {
+        field.parse().ok()
+    }
+}
+
+fn stats(field: &str, default: u8) -> Option<StatsTable> {
+    if field.is_empty() {
+        return Some(StatsTable::uniform(default));
+    }
+    let mut values = [default; 6];
+    let mut parts = field.split(',');
+    for value in &mut values {
+        *value = number(parts.next()?, default)?;
+    }
+    match parts.next() {
+        Some(_) => None,
+        None => Some(StatsTable::from_array(values)),
+    }
+}
+
+/// Packed form of a set, written like Showdown's `Teams.pack`.
+struct Packed<'a>(&'a PokemonSet);
+
+impl Display for Packed<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let set = self.0;
+        let species = if set.name == set.species {
+            ""
+        } else {
+            &set.species
+        };
+        write!(
+            f,
+            "{}|{}|{}|{}|{}|{}|",
+            set.name,
+            species,
+            or_empty(&set.item),
+            or_empty(&set.ability),
+            set.moves.join(","),
+            or_empty(&set.nature),
+        )?;
+        write_stats(f, set.evs, 0)?;
+        let gender = match set.gender {
+            Some(Gender::Male) => "M",
+            Some(Gender::Female) => "F",
+            None => "",
+        };
+        write!(f, "|{}|", gender)?;
+        write_stats(f, set.ivs, 31)?;
+        f.write_str(if set.shiny { "|S|" } else { "||" })?;
+        if set.level != 100 {
+            write!(f, "{}", set.level)?;
+        }
+        f.write_char('|')?;
+        if set.happiness != 255 {
+            write!(f, "{}", set.happiness)?;
+        }
+        if set.hidden_power_type.is_some()
+            || set.pokeball.is_some()
+            || set.gigantamax
+            || set.dynamax_level != 10
+            || set.tera_type.is_some()
+        {
+            write!(
+                f,
+                ",{},{},{},",
+                or_empty(&set.hidden_power_type),
+                or_empty(&set.pokeball),
+                if set.gigantamax { "G" } else { "" },
+            )?;
+            if set.dynamax_level != 10 {
+                write!(f, "{}", set.dynamax_level)?;
+            }
+            write!(f, ",{}", or_empty(&set.tera_type))?;
+        }
+        Ok(())
+    }
+}
+
+fn or_empty(field: &Option<String>) -> &str {
+    field.as_deref().unwrap_or("")
+}
+
+fn write_stats(f: &mut Formatter<'_>, stats: StatsTable, default: u8) -> fmt::Result {
+    if stats == StatsTable::uniform(default) {
+        return Ok(());
+    }
+    for (i, &value) in stats.to_array().iter().enumerate() {
+        if i != 0 {
+            f.write_char(',')?;
+        }
+        if value != default {
+            write!(f, "{}", value)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::UnpackError;
+    use crate::message::Gender;
+    use crate::team::{PokemonSet, StatsTable, Team};
+    use proptest::collection::vec;
+    use proptest::option;
+    use proptest::prelude::*;
+
+    // Teams exported from the Showdown teambuilder.
+    const TEAMS: [&str; 3] = [
+        "Great Tusk||boosterenergy|protosynthesis|headlongrush,icespinner,rapidspin,knockoff|Jolly|,252,4,,,252|||||,,,,,Ground]\
+         Kingambit||blackglasses|supremeoverlord|swordsdance,kowtowcleave,suckerpunch,ironhead|Adamant|100,252,,,,156|||||,,,,,Dark]\
+         Gholdengo||airballoon|goodasgold|makeitrain,shadowball,nastyplot,recover|Timid|,,,252,4,252||,0,,,,|||,,,,,Fighting]\
+         Dragapult||choicespecs|infiltrator|dracometeor,shadowball,flamethrower,uturn|Timid|,,,252,4,252|F||S||,,,,,Ghost]\
+         Ting-Lu||leftovers|vesselofruin|stealthrock,earthquake,ruination,whirlwind|Careful|252,,4,,252,|||||,,,,,Water]\
+         Sparky|Iron Moth|heavydutyboots|quarkdrive|fierydance,sludgewave,dazzlinggleam,uturn|Timid|,,,252,4,252|||||,,,,,Fairy",
+        "Pikachu||lightball|static|fakeout,volttackle,knockoff,surf|Jolly|4,252,,,,252|M||S|50|,,,G,,",
+        "Magearna||fairiumz|soulheart|fleurcannon,ironhead,hiddenpowerfire,calmmind|Modest|252,,,252,4,||,0,,,,|||,Fire,cherishball,,,",
+    ];
+
+    #[test]
+    fn round_trip_real_teams() {
+        for packed in TEAMS {
+            let team = Team::unpack(packed).unwrap();
+            assert_eq!(team.pack(), packed);
+        }
+    }
+
+    #[test]
+    fn unpack_fields() {
+        let team = Team::unpack(TEAMS[0]).unwrap();
+        assert_eq!(team.pokemon.len(), 6);
+        let dragapult = &team.pokemon[3];
+        assert_eq!(dragapult.nickname(), None);
+        assert_eq!(dragapult.item.as_deref(), Some("choicespecs"));
+        assert_eq!(dragapult.moves[3], "uturn");
+        assert_eq!(dragapult.gender, Some(Gender::Female));
+        assert!(dragapult.shiny);
+        assert_eq!(dragapult.level, 100);
+        assert_eq!(team.pokemon[2].ivs.atk, 0);
+        assert_eq!(team.pokemon[5].nickname(), Some("Sparky"));
+        assert_eq!(team.pokemon[5].species, "Iron Moth");
+
+        let pikachu = PokemonSet::unpack(TEAMS[1]).unwrap();
+        assert_eq!(pikachu.level, 50);
+        assert!(pikachu.gigantamax);
+        assert_eq!(pikachu.happiness, 255);
+        assert_eq!(pikachu.evs, StatsTable::from_array([4, 252, 0, 0, 0, 252]));
+
+        let magearna = PokemonSet::unpack(TEAMS[2]).unwrap();
+        assert_eq!(magearna.hidden_power_type.as_deref(), Some("Fire"));
+        assert_eq!(magearna.pokeball.as_deref(), Some("cherishball"));
+        assert_eq!(magearna.tera_type, None);
+    }
+
+    #[test]
+    fn unpack_errors() {
+        assert_eq!(Team::unpack(""), Ok(Team::new()));
+        assert_eq!(
+            Team::unpack("Pikachu||||]Pichu|||||||||||"),
+            Err(UnpackError::MissingField {
+                pokemon: 1,
+                field: "nature",
+            }),
+        );
+        assert_eq!(
+            Team::unpack("Pikachu||||||,,300,,,||||"),
+            Err(UnpackError::InvalidField {
+                pokemon: 1,
+                field: "EVs",
+                value: ",,300,,,".into(),
+            }),
+        );
+        assert_eq!(
+            Team::unpack("Pichu||||||||||]Pikachu||||||||||x1"),
+            Err(UnpackError::InvalidField {
+                pokemon: 2,
+                field: "level",
+                value: "x1".into(),
+            }),
+        );
+    }
+
+    fn name() -> impl Strategy<Value = String> {
+        "[^|,\\]]{1,12}"
+    }
+
+    fn field() -> impl Strategy<Value = Option<String>> {
+        option::of(name())
+    }
+
+    fn stats() -> impl Strategy<Value = StatsTable> {
+        any::<[u8; 6]>().prop_map(StatsTable::from_array)
+    }
+
+    prop_compose! {
+        fn pokemon_set()(
+            (name, species, item, ability, nature) in (name(), name(), field(), field(), field()),
+            moves in vec(name(), 0..4),
+            (evs, ivs) in (stats(), stats()),
+            gender in option::of(prop_oneof![Just(Gender::Male), Just(Gender::Female)]),
+            (shiny, level, happiness, gigantamax, dynamax_level) in any::<(bool, u8, u8, bool, u8)>(),
+            (hidden_power_type, pokeball, tera_type) in (field(), field(), field()),
+        ) -> PokemonSet {
+            PokemonSet {
+                name,
+                species,
+                item,
+                ability,
+                moves,
+                nature,
+                evs,
+                gender,
+                ivs,
+                shiny,
+                level,
+                happiness,
+                hidden_power_type,
+                pokeball,
+                gigantamax,
+                dynamax_level,
+                tera_type,
+            }
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn set_round_trip(set in pokemon_set()) {
+            prop_assert_eq!(PokemonSet::unpack(&set.pack()), Ok(set));
+        }
+    }
+}
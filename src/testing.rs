@@ -0,0 +1,436 @@
+//! Utilities for testing bots without connecting to Showdown.
+//!
+//! Requires `test-util` feature.
+
+use crate::message::{Kind, QueryResponse, Room, RoomsList, ServerMessage};
+use crate::server::ServerInfo;
+use crate::{to_id, Error, ErrorInner, Result, SendMessage};
+use futures_util::sink::SinkExt;
+use futures_util::stream::StreamExt;
+use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
+use std::io;
+use std::net::Ipv4Addr;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_tungstenite::tungstenite::Message as OwnedMessage;
+use tokio_tungstenite::WebSocketStream;
+use url::form_urlencoded;
+use url::Url;
+
+/// Challenge string sent by [`MockConnection::send_challstr`].
+pub const CHALLSTR: &str = "4|0123456789abcdef";
+
+/// Local websocket server pretending to be Showdown.
+///
+/// # Examples
+///
+/// ```
+/// use futures::{SinkExt, StreamExt};
+/// use showdown::message::Kind;
+/// use showdown::testing::MockServer;
+/// use showdown::{Result, SendMessage, Stream};
+///
+/// #[tokio::main]
+/// async fn main() -> Result<()> {
+///     let server = MockServer::bind().await?.room("botdev", "Bot Development");
+///     let (connection, stream) = tokio::join!(
+///         server.accept(),
+///         Stream::connect_to_url(server.url()),
+///     );
+///     let (mut connection, mut stream) = (connection?, stream?);
+///     stream.send(SendMessage::global_command("join botdev")).await?;
+///     connection.respond().await?;
+///     let message = stream.next().await.unwrap()?;
+///     assert!(matches!(message.kind(), Kind::RoomInit(_)));
+///     assert_eq!(message.room().0, "botdev");
+///     Ok(())
+/// }
+/// ```
+pub struct MockServer {
+    listener: TcpListener,
+    url: Url,
+    rooms: Vec<MockRoom>,
+}
+
+#[derive(Clone, Debug)]
+struct MockRoom {
+    id: String,
+    title: String,
+}
+
+impl MockServer {
+    /// Binds a server to a random local port.
+    pub async fn bind() -> Result<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+            .await
+            .map_err(io_error)?;
+        let port = listener.local_addr().map_err(io_error)?.port();
+        let url = Url::parse(&format!("ws://127.0.0.1:{}/showdown/websocket", port))
+            .expect("websocket URL should be valid");
+        Ok(Self {
+            listener,
+            url,
+            rooms: Vec::new(),
+        })
+    }
+
+    /// Adds a chat room that can be joined and is listed by `/cmd rooms`.
+    pub fn room(mut self, id: impl Into<String>, title: impl Into<String>) -> Self {
+        self.rooms.push(MockRoom {
+            id: id.into(),
+            title: title.into(),
+        });
+        self
+    }
+
+    /// Gets an URL which can be passed to
+    /// [`Stream::connect_to_url`](crate::Stream::connect_to_url).
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    /// Gets a server entry pointing to this server, which can be used
+    /// with [`StaticResolver`](crate::server::StaticResolver).
+    pub fn server_info(&self, id: &str) -> ServerInfo {
+        ServerInfo {
+            id: id.into(),
+            name: id.into(),
+            host: "127.0.0.1".into(),
+            port: self.url.port().unwrap(),
+            httpport: None,
+            altport: None,
+            registered: false,
+            https: false,
+        }
+    }
+
+    /// Waits for a client to connect.
+    pub async fn accept(&self) -> Result<MockConnection> {
+        let (socket, _) = self.listener.accept().await.map_err(io_error)?;
+        let socket = Error::from_ws(tokio_tungstenite::accept_async(socket).await)?;
+        Ok(MockConnection {
+            socket,
+            rooms: self.rooms.clone(),
+            username: None,
+        })
+    }
+}
+
+impl fmt::Debug for MockServer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MockServer")
+            .field("url", &self.url)
+            .field("rooms", &self.rooms)
+            .finish()
+    }
+}
+
+/// Client connected to [`MockServer`].
+pub struct MockConnection {
+    socket: WebSocketStream<TcpStream>,
+    rooms: Vec<MockRoom>,
+    username: Option<String>,
+}
+
+impl MockConnection {
+    /// Sends a raw message to the client.
+    pub async fn send(&mut self, message: impl Display) -> Result<()> {
+        Error::from_ws(
+            self.socket
+                .send(OwnedMessage::Text(message.to_string()))
+                .await,
+        )
+    }
+
+    /// Sends a [`CHALLSTR`] challenge, like Showdown does after connecting.
+    pub async fn send_challstr(&mut self) -> Result<()> {
+        self.send(format!("|challstr|{}", CHALLSTR)).await
+    }
+
+    /// Receives a message from the client.
+    ///
+    /// Returns `None` when the client disconnected.
+    pub async fn receive(&mut self) -> Result<Option<SendMessage>> {
+        while let Some(message) = self.socket.next().await {
+            match Error::from_ws(message)? {
+                OwnedMessage::Text(text) => return Ok(Some(SendMessage(text))),
+                OwnedMessage::Close(_) => break,
+                _ => {}
+            }
+        }
+        Ok(None)
+    }
+
+    /// Receives a message and asserts it's equal to `expected`.
+    ///
+    /// # Panics
+    ///
+    /// Panics when a different message was received or the client
+    /// disconnected.
+    pub async fn expect(&mut self, expected: &SendMessage) -> Result<()> {
+        let received = self.receive().await?;
+        assert_eq!(received.as_ref(), Some(expected), "unexpected message");
+        Ok(())
+    }
+
+    /// Receives a message and replies to it the way Showdown would.
+    ///
+    /// The following commands are understood:
+    ///
+    /// - `/trn` logs in, accepting any assertion,
+    /// - `/join` joins rooms added with [`MockServer::room`],
+    /// - `/leave` leaves a room,
+    /// - `/cmd rooms` lists rooms added with [`MockServer::room`].
+    ///
+    /// Other messages are received without a reply. Returns the received
+    /// message, or `None` when the client disconnected.
+    pub async fn respond(&mut self) -> Result<Option<SendMessage>> {
+        let message = match self.receive().await? {
+            Some(message) => message,
+            None => return Ok(None),
+        };
+        if let Some(reply) = self.reply(&message.0) {
+            self.send(reply).await?;
+        }
+        Ok(Some(message))
+    }
+
+    /// Replies to messages until the client disconnects.
+    ///
+    /// Returns all received messages.
+    pub async fn serve(mut self) -> Result<Vec<SendMessage>> {
+        let mut received = Vec::new();
+        while let Some(message) = self.respond().await? {
+            received.push(message);
+        }
+        Ok(received)
+    }
+
+    fn reply(&mut self, message: &str) -> Option<String> {
+        let (room, command) = message.split_once('|')?;
+        let command = command.strip_prefix('/')?;
+        let (command, arguments) = command.split_once(' ').unwrap_or((command, ""));
+        match command {
+            "trn" => {
+                let name = arguments.split(',').next().unwrap_or_default();
+                self.username = Some(name.into());
+                Some(format!("|updateuser| {}|1|1|{{}}", name))
+            }
+            "join" | "j" => Some(match self.rooms.iter().find(|r| r.id == arguments) {
+                Some(MockRoom { id, title }) => {
+                    let user = self.username.as_deref().unwrap_or("Guest 1");
+                    format!(">{}\n|init|chat\n|title|{}\n|users|1, {}", id, title, user)
+                }
+                None => format!(
+                    "|noinit|nonexistent|The room \"{}\" does not exist.",
+                    arguments,
+                ),
+            }),
+            "leave" | "part" => {
+                let room = if arguments.is_empty() {
+                    room
+                } else {
+                    arguments
+                };
+                Some(format!(">{}\n|deinit", room))
+            }
+            "cmd" if arguments == "rooms" => {
+                let official: Vec<_> = self
+                    .rooms
+                    .iter()
+                    .map(|room| Room {
+                        title: room.title.as_str().into(),
+                        desc: "".into(),
+                        user_count: 1,
+                        sub_rooms: Vec::new(),
+                    })
+                    .collect();
+                let rooms = RoomsList {
+                    official,
+                    pspl: Vec::new(),
+                    chat: Vec::new(),
+                    user_count: 1,
+                    battle_count: 0,
+                };
+                let response = Kind::QueryResponse(QueryResponse::Rooms(rooms));
+                Some(ServerMessage::new().kind(&response).to_string())
+            }
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Debug for MockConnection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MockConnection")
+            .field("username", &self.username)
+            .finish()
+    }
+}
+
+/// Local HTTP server pretending to be a login server (`action.php`).
+///
+/// Handles `getassertion` and `login` acts. Issued assertions aren't
+/// signed, [`MockConnection`] accepts any assertion.
+///
+/// # Examples
+///
+#[cfg_attr(feature = "__tls", doc = "```")]
+#[cfg_attr(not(feature = "__tls"), doc = "```ignore")]
+/// use futures::StreamExt;
+/// use showdown::login::LoginClient;
+/// use showdown::message::Kind;
+/// use showdown::testing::{MockLoginServer, MockServer};
+/// use showdown::{Result, Stream};
+///
+/// #[tokio::main]
+/// async fn main() -> Result<()> {
+///     let login_server = MockLoginServer::bind().await?.user("xfix", "hunter2");
+///     let client = LoginClient::new(login_server.url().clone());
+///     tokio::spawn(login_server.serve());
+///     let server = MockServer::bind().await?;
+///     let (connection, stream) = tokio::join!(
+///         server.accept(),
+///         Stream::connect_to_url(server.url()),
+///     );
+///     let (mut connection, mut stream) = (connection?, stream?);
+///     connection.send_challstr().await?;
+///     let message = stream.next().await.unwrap()?;
+///     if let Kind::Challenge(challenge) = message.kind() {
+///         client
+///             .login_with_password(challenge, &mut stream, "xfix", "hunter2")
+///             .await?;
+///     }
+///     let trn = connection.respond().await?.unwrap();
+///     assert!(format!("{:?}", trn).contains("trn xfix,0,"));
+///     Ok(())
+/// }
+/// ```
+pub struct MockLoginServer {
+    listener: TcpListener,
+    url: Url,
+    users: HashMap<String, String>,
+}
+
+impl MockLoginServer {
+    /// Binds a server to a random local port.
+    pub async fn bind() -> Result<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+            .await
+            .map_err(io_error)?;
+        let port = listener.local_addr().map_err(io_error)?.port();
+        let url = Url::parse(&format!("http://127.0.0.1:{}/", port))
+            .expect("login server URL should be valid");
+        Ok(Self {
+            listener,
+            url,
+            users: HashMap::new(),
+        })
+    }
+
+    /// Registers an user.
+    ///
+    /// Registered users need to provide a password, other users can log
+    /// in without one.
+    pub fn user(mut self, name: &str, password: impl Into<String>) -> Self {
+        self.users.insert(to_id(name), password.into());
+        self
+    }
+
+    /// Gets a base URL which can be passed to
+    #[cfg_attr(
+        feature = "__tls",
+        doc = " [`LoginClient::new`](crate::login::LoginClient::new)."
+    )]
+    #[cfg_attr(not(feature = "__tls"), doc = " `LoginClient::new`.")]
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    /// Handles requests forever.
+    ///
+    /// Every connection is closed after a single request.
+    pub async fn serve(self) -> Result<()> {
+        loop {
+            let (socket, _) = self.listener.accept().await.map_err(io_error)?;
+            self.handle(socket).await.map_err(io_error)?;
+        }
+    }
+
+    async fn handle(&self, socket: TcpStream) -> io::Result<()> {
+        let mut socket = BufReader::new(socket);
+        let mut content_length = 0;
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if socket.read_line(&mut line).await? == 0 {
+                return Ok(());
+            }
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                if name.eq_ignore_ascii_case("content-length") {
+                    content_length = value.trim().parse().unwrap_or(0);
+                }
+            }
+        }
+        let mut body = vec![0; content_length];
+        socket.read_exact(&mut body).await?;
+        let form: HashMap<_, _> = form_urlencoded::parse(&body).collect();
+        let field = |name| form.get(name).map_or("", |value| &**value);
+        let (status, response) = match field("act") {
+            "getassertion" => {
+                let userid = to_id(field("userid"));
+                let response = if self.users.contains_key(&userid) {
+                    ";".into()
+                } else {
+                    assertion(&userid, field("challstr"))
+                };
+                ("200 OK", response)
+            }
+            "login" => {
+                let userid = to_id(field("name"));
+                let assertion =
+                    if self.users.get(&userid).map(String::as_str) == Some(field("pass")) {
+                        assertion(&userid, field("challstr"))
+                    } else {
+                        ";;Wrong username or password.".into()
+                    };
+                let json = serde_json::json!({
+                    "actionsuccess": !assertion.starts_with(';'),
+                    "assertion": assertion,
+                });
+                ("200 OK", format!("]{}", json))
+            }
+            _ => ("400 Bad Request", "".into()),
+        };
+        let response = format!(
+            "HTTP/1.1 {}\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            response.len(),
+            response,
+        );
+        let socket = socket.get_mut();
+        socket.write_all(response.as_bytes()).await?;
+        socket.shutdown().await
+    }
+}
+
+impl fmt::Debug for MockLoginServer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MockLoginServer")
+            .field("url", &self.url)
+            .finish()
+    }
+}
+
+fn assertion(userid: &str, challstr: &str) -> String {
+    format!("{}|{}|mock", challstr, userid)
+}
+
+fn io_error(e: io::Error) -> Error {
+    Error(ErrorInner::Io(e))
+}
@@ -0,0 +1,260 @@
+//! Outgoing message throttling.
+
+use crate::SendMessage;
+use futures_util::ready;
+use futures_util::sink::Sink;
+use futures_util::stream::Stream as FuturesStream;
+use std::collections::VecDeque;
+use std::fmt::{self, Formatter};
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::time::{self, Instant, Sleep};
+
+/// Sink that delays messages to avoid Showdown's chat throttle.
+///
+/// Messages that cannot be sent yet are queued. Every room has its own
+/// queue, and queues take turns, so a burst of messages to one room
+/// doesn't delay messages sent to other rooms.
+///
+/// If the inner type is also a stream, messages can be received through
+/// this wrapper.
+///
+/// # Examples
+///
+#[cfg_attr(feature = "__tls", doc = "```no_run")]
+#[cfg_attr(not(feature = "__tls"), doc = "```compile_fail")]
+/// use futures::SinkExt;
+/// use showdown::throttle::{RateLimit, Throttle};
+/// use showdown::{Result, RoomId, SendMessage, Stream};
+///
+/// #[tokio::main]
+/// async fn main() -> Result<()> {
+///     let stream = Stream::connect("showdown").await?;
+///     let mut stream = Throttle::new(stream, RateLimit::NORMAL);
+///     for i in 0..10 {
+///         stream
+///             .send(SendMessage::chat_message(RoomId::LOBBY, i))
+///             .await?;
+///     }
+///     Ok(())
+/// }
+/// ```
+pub struct Throttle<S> {
+    inner: S,
+    limit: RateLimit,
+    tokens: u32,
+    refilled: Instant,
+    queues: VecDeque<RoomQueue>,
+    sleep: Option<Pin<Box<Sleep>>>,
+}
+
+struct RoomQueue {
+    room: String,
+    messages: VecDeque<SendMessage>,
+}
+
+impl<S> Throttle<S> {
+    pub fn new(inner: S, limit: RateLimit) -> Self {
+        Self {
+            inner,
+            limit,
+            tokens: limit.burst,
+            refilled: Instant::now(),
+            queues: VecDeque::new(),
+            sleep: None,
+        }
+    }
+
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    /// Gets the number of messages waiting to be sent.
+    pub fn queued(&self) -> usize {
+        self.queues.iter().map(|queue| queue.messages.len()).sum()
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let interval = self.limit.interval.as_nanos().max(1);
+        let new_tokens = (now - self.refilled).as_nanos() / interval;
+        if new_tokens == 0 {
+            return;
+        }
+        let tokens = u128::from(self.tokens) + new_tokens;
+        if tokens >= u128::from(self.limit.burst) {
+            self.tokens = self.limit.burst;
+            self.refilled = now;
+        } else {
+            self.tokens = tokens as u32;
+            self.refilled += self.limit.interval * new_tokens as u32;
+        }
+    }
+}
+
+impl<S: Sink<SendMessage> + Unpin> Throttle<S> {
+    /// Sends queued messages as long as the rate limit allows.
+    fn poll_queued(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+        while !self.queues.is_empty() {
+            self.refill();
+            if self.tokens == 0 {
+                let deadline = self.refilled + self.limit.interval;
+                let sleep = self
+                    .sleep
+                    .get_or_insert_with(|| Box::pin(time::sleep_until(deadline)));
+                if sleep.deadline() != deadline {
+                    sleep.as_mut().reset(deadline);
+                }
+                ready!(sleep.as_mut().poll(cx));
+                continue;
+            }
+            ready!(Pin::new(&mut self.inner).poll_ready(cx))?;
+            let mut queue = self.queues.pop_front().unwrap();
+            let message = queue.messages.pop_front().unwrap();
+            if !queue.messages.is_empty() {
+                self.queues.push_back(queue);
+            }
+            self.tokens -= 1;
+            Pin::new(&mut self.inner).start_send(message)?;
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<S> fmt::Debug for Throttle<S> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Throttle")
+            .field("limit", &self.limit)
+            .field("queued", &self.queued())
+            .finish()
+    }
+}
+
+impl<S: Sink<SendMessage> + Unpin> Sink<SendMessage> for Throttle<S> {
+    type Error = S::Error;
+
+    /// Always ready, as messages are queued.
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+        match self.poll_queued(cx) {
+            Poll::Ready(Err(e)) => Poll::Ready(Err(e)),
+            _ => Poll::Ready(Ok(())),
+        }
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, item: SendMessage) -> Result<(), S::Error> {
+        let room = item.room();
+        match self.queues.iter_mut().find(|queue| queue.room == room) {
+            Some(queue) => queue.messages.push_back(item),
+            None => self.queues.push_back(RoomQueue {
+                room: room.into(),
+                messages: VecDeque::from(vec![item]),
+            }),
+        }
+        Ok(())
+    }
+
+    /// Waits until all queued messages are sent.
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+        ready!(self.poll_queued(cx))?;
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+        ready!(self.poll_queued(cx))?;
+        Pin::new(&mut self.inner).poll_close(cx)
+    }
+}
+
+impl<S: FuturesStream + Unpin> FuturesStream for Throttle<S> {
+    type Item = S::Item;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<S::Item>> {
+        Pin::new(&mut self.inner).poll_next(cx)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+/// Token bucket rate limit.
+///
+/// Up to `burst` messages can be sent at once, after that one message
+/// can be sent every `interval`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct RateLimit {
+    burst: u32,
+    interval: Duration,
+}
+
+impl RateLimit {
+    /// Limit for regular users, who can send a message every 600
+    /// milliseconds.
+    pub const NORMAL: RateLimit = RateLimit::new(3, Duration::from_millis(600));
+
+    /// Limit for trusted users (like global voices), who can send a
+    /// message every 100 milliseconds.
+    pub const TRUSTED: RateLimit = RateLimit::new(3, Duration::from_millis(100));
+
+    /// Creates a rate limit.
+    ///
+    /// # Panics
+    ///
+    /// Panics when `burst` is 0.
+    pub const fn new(burst: u32, interval: Duration) -> Self {
+        assert!(burst != 0, "burst must be positive");
+        Self { burst, interval }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{RateLimit, Throttle};
+    use crate::{RoomId, SendMessage};
+    use futures::channel::mpsc;
+    use futures::{SinkExt, StreamExt};
+    use std::time::Duration;
+    use tokio::time::Instant;
+
+    #[tokio::test(start_paused = true)]
+    async fn queues_fairly() {
+        let (sender, mut receiver) = mpsc::unbounded();
+        let mut throttle = Throttle::new(sender, RateLimit::new(1, Duration::from_millis(30)));
+        let start = Instant::now();
+        for i in 0..3 {
+            throttle
+                .feed(SendMessage::chat_message(RoomId("a"), i))
+                .await
+                .unwrap();
+        }
+        for i in 0..2 {
+            throttle
+                .feed(SendMessage::chat_message(RoomId("b"), i))
+                .await
+                .unwrap();
+        }
+        assert_eq!(throttle.queued(), 4);
+        throttle.flush().await.unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(120));
+        assert_eq!(throttle.queued(), 0);
+        drop(throttle);
+        let sent: Vec<_> = receiver.by_ref().collect().await;
+        assert_eq!(
+            sent,
+            ["a| 0", "a| 1", "b| 0", "a| 2", "b| 1"]
+                .iter()
+                .map(|&m| SendMessage(m.into()))
+                .collect::<Vec<_>>(),
+        );
+    }
+}
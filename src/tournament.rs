@@ -0,0 +1,293 @@
+//! Tournament tracking.
+
+use crate::message::{BracketData, Kind, Message, TournamentEvent, TournamentUpdate};
+use crate::to_id;
+use std::collections::BTreeMap;
+
+/// Tournament model built from tournament messages of a room.
+///
+/// Messages of a single room need to be passed to
+/// [`update`](Self::update) in order they were received.
+///
+/// # Examples
+///
+#[cfg_attr(feature = "__tls", doc = "```no_run")]
+#[cfg_attr(not(feature = "__tls"), doc = "```compile_fail")]
+/// use futures::{SinkExt, StreamExt};
+/// use showdown::tournament::TournamentState;
+/// use showdown::{Result, RoomId, SendMessage, Stream};
+///
+/// #[tokio::main]
+/// async fn main() -> Result<()> {
+///     let mut stream = Stream::connect("showdown").await?;
+///     stream.send(SendMessage::global_command("join tours")).await?;
+///     let mut state = TournamentState::new();
+///     while let Some(message) = stream.next().await {
+///         let message = message?;
+///         if message.room().0 == "tours" {
+///             state.update(&message);
+///             if state.is_ended() {
+///                 println!("Winners: {:?}", state.results());
+///             }
+///         }
+///     }
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct TournamentState {
+    format: Option<String>,
+    generator: Option<String>,
+    player_cap: Option<u32>,
+    started: bool,
+    joined: bool,
+    ended: bool,
+    players: Vec<String>,
+    bracket: Option<BracketData>,
+    challenges: Vec<String>,
+    challenge_bys: Vec<String>,
+    challenging: Option<String>,
+    challenged: Option<String>,
+    battles: BTreeMap<String, (String, String)>,
+    results: Vec<Vec<String>>,
+}
+
+impl TournamentState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Updates the state with every tournament line of a message.
+    pub fn update(&mut self, message: &Message) {
+        for kind in message.kinds() {
+            if let Kind::Tournament(event) = kind {
+                self.handle(&event);
+            }
+        }
+    }
+
+    /// Updates the state with a single tournament event.
+    ///
+    /// A new tournament being created resets the state.
+    pub fn handle(&mut self, event: &TournamentEvent<'_>) {
+        match event {
+            TournamentEvent::Create {
+                format,
+                generator,
+                player_cap,
+            } => {
+                *self = Self {
+                    format: Some(format.to_string()),
+                    generator: Some(generator.to_string()),
+                    player_cap: *player_cap,
+                    ..Self::default()
+                }
+            }
+            TournamentEvent::Update(update) => self.apply(update),
+            TournamentEvent::Join(user) if self.player_index(user).is_none() => {
+                self.players.push(user.to_string());
+            }
+            TournamentEvent::Leave(user) => {
+                if let Some(i) = self.player_index(user) {
+                    self.players.remove(i);
+                }
+            }
+            TournamentEvent::Replace { old, new } => {
+                if let Some(i) = self.player_index(old) {
+                    self.players[i] = new.to_string();
+                }
+            }
+            TournamentEvent::Start(_) => self.started = true,
+            TournamentEvent::BattleStart {
+                player1,
+                player2,
+                room,
+            } => {
+                self.battles
+                    .insert(room.to_string(), (player1.to_string(), player2.to_string()));
+            }
+            TournamentEvent::BattleEnd { room, .. } => {
+                self.battles.remove(&**room);
+            }
+            TournamentEvent::End(end) => {
+                self.format = Some(end.format.clone());
+                self.generator = Some(end.generator.clone());
+                self.bracket = Some(end.bracket_data.clone());
+                self.results = end.results.clone();
+                self.end();
+            }
+            TournamentEvent::ForceEnd => self.end(),
+            _ => {}
+        }
+    }
+
+    fn apply(&mut self, update: &TournamentUpdate) {
+        fn set<T: Clone>(field: &mut T, value: &Option<T>) {
+            if let Some(value) = value {
+                *field = value.clone();
+            }
+        }
+        if update.format.is_some() {
+            self.format = update.format.clone();
+        }
+        if update.generator.is_some() {
+            self.generator = update.generator.clone();
+        }
+        if update.bracket_data.is_some() {
+            self.bracket = update.bracket_data.clone();
+        }
+        set(&mut self.player_cap, &update.player_cap);
+        set(&mut self.started, &update.is_started);
+        set(&mut self.joined, &update.is_joined);
+        set(&mut self.challenges, &update.challenges);
+        set(&mut self.challenge_bys, &update.challenge_bys);
+        set(&mut self.challenging, &update.challenging);
+        set(&mut self.challenged, &update.challenged);
+    }
+
+    fn end(&mut self) {
+        self.ended = true;
+        self.joined = false;
+        self.challenges.clear();
+        self.challenge_bys.clear();
+        self.challenging = None;
+        self.challenged = None;
+        self.battles.clear();
+    }
+
+    fn player_index(&self, user: &str) -> Option<usize> {
+        let id = to_id(user);
+        self.players.iter().position(|player| to_id(player) == id)
+    }
+
+    pub fn format(&self) -> Option<&str> {
+        self.format.as_deref()
+    }
+
+    /// Gets a bracket generator, like `Single Elimination`.
+    pub fn generator(&self) -> Option<&str> {
+        self.generator.as_deref()
+    }
+
+    pub fn player_cap(&self) -> Option<u32> {
+        self.player_cap
+    }
+
+    pub fn is_started(&self) -> bool {
+        self.started
+    }
+
+    /// Checks whether the user is a player in the tournament.
+    pub fn is_joined(&self) -> bool {
+        self.joined
+    }
+
+    pub fn is_ended(&self) -> bool {
+        self.ended
+    }
+
+    /// Gets players who joined, in order they joined.
+    pub fn players(&self) -> &[String] {
+        &self.players
+    }
+
+    pub fn bracket(&self) -> Option<&BracketData> {
+        self.bracket.as_ref()
+    }
+
+    /// Gets players the user can challenge.
+    pub fn challenges(&self) -> &[String] {
+        &self.challenges
+    }
+
+    /// Gets players that can challenge the user.
+    pub fn challenge_bys(&self) -> &[String] {
+        &self.challenge_bys
+    }
+
+    /// Gets a player challenged by the user.
+    pub fn challenging(&self) -> Option<&str> {
+        self.challenging.as_deref()
+    }
+
+    /// Gets a player who challenged the user.
+    pub fn challenged(&self) -> Option<&str> {
+        self.challenged.as_deref()
+    }
+
+    /// Iterates over battles in progress as room IDs and players.
+    pub fn battles(&self) -> impl Iterator<Item = (&str, &str, &str)> {
+        self.battles
+            .iter()
+            .map(|(room, (player1, player2))| (&**room, &**player1, &**player2))
+    }
+
+    /// Gets winners of an ended tournament, empty for tournaments that
+    /// were forcibly ended.
+    pub fn results(&self) -> &[Vec<String>] {
+        &self.results
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::TournamentState;
+    use crate::message::{BracketData, Message};
+
+    fn update(state: &mut TournamentState, raw: &str) {
+        state.update(&Message { raw: raw.into() });
+    }
+
+    #[test]
+    fn tracks_tournament() {
+        let mut state = TournamentState::new();
+        update(
+            &mut state,
+            ">tours\n|tournament|create|gen9ou|Single Elimination|0\n|tournament|update|{\"format\":\"gen9ou\",\"isJoined\":false}\n|tournament|updateEnd",
+        );
+        assert_eq!(state.format(), Some("gen9ou"));
+        assert_eq!(state.player_cap(), None);
+        update(
+            &mut state,
+            ">tours\n|tournament|join|Zarel\n|tournament|join|Bot\n|tournament|join|Guest\n|tournament|leave|guest\n|tournament|replace|Zarel|xfix",
+        );
+        assert_eq!(state.players(), ["xfix", "Bot"]);
+        update(
+            &mut state,
+            ">tours\n|tournament|start|2\n|tournament|update|{\"isStarted\":true,\"isJoined\":true,\"challenges\":[\"xfix\"],\"bracketData\":{\"type\":\"tree\",\"rootNode\":null}}\n|tournament|updateEnd",
+        );
+        assert!(state.is_started());
+        assert!(state.is_joined());
+        assert_eq!(state.challenges(), ["xfix"]);
+        update(
+            &mut state,
+            ">tours\n|tournament|update|{\"challenges\":[],\"challenging\":\"xfix\"}\n|tournament|updateEnd",
+        );
+        assert_eq!(state.challenging(), Some("xfix"));
+        update(
+            &mut state,
+            ">tours\n|tournament|update|{\"challenging\":null}\n|tournament|updateEnd\n|tournament|battlestart|Bot|xfix|battle-gen9ou-1",
+        );
+        assert_eq!(state.challenging(), None);
+        assert_eq!(
+            state.battles().collect::<Vec<_>>(),
+            [("battle-gen9ou-1", "Bot", "xfix")],
+        );
+        update(
+            &mut state,
+            ">tours\n|tournament|battleend|Bot|xfix|win|1,0|success|battle-gen9ou-1\n|tournament|end|{\"results\":[[\"Bot\"]],\"format\":\"gen9ou\",\"generator\":\"Single Elimination\",\"bracketData\":{\"type\":\"tree\",\"rootNode\":{\"team\":\"Bot\",\"children\":[{\"team\":\"Bot\"},{\"team\":\"xfix\"}],\"state\":\"finished\",\"result\":\"win\",\"score\":[1,0]}}}",
+        );
+        assert!(state.is_ended());
+        assert!(!state.is_joined());
+        assert_eq!(state.battles().count(), 0);
+        assert_eq!(state.results(), [["Bot"]]);
+        assert!(matches!(state.bracket(), Some(BracketData::Tree(_))));
+        update(
+            &mut state,
+            ">tours\n|tournament|create|gen9ubers|Round Robin|8",
+        );
+        assert!(!state.is_ended());
+        assert!(state.players().is_empty());
+        assert_eq!(state.player_cap(), Some(8));
+    }
+}
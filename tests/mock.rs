@@ -1,9 +1,11 @@
 use futures::{SinkExt, StreamExt};
 use showdown::message::{Kind, QueryResponse, Room};
+use showdown::reconnect::{Backoff, Event, ReconnectingStream};
 use showdown::{RoomId, SendMessage, Stream};
 use std::borrow::Cow;
 use std::error::Error;
 use std::net::Ipv4Addr;
+use std::time::Duration;
 use tokio::net::{TcpListener, TcpStream};
 use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
 use tokio_tungstenite::tungstenite::protocol::CloseFrame;
@@ -74,6 +76,50 @@ async fn test_global_command() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_challenge_commands() -> Result<(), Box<dyn Error>> {
+    let (mut socket, mut stream) = mock_connection().await?;
+    let messages = [
+        (SendMessage::upload_team("null"), "|/utm null"),
+        (
+            SendMessage::challenge("xfix", "gen9randombattle"),
+            "|/challenge xfix, gen9randombattle",
+        ),
+        (SendMessage::accept_challenge("xfix"), "|/accept xfix"),
+        (SendMessage::reject_challenge("xfix"), "|/reject xfix"),
+        (
+            SendMessage::cancel_challenge("xfix"),
+            "|/cancelchallenge xfix",
+        ),
+        (SendMessage::search("gen9ou"), "|/search gen9ou"),
+        (SendMessage::cancel_search(), "|/cancelsearch"),
+        (
+            SendMessage::join_tournament(RoomId("tours")),
+            "tours|/tour join",
+        ),
+        (
+            SendMessage::leave_tournament(RoomId("tours")),
+            "tours|/tour leave",
+        ),
+        (
+            SendMessage::tournament_challenge(RoomId("tours"), "xfix"),
+            "tours|/tour challenge xfix",
+        ),
+        (
+            SendMessage::accept_tournament_challenge(RoomId("tours")),
+            "tours|/tour acceptchallenge",
+        ),
+    ];
+    for (message, expected) in messages {
+        stream.send(message).await?;
+        assert_eq!(
+            socket.next().await.transpose()?,
+            Some(Message::Text(expected.into())),
+        );
+    }
+    Ok(())
+}
+
 #[tokio::test]
 async fn parsing_roomlist() -> Result<(), Box<dyn Error>> {
     let (mut socket, mut stream) = mock_connection().await?;
@@ -143,3 +189,96 @@ async fn parsing_close_message() -> Result<(), Box<dyn Error>> {
     assert!(stream.next().await.is_none());
     Ok(())
 }
+
+#[tokio::test]
+async fn reconnecting_rejoins_rooms() -> Result<(), Box<dyn Error>> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await?;
+    let port = listener.local_addr()?.port();
+    let uri = format!("ws://127.0.0.1:{}", port).parse()?;
+    let (socket, stream) = tokio::join!(
+        async { tokio_tungstenite::accept_async(listener.accept().await?.0).await },
+        ReconnectingStream::builder_for_url(uri)
+            .backoff(Backoff::constant(Duration::from_millis(10)))
+            .connect(),
+    );
+    let (mut socket, mut stream) = (socket?, stream?);
+    socket
+        .send(Message::Text(
+            ">botdev\n|init|chat\n|title|Bot Development\n|users|1,*xfix".into(),
+        ))
+        .await?;
+    match stream.next().await.unwrap()? {
+        Event::Message(message) => assert!(matches!(message.kind(), Kind::RoomInit(_))),
+        event => panic!("{:?}", event),
+    }
+    drop(socket);
+    let (socket, event) = tokio::join!(
+        async { tokio_tungstenite::accept_async(listener.accept().await?.0).await },
+        stream.next(),
+    );
+    let mut socket = socket?;
+    assert!(matches!(event.unwrap()?, Event::Reconnected));
+    socket.send(Message::Text("|challstr|4|abc".into())).await?;
+    match stream.next().await.unwrap()? {
+        Event::Message(message) => assert!(matches!(message.kind(), Kind::Challenge(_))),
+        event => panic!("{:?}", event),
+    }
+    assert_eq!(
+        socket.next().await.transpose()?,
+        Some(Message::Text("|/join botdev".into())),
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn reconnecting_retries_failed_attempts() -> Result<(), Box<dyn Error>> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await?;
+    let port = listener.local_addr()?.port();
+    let uri = format!("ws://127.0.0.1:{}", port).parse()?;
+    let (socket, stream) = tokio::join!(
+        async { tokio_tungstenite::accept_async(listener.accept().await?.0).await },
+        ReconnectingStream::builder_for_url(uri)
+            .backoff(Backoff::constant(Duration::from_millis(10)))
+            .connect(),
+    );
+    let mut stream = stream?;
+    drop(socket?);
+    let server = tokio::spawn(async move {
+        for _ in 0..3 {
+            drop(listener.accept().await?);
+        }
+        tokio_tungstenite::accept_async(listener.accept().await?.0).await
+    });
+    assert!(matches!(stream.next().await.unwrap()?, Event::Reconnected));
+    server.await??;
+    Ok(())
+}
+
+#[tokio::test]
+async fn reconnecting_gives_up_after_max_attempts() -> Result<(), Box<dyn Error>> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await?;
+    let port = listener.local_addr()?.port();
+    let uri = format!("ws://127.0.0.1:{}", port).parse()?;
+    let (socket, stream) = tokio::join!(
+        async { tokio_tungstenite::accept_async(listener.accept().await?.0).await },
+        ReconnectingStream::builder_for_url(uri)
+            .backoff(Backoff::constant(Duration::from_millis(10)))
+            .max_attempts(2)
+            .connect(),
+    );
+    let mut stream = stream?;
+    drop(socket?);
+    let (accepted, event) = tokio::join!(
+        async {
+            for _ in 0..2 {
+                drop(listener.accept().await?);
+            }
+            Ok::<_, std::io::Error>(())
+        },
+        stream.next(),
+    );
+    accepted?;
+    assert!(event.unwrap().is_err());
+    assert!(stream.next().await.is_none());
+    Ok(())
+}
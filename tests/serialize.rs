@@ -0,0 +1,114 @@
+#![cfg(feature = "serialize")]
+
+use serde_json::{json, Value};
+use showdown::message::{Kind, Message};
+use std::error::Error;
+
+const MESSAGES: &[&str] = &[
+    "|c:|1634571729|+xfix|Hello|world",
+    "|pm| xfix| Someone|Hi there",
+    "|J|+xfix",
+    "|N|+xfix|@xfix",
+    "|L|+xfix",
+    "|challstr|4|abcdef",
+    "|html|<b>Hello</b>",
+    "|noinit|nonexistent|The room \"nonexistent\" does not exist.",
+    ">lobby\n|init|chat\n|title|Lobby\n|users|2, xfix,+Someone\n|:|1634571729",
+    r#"|queryresponse|rooms|{"official":[{"title":"Lobby","desc":"Chat","userCount":42}],"pspl":[],"chat":[],"userCount":100,"battleCount":12}"#,
+    "|updateuser| xfix|1|1|{}",
+    ">battle-gen9ou-1\n|player|p1|xfix|1|1500",
+    ">battle-gen9ou-1\n|switch|p1a: Sparky|Pikachu, L50, M, shiny|100/100",
+    ">battle-gen9ou-1\n|move|p1a: Pikachu|Thunderbolt||[still]|[miss]",
+    ">battle-gen9ou-1\n|-damage|p2a: Garchomp|0 fnt|[from] item: Life Orb",
+    ">battle-gen9ou-1\n|-unboost|p2a: Garchomp|atk|1",
+    ">battle-gen9ou-1\n|turn|3",
+    ">battle-gen9ou-1\n|upkeep",
+    r#">battle-gen9ou-1
+|request|{"active":[{"moves":[{"move":"Protect","id":"protect","pp":0,"maxpp":16,"target":"self","disabled":"Imprison"}]}],"side":{"name":"xfix","id":"p1","pokemon":[]},"rqid":3}"#,
+    "|something new",
+];
+
+fn message(raw: &str) -> Result<Message, serde_json::Error> {
+    serde_json::from_value(Value::from(raw))
+}
+
+#[test]
+fn message_is_serialized_as_raw_text() -> Result<(), Box<dyn Error>> {
+    let raw = ">lobby\n|J|+xfix";
+    let message = message(raw)?;
+    assert_eq!(serde_json::to_value(&message)?, json!(raw));
+    assert_eq!(message.room().0, "lobby");
+    Ok(())
+}
+
+#[test]
+fn kinds_round_trip() -> Result<(), Box<dyn Error>> {
+    for raw in MESSAGES {
+        let message = message(raw)?;
+        for kind in message.kinds() {
+            let serialized = serde_json::to_string(&kind)?;
+            let deserialized: Kind<'_> = serde_json::from_str(&serialized)?;
+            assert_eq!(
+                serde_json::to_string(&deserialized)?,
+                serialized,
+                "{:?}",
+                raw,
+            );
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn chat_representation() -> Result<(), Box<dyn Error>> {
+    let message = message("|c:|1634571729|+xfix|Hello|world")?;
+    assert_eq!(
+        serde_json::to_value(message.kind())?,
+        json!({
+            "type": "chat",
+            "data": {
+                "timestamp": "1634571729",
+                "user": "+xfix",
+                "message": "Hello|world",
+            },
+        }),
+    );
+    Ok(())
+}
+
+#[test]
+fn battle_event_representation() -> Result<(), Box<dyn Error>> {
+    let message =
+        message(">battle-gen9ou-1\n|-damage|p2a: Garchomp|30/100 par|[from] item: Life Orb")?;
+    assert_eq!(
+        serde_json::to_value(message.kind())?,
+        json!({
+            "type": "battle",
+            "data": {
+                "type": "damage",
+                "data": {
+                    "pokemon": { "side": "p2", "slot": 0, "name": "Garchomp" },
+                    "hp_status": { "hp": 30, "max_hp": 100, "status": "paralysis" },
+                    "tags": "[from] item: Life Orb",
+                },
+            },
+        }),
+    );
+    Ok(())
+}
+
+#[test]
+fn deserialize_nickname_change() -> Result<(), Box<dyn Error>> {
+    let serialized = {
+        let message = message("|N|+xfix|@xfix")?;
+        serde_json::to_string(&message.kind())?
+    };
+    match serde_json::from_str(&serialized)? {
+        Kind::NicknameChange(new, old) => {
+            assert_eq!(new, "+xfix");
+            assert_eq!(old, "@xfix");
+        }
+        kind => panic!("{:?}", kind),
+    }
+    Ok(())
+}
@@ -0,0 +1,203 @@
+#![cfg(feature = "test-util")]
+
+use futures::{SinkExt, StreamExt};
+use showdown::message::{Kind, NoInitKind, QueryResponse, UpdateUser};
+use showdown::testing::{MockConnection, MockServer, CHALLSTR};
+use showdown::{RoomId, SendMessage, Stream};
+use std::error::Error;
+
+async fn connect(server: &MockServer) -> Result<(MockConnection, Stream), Box<dyn Error>> {
+    let (connection, stream) = tokio::join!(server.accept(), Stream::connect_to_url(server.url()));
+    Ok((connection?, stream?))
+}
+
+#[tokio::test]
+async fn login() -> Result<(), Box<dyn Error>> {
+    let server = MockServer::bind().await?;
+    let (mut connection, mut stream) = connect(&server).await?;
+    connection.send_challstr().await?;
+    let message = stream.next().await.unwrap()?;
+    match message.kind() {
+        Kind::Challenge(challenge) => {
+            let challstr = format!("{:?}", challenge);
+            assert!(challstr.contains(CHALLSTR), "{}", challstr);
+        }
+        kind => panic!("{:?}", kind),
+    }
+    stream
+        .send(SendMessage::global_command("trn xfix,0,assertion"))
+        .await?;
+    connection.respond().await?;
+    match stream.next().await.unwrap()?.kind() {
+        Kind::UpdateUser(UpdateUser {
+            username, named, ..
+        }) => {
+            assert_eq!(username, " xfix");
+            assert!(named);
+        }
+        kind => panic!("{:?}", kind),
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn join_and_leave() -> Result<(), Box<dyn Error>> {
+    let server = MockServer::bind().await?.room("botdev", "Bot Development");
+    let (mut connection, mut stream) = connect(&server).await?;
+    stream
+        .send(SendMessage::global_command("join botdev"))
+        .await?;
+    connection.respond().await?;
+    let message = stream.next().await.unwrap()?;
+    assert_eq!(message.room().0, "botdev");
+    match message.kind() {
+        Kind::RoomInit(init) => assert_eq!(init.title, "Bot Development"),
+        kind => panic!("{:?}", kind),
+    }
+    stream
+        .send(SendMessage::global_command("join nonexistent"))
+        .await?;
+    connection.respond().await?;
+    match stream.next().await.unwrap()?.kind() {
+        Kind::NoInit(no_init) => assert_eq!(no_init.kind, NoInitKind::Nonexistent),
+        kind => panic!("{:?}", kind),
+    }
+    stream
+        .send(SendMessage::chat_command(RoomId("botdev"), "leave"))
+        .await?;
+    connection.respond().await?;
+    let message = stream.next().await.unwrap()?;
+    assert_eq!(message.room().0, "botdev");
+    Ok(())
+}
+
+#[tokio::test]
+async fn room_list() -> Result<(), Box<dyn Error>> {
+    let server = MockServer::bind()
+        .await?
+        .room("lobby", "Lobby")
+        .room("botdev", "Bot Development");
+    let (mut connection, mut stream) = connect(&server).await?;
+    stream
+        .send(SendMessage::global_command("cmd rooms"))
+        .await?;
+    connection.respond().await?;
+    match stream.next().await.unwrap()?.kind() {
+        Kind::QueryResponse(QueryResponse::Rooms(rooms)) => {
+            let titles: Vec<_> = rooms.iter().map(|room| &room.title).collect();
+            assert_eq!(titles, ["Lobby", "Bot Development"]);
+        }
+        kind => panic!("{:?}", kind),
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn serve_records_messages() -> Result<(), Box<dyn Error>> {
+    let server = MockServer::bind().await?;
+    let (mut connection, mut stream) = connect(&server).await?;
+    stream
+        .send(SendMessage::chat_message(RoomId::LOBBY, "Hello"))
+        .await?;
+    connection
+        .expect(&SendMessage::chat_message(RoomId::LOBBY, "Hello"))
+        .await?;
+    stream
+        .send(SendMessage::chat_message(RoomId::LOBBY, "Bye"))
+        .await?;
+    stream.close().await?;
+    assert_eq!(
+        connection.serve().await?,
+        [SendMessage::chat_message(RoomId::LOBBY, "Bye")],
+    );
+    Ok(())
+}
+
+#[cfg(feature = "__tls")]
+#[tokio::test]
+async fn resolve_named_server() -> Result<(), Box<dyn Error>> {
+    use showdown::server::{CachingResolver, StaticResolver};
+
+    let server = MockServer::bind().await?;
+    let resolver: StaticResolver = vec![server.server_info("local")].into_iter().collect();
+    let (connection, stream) = tokio::join!(
+        server.accept(),
+        Stream::builder("local")
+            .resolver(CachingResolver::new(resolver))
+            .connect(),
+    );
+    let (mut connection, mut stream) = (connection?, stream?);
+    connection.send_challstr().await?;
+    let message = stream.next().await.unwrap()?;
+    assert!(matches!(message.kind(), Kind::Challenge(_)));
+    Ok(())
+}
+
+#[cfg(feature = "__tls")]
+mod login {
+    use super::connect;
+    use futures::StreamExt;
+    use showdown::login::LoginClient;
+    use showdown::message::{Challenge, Kind};
+    use showdown::testing::{MockLoginServer, MockServer};
+    use std::error::Error;
+
+    async fn challenge() -> Result<(LoginClient, Challenge<'static>), Box<dyn Error>> {
+        let login_server = MockLoginServer::bind().await?.user("xfix", "hunter2");
+        let client = LoginClient::new(login_server.url().clone());
+        tokio::spawn(login_server.serve());
+        let server = MockServer::bind().await?;
+        let (mut connection, mut stream) = connect(&server).await?;
+        connection.send_challstr().await?;
+        match stream.next().await.unwrap()?.kind() {
+            Kind::Challenge(challenge) => Ok((client, challenge.into_owned())),
+            kind => panic!("{:?}", kind),
+        }
+    }
+
+    #[tokio::test]
+    async fn unregistered_assertion() -> Result<(), Box<dyn Error>> {
+        let (client, challenge) = challenge().await?;
+        let assertion = client.assertion(&challenge, "Someone").await?.unwrap();
+        assert!(assertion.contains("someone"), "{}", assertion);
+        assert_eq!(client.assertion(&challenge, "xfix").await?, None);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn password_assertion() -> Result<(), Box<dyn Error>> {
+        let (client, challenge) = challenge().await?;
+        let assertion = client
+            .password_assertion(&challenge, "xfix", "hunter2")
+            .await?;
+        assert!(assertion.contains("xfix"), "{}", assertion);
+        let error = client
+            .password_assertion(&challenge, "xfix", "hunter3")
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("Wrong"), "{}", error);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn login_sends_trn() -> Result<(), Box<dyn Error>> {
+        let login_server = MockLoginServer::bind().await?;
+        let client = LoginClient::new(login_server.url().clone());
+        tokio::spawn(login_server.serve());
+        let server = MockServer::bind().await?;
+        let (mut connection, mut stream) = connect(&server).await?;
+        connection.send_challstr().await?;
+        let message = stream.next().await.unwrap()?;
+        let challenge = match message.kind() {
+            Kind::Challenge(challenge) => challenge,
+            kind => panic!("{:?}", kind),
+        };
+        assert!(client
+            .login(challenge, &mut stream, "Someone")
+            .await?
+            .is_none());
+        let trn = connection.respond().await?.unwrap();
+        assert!(format!("{:?}", trn).contains("trn Someone,0,"), "{:?}", trn);
+        Ok(())
+    }
+}